@@ -1,12 +1,27 @@
 pub use esc_client_base::identity::TokenConfig;
 
+pub use esc_client_base::client::RefreshTokenAuthorizer;
+pub use esc_client_base::client::StaticTokenAuthorizer;
+pub use esc_client_base::client::{RefreshTokenSource, StaticTokenSource, TokenSource};
+pub use esc_client_base::errors::BuildError;
+#[cfg(feature = "tracing")]
+pub use esc_client_base::requests::TracingObserver;
+pub use esc_client_base::requests::{FailureEvent, Redactor, RequestEvent, ResponseEvent};
 pub use esc_client_base::Authorization;
 pub use esc_client_base::Client;
+pub use esc_client_base::ClientBuilder;
 pub use esc_client_base::Error;
+pub use esc_client_base::ErrorKind;
+pub use esc_client_base::HttpConfig;
 pub use esc_client_base::RequestObserver;
 pub use esc_client_base::RequestSender;
 pub use esc_client_base::Result;
+pub use esc_client_base::RetryPolicy;
 pub use esc_client_base::Token;
+pub use esc_client_base::Transport;
+pub use wait::{WaitError, WaitOptions};
+
+mod wait;
 
 pub mod access {
     pub use esc_client_generated::access::*;
@@ -17,6 +32,7 @@ pub mod audit {
 }
 
 pub mod infra {
+    pub use crate::wait::{wait_for_network, wait_for_peering};
     pub use esc_client_generated::infra::*;
 }
 
@@ -25,6 +41,7 @@ pub mod integrate {
 }
 
 pub mod mesdb {
+    pub use crate::wait::{wait_for_backup, wait_for_cluster};
     pub use esc_client_generated::mesdb::*;
 }
 
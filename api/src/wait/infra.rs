@@ -0,0 +1,89 @@
+use esc_client_base::Client;
+use esc_client_generated::infra::{
+    self, Network, NetworkId, NetworkStatus, Peering, PeeringId, PeeringStatus,
+};
+use esc_client_generated::resources::{OrganizationId, ProjectId};
+
+use super::{wait_until, WaitError, WaitOptions, Watched};
+
+impl Watched for Network {
+    fn status_name(&self) -> String {
+        self.status.to_string()
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(self.status, NetworkStatus::Defunct | NetworkStatus::Deleted)
+    }
+}
+
+impl Watched for Peering {
+    fn status_name(&self) -> String {
+        self.status.to_string()
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(self.status, PeeringStatus::Defunct | PeeringStatus::Deleted)
+    }
+}
+
+/// Waits until the predicate accepts the network. Fails if the network gets
+/// defunct or deleted first.
+pub async fn wait_for_network<P: FnMut(&Network) -> bool>(
+    client: &Client,
+    organization_id: OrganizationId,
+    project_id: ProjectId,
+    network_id: NetworkId,
+    options: &WaitOptions,
+    predicate: P,
+) -> Result<Network, WaitError> {
+    wait_until(
+        client,
+        format!("Network {}", network_id),
+        options,
+        |client| {
+            let (organization_id, project_id, network_id) = (
+                organization_id.clone(),
+                project_id.clone(),
+                network_id.clone(),
+            );
+            async move {
+                infra::get_network(&client, organization_id, project_id, network_id)
+                    .await
+                    .map(|resp| resp.network)
+            }
+        },
+        predicate,
+    )
+    .await
+}
+
+/// Waits until the predicate accepts the peering. Fails if the peering gets
+/// defunct or deleted first.
+pub async fn wait_for_peering<P: FnMut(&Peering) -> bool>(
+    client: &Client,
+    organization_id: OrganizationId,
+    project_id: ProjectId,
+    peering_id: PeeringId,
+    options: &WaitOptions,
+    predicate: P,
+) -> Result<Peering, WaitError> {
+    wait_until(
+        client,
+        format!("Peering {}", peering_id),
+        options,
+        |client| {
+            let (organization_id, project_id, peering_id) = (
+                organization_id.clone(),
+                project_id.clone(),
+                peering_id.clone(),
+            );
+            async move {
+                infra::get_peering(&client, organization_id, project_id, peering_id)
+                    .await
+                    .map(|resp| resp.peering)
+            }
+        },
+        predicate,
+    )
+    .await
+}
@@ -0,0 +1,105 @@
+use esc_client_base::Client;
+use esc_client_generated::mesdb::{
+    self, Backup, BackupId, BackupStatus, Cluster, ClusterId, ClusterStatus,
+};
+use esc_client_generated::resources::{OrganizationId, ProjectId};
+
+use super::{wait_until, WaitError, WaitOptions, Watched};
+
+impl Watched for Cluster {
+    fn status_name(&self) -> String {
+        self.status.to_string()
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(
+            self.status,
+            ClusterStatus::Defunct | ClusterStatus::Inconsistent | ClusterStatus::Deleted
+        )
+    }
+}
+
+impl Watched for Backup {
+    fn status_name(&self) -> String {
+        self.status.to_string()
+    }
+
+    fn has_failed(&self) -> bool {
+        matches!(self.status, BackupStatus::Defunct | BackupStatus::Deleted)
+    }
+}
+
+/// Waits until the predicate accepts the cluster. Fails if the cluster gets
+/// defunct, inconsistent or deleted first.
+///
+/// ```no_run
+/// # async fn example(client: esc_api::Client, org_id: esc_api::OrgId, project_id: esc_api::ProjectId, cluster_id: esc_api::ClusterId) -> Result<(), esc_api::WaitError> {
+/// use esc_api::mesdb::{self, ClusterStatus};
+///
+/// let options = esc_api::WaitOptions::default();
+/// mesdb::wait_for_cluster(&client, org_id, project_id, cluster_id, &options, |cluster| {
+///     cluster.status == ClusterStatus::Available
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn wait_for_cluster<P: FnMut(&Cluster) -> bool>(
+    client: &Client,
+    organization_id: OrganizationId,
+    project_id: ProjectId,
+    cluster_id: ClusterId,
+    options: &WaitOptions,
+    predicate: P,
+) -> Result<Cluster, WaitError> {
+    wait_until(
+        client,
+        format!("Cluster {}", cluster_id),
+        options,
+        |client| {
+            let (organization_id, project_id, cluster_id) = (
+                organization_id.clone(),
+                project_id.clone(),
+                cluster_id.clone(),
+            );
+            async move {
+                mesdb::get_cluster(&client, organization_id, project_id, cluster_id)
+                    .await
+                    .map(|resp| resp.cluster)
+            }
+        },
+        predicate,
+    )
+    .await
+}
+
+/// Waits until the predicate accepts the backup. Fails if the backup gets
+/// defunct or deleted first.
+pub async fn wait_for_backup<P: FnMut(&Backup) -> bool>(
+    client: &Client,
+    organization_id: OrganizationId,
+    project_id: ProjectId,
+    backup_id: BackupId,
+    options: &WaitOptions,
+    predicate: P,
+) -> Result<Backup, WaitError> {
+    wait_until(
+        client,
+        format!("Backup {}", backup_id),
+        options,
+        |client| {
+            let (organization_id, project_id, backup_id) = (
+                organization_id.clone(),
+                project_id.clone(),
+                backup_id.clone(),
+            );
+            async move {
+                mesdb::get_backup(&client, organization_id, project_id, backup_id)
+                    .await
+                    .map(|resp| resp.backup)
+            }
+        },
+        predicate,
+    )
+    .await
+}
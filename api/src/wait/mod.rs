@@ -0,0 +1,172 @@
+//! Waiting for resources to reach a state, for instance for a cluster to be
+//! available after it was created. Every operation which changes a resource
+//! returns as soon as the API has accepted the change, so these poll the
+//! resource until it's done.
+
+mod infra;
+mod mesdb;
+
+pub use infra::{wait_for_network, wait_for_peering};
+pub use mesdb::{wait_for_backup, wait_for_cluster};
+
+use std::future::Future;
+use std::time::Duration;
+
+use esc_client_base::Client;
+use tokio::time::Instant;
+
+/// How long to wait for, and how often to look at the resource meanwhile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaitOptions {
+    /// Time after which waiting fails, counted from the first poll.
+    pub timeout: Duration,
+    /// Delay between the first two polls. It grows by half after each poll.
+    pub initial_interval: Duration,
+    /// Upper bound for the delay between two polls.
+    pub max_interval: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30 * 60),
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn interval(mut self, initial_interval: Duration, max_interval: Duration) -> Self {
+        self.initial_interval = initial_interval;
+        self.max_interval = max_interval;
+        self
+    }
+}
+
+/// Why waiting for a resource failed.
+pub enum WaitError {
+    /// The resource went into a state it won't come out of by itself, such
+    /// as defunct.
+    Failed { resource: String, status: String },
+    /// The resource didn't reach the expected state before the deadline.
+    TimedOut {
+        resource: String,
+        status: String,
+        waited: Duration,
+    },
+    /// Looking at the resource failed, for instance because it's gone.
+    Api(esc_client_base::Error),
+}
+
+impl WaitError {
+    /// True if the resource doesn't exist (anymore).
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, WaitError::Api(err) if err.is_not_found())
+    }
+}
+
+impl std::fmt::Display for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WaitError::Failed { resource, status } => {
+                write!(f, "{} is {}, giving up", resource, status)
+            }
+            WaitError::TimedOut {
+                resource,
+                status,
+                waited,
+            } => write!(
+                f,
+                "{} is still {} after waiting for {} seconds",
+                resource,
+                status,
+                waited.as_secs()
+            ),
+            WaitError::Api(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::fmt::Debug for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WaitError::Api(err) => write!(f, "{:?}", err),
+            _ => std::fmt::Display::fmt(self, f),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WaitError::Api(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A resource whose status can be waited on.
+pub(crate) trait Watched {
+    fn status_name(&self) -> String;
+
+    /// True if the resource won't ever get to a state which is waited for,
+    /// unless that state is this one.
+    fn has_failed(&self) -> bool;
+}
+
+/// Gets the resource until the predicate accepts it, backing off between
+/// polls. The predicate sees every state, so it can also report progress.
+pub(crate) async fn wait_until<T, G, F, P>(
+    client: &Client,
+    resource: String,
+    options: &WaitOptions,
+    mut get: G,
+    mut predicate: P,
+) -> Result<T, WaitError>
+where
+    T: Watched,
+    G: FnMut(Client) -> F,
+    F: Future<Output = esc_client_base::Result<T>>,
+    P: FnMut(&T) -> bool,
+{
+    let started = Instant::now();
+    let deadline = started + options.timeout;
+    let mut interval = options.initial_interval;
+    loop {
+        // A poll mustn't hang on past the deadline either.
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let limit = match client.sender.timeout {
+            Some(timeout) => timeout.min(remaining),
+            None => remaining,
+        };
+        let current = get(client.with_timeout(limit.max(Duration::from_secs(1))))
+            .await
+            .map_err(WaitError::Api)?;
+        if predicate(&current) {
+            return Ok(current);
+        }
+        if current.has_failed() {
+            return Err(WaitError::Failed {
+                resource,
+                status: current.status_name(),
+            });
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(WaitError::TimedOut {
+                resource,
+                status: current.status_name(),
+                waited: now - started,
+            });
+        }
+        tokio::time::sleep(interval.min(deadline - now)).await;
+        interval = (interval * 3 / 2).min(options.max_interval);
+    }
+}
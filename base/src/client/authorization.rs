@@ -1,12 +1,17 @@
-use crate::identity::Token;
+use std::sync::{Arc, RwLock};
+
+use crate::identity::operations;
+use crate::identity::{Token, TokenConfig};
+use crate::transport::Transport;
 
 /// Creates value used for "Authorization" header, and refreshes it if needed
+#[async_trait]
 pub trait Authorization {
     /// Gets the authorization header
     fn authorization_header(&self) -> String;
 
     /// Refreshes the authorization. If successful, this returns true.
-    fn refresh(&mut self) -> bool;
+    async fn refresh(&self) -> bool;
 }
 
 /// This authorizes uses a static token and doesn't do anything if the auth
@@ -15,12 +20,71 @@ pub struct StaticTokenAuthorizer {
     pub token: Token,
 }
 
+#[async_trait]
 impl Authorization for StaticTokenAuthorizer {
     fn authorization_header(&self) -> String {
         self.token.authorization_header()
     }
 
-    fn refresh(&mut self) -> bool {
+    async fn refresh(&self) -> bool {
         false
     }
 }
+
+/// Authorizes using an access token which is swapped for a new one, using the
+/// given refresh token, whenever the API rejects it.
+pub struct RefreshTokenAuthorizer {
+    transport: Arc<dyn Transport + Send + Sync>,
+    config: TokenConfig,
+    refresh_token: String,
+    token: RwLock<Token>,
+}
+
+impl RefreshTokenAuthorizer {
+    pub fn new(
+        transport: Arc<dyn Transport + Send + Sync>,
+        config: TokenConfig,
+        refresh_token: String,
+        token: Token,
+    ) -> Self {
+        Self {
+            transport,
+            config,
+            refresh_token,
+            token: RwLock::new(token),
+        }
+    }
+}
+
+#[async_trait]
+impl Authorization for RefreshTokenAuthorizer {
+    fn authorization_header(&self) -> String {
+        self.token
+            .read()
+            .expect("token lock was poisoned")
+            .authorization_header()
+    }
+
+    async fn refresh(&self) -> bool {
+        // There's nobody to answer an OTP prompt in the middle of a request,
+        // so if identity issues an MFA challenge the refresh simply fails.
+        let result = operations::refresh(
+            self.transport.as_ref(),
+            &self.config,
+            &self.refresh_token,
+            None,
+        )
+        .await;
+        match result {
+            Ok(new_token) => {
+                let mut token = self.token.write().expect("token lock was poisoned");
+                *token = token.clone().update_access_token(new_token.access_token());
+                true
+            }
+            Err(err) => {
+                warn!("Could not refresh access token: {}", err);
+                false
+            }
+        }
+    }
+}
@@ -1,8 +1,172 @@
-use crate::identity::Token;
-use crate::sender::RequestSender;
+use std::sync::Arc;
+use std::time::Duration;
+
 use super::client::Client;
-use super::authorization::StaticTokenAuthorizer;
+use super::token_source::{RefreshTokenSource, StaticTokenSource, TokenSource};
+use crate::errors::BuildError;
+use crate::identity::operations::OtpPrompt;
+use crate::identity::{Token, TokenConfig};
+use crate::requests::{HttpConfig, RequestObserver, RequestSender, RetryPolicy};
+use crate::transport::Transport;
+
+/// Base URL of the Event Store Cloud API.
+pub const DEFAULT_BASE_URL: &str = "https://api.eventstore.cloud";
+
+/// Configures a client in one place: where the API is, where its tokens come
+/// from, and how requests are sent.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), esc_client_base::errors::BuildError> {
+/// use esc_client_base::client::ClientBuilder;
+///
+/// let client = ClientBuilder::new()
+///     .refresh_token("my-refresh-token")
+///     .retries(3)
+///     .timeout(std::time::Duration::from_secs(30))
+///     .user_agent("my-tool/1.0")
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+    base_url: String,
+    token_config: TokenConfig,
+    token_source: Option<Arc<dyn TokenSource + Send + Sync>>,
+    observer: Option<Arc<dyn RequestObserver + Send + Sync>>,
+    retry_policy: RetryPolicy,
+    http_config: HttpConfig,
+    transport: Option<Arc<dyn Transport + Send + Sync>>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            token_config: TokenConfig::default(),
+            token_source: None,
+            observer: None,
+            retry_policy: RetryPolicy::none(),
+            http_config: HttpConfig::default(),
+            transport: None,
+        }
+    }
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Where identity is and how tokens are checked. Defaults to Event
+    /// Store Cloud's identity.
+    pub fn token_config(mut self, token_config: TokenConfig) -> Self {
+        self.token_config = token_config;
+        self
+    }
+
+    pub fn token_source<S: TokenSource + Send + Sync + 'static>(mut self, source: S) -> Self {
+        self.token_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Authorizes with the given token and nothing else.
+    pub fn static_token(self, token: Token) -> Self {
+        self.token_source(StaticTokenSource(token))
+    }
+
+    /// Authorizes with access tokens obtained for the given refresh token.
+    pub fn refresh_token(self, refresh_token: &str) -> Self {
+        self.token_source(RefreshTokenSource {
+            refresh_token: refresh_token.to_string(),
+            otp_prompt: None,
+        })
+    }
+
+    /// Like `refresh_token`, asking for a one time password when identity
+    /// wants one.
+    pub fn refresh_token_with_otp(self, refresh_token: &str, otp_prompt: OtpPrompt) -> Self {
+        self.token_source(RefreshTokenSource {
+            refresh_token: refresh_token.to_string(),
+            otp_prompt: Some(otp_prompt),
+        })
+    }
+
+    pub fn observer(mut self, observer: Arc<dyn RequestObserver + Send + Sync>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Retries requests which fail for transient reasons up to `retries`
+    /// times.
+    pub fn retries(self, retries: u32) -> Self {
+        self.retry_policy(RetryPolicy::with_retries(retries))
+    }
+
+    /// Replaces all the HTTP settings at once. The ones set before, such as
+    /// the timeouts, are lost.
+    pub fn http_config(mut self, http_config: HttpConfig) -> Self {
+        self.http_config = http_config;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http_config = self.http_config.timeout(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.http_config = self.http_config.connect_timeout(connect_timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.http_config = self.http_config.user_agent(user_agent.to_string());
+        self
+    }
+
+    /// Sends requests through the given transport instead of an HTTP client
+    /// built from the HTTP settings, which are ignored then.
+    pub fn transport(mut self, transport: Arc<dyn Transport + Send + Sync>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    pub fn get_http_config(&self) -> &HttpConfig {
+        &self.http_config
+    }
 
-fn static_token_client(token: Token) -> Client {
-    let authorizer = StaticTokenAut
-}
\ No newline at end of file
+    /// Creates the client, which includes getting an access token from the
+    /// token source.
+    pub async fn build(self) -> Result<Client, BuildError> {
+        let token_source = self
+            .token_source
+            .ok_or_else(|| BuildError::new("no token source was configured"))?;
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(self.http_config.build_client().map_err(|err| {
+                BuildError::new("could not create the HTTP client").source(Box::new(err))
+            })?),
+        };
+        let authorization = token_source
+            .authorization(transport.clone(), &self.token_config)
+            .await
+            .map_err(|err| BuildError::new("could not get an access token").source(err))?;
+        Ok(Client {
+            authorization,
+            base_url: self.base_url,
+            sender: RequestSender::new(transport, self.observer).retry_policy(self.retry_policy),
+        })
+    }
+}
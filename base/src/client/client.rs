@@ -1,8 +1,10 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::authorization::Authorization;
+use crate::errors::EscError;
 use crate::requests::RequestSender;
 
 #[derive(Clone)]
@@ -13,6 +15,16 @@ pub struct Client {
 }
 
 impl Client {
+    /// Returns a copy of this client whose requests are abandoned after the
+    /// given amount of time, overriding any time limit set before.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let mut client = self.clone();
+        client.sender.timeout = Some(timeout);
+        client
+    }
+
+    /// Sends a request to the API. If the API rejects the current
+    /// authorization, it's refreshed and the request is replayed once.
     pub async fn send_request<B: Serialize + ?Sized, R: DeserializeOwned>(
         &self,
         method: reqwest::Method,
@@ -26,14 +38,35 @@ impl Client {
             format!("{}/{}", self.base_url, relative_url)
         };
 
-        self.sender
-            .send_request(
+        let result = self
+            .sender
+            .send(
                 &self.authorization.authorization_header(),
-                method,
-                url,
+                method.clone(),
+                url.clone(),
                 body,
-                use_return_value,
             )
-            .await
+            .await;
+
+        let response = match result {
+            Err(EscError::ApiResponse(err))
+                if err.status_code == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                if !self.authorization.refresh().await {
+                    return Err(EscError::ApiResponse(err));
+                }
+                debug!("Authorization refreshed, retrying {} {}", method, url);
+                self.sender
+                    .send(
+                        &self.authorization.authorization_header(),
+                        method,
+                        url,
+                        body,
+                    )
+                    .await?
+            }
+            result => result?,
+        };
+        response.decode(use_return_value)
     }
 }
@@ -1,7 +1,12 @@
 #![allow(clippy::module_inception)]
 mod authorization;
+mod builder;
 mod client;
+mod token_source;
 
 pub use authorization::Authorization;
+pub use authorization::RefreshTokenAuthorizer;
 pub use authorization::StaticTokenAuthorizer;
+pub use builder::{ClientBuilder, DEFAULT_BASE_URL};
 pub use client::Client;
+pub use token_source::{RefreshTokenSource, StaticTokenSource, TokenSource, TokenSourceError};
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use super::authorization::{Authorization, RefreshTokenAuthorizer, StaticTokenAuthorizer};
+use crate::identity::operations::{self, OtpPrompt};
+use crate::identity::{Token, TokenConfig};
+use crate::transport::Transport;
+
+pub type TokenSourceError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Where a client gets the access tokens it authorizes requests with.
+#[async_trait]
+pub trait TokenSource {
+    /// Creates the authorization of a new client. The transport is the one
+    /// the client uses, for sources which need to reach identity.
+    async fn authorization(
+        &self,
+        transport: Arc<dyn Transport + Send + Sync>,
+        config: &TokenConfig,
+    ) -> Result<Arc<dyn Authorization + Send + Sync>, TokenSourceError>;
+}
+
+/// Always uses the same token, which is never refreshed.
+pub struct StaticTokenSource(pub Token);
+
+#[async_trait]
+impl TokenSource for StaticTokenSource {
+    async fn authorization(
+        &self,
+        _transport: Arc<dyn Transport + Send + Sync>,
+        _config: &TokenConfig,
+    ) -> Result<Arc<dyn Authorization + Send + Sync>, TokenSourceError> {
+        Ok(Arc::new(StaticTokenAuthorizer {
+            token: self.0.clone(),
+        }))
+    }
+}
+
+/// Gets an access token for a refresh token, and a new one whenever the
+/// API rejects it.
+pub struct RefreshTokenSource {
+    pub refresh_token: String,
+    /// Asks for a one time password if identity wants one. Without it, a
+    /// multi-factor challenge fails the refresh.
+    pub otp_prompt: Option<OtpPrompt>,
+}
+
+#[async_trait]
+impl TokenSource for RefreshTokenSource {
+    async fn authorization(
+        &self,
+        transport: Arc<dyn Transport + Send + Sync>,
+        config: &TokenConfig,
+    ) -> Result<Arc<dyn Authorization + Send + Sync>, TokenSourceError> {
+        let token = operations::refresh(
+            transport.as_ref(),
+            config,
+            &self.refresh_token,
+            self.otp_prompt,
+        )
+        .await?;
+        Ok(Arc::new(RefreshTokenAuthorizer::new(
+            transport,
+            config.clone(),
+            self.refresh_token.clone(),
+            token,
+        )))
+    }
+}
@@ -1,5 +1,7 @@
+use super::error_kind::ErrorKind;
 use super::problem_details::ProblemDetails;
 use reqwest::StatusCode;
+use std::collections::HashMap;
 
 /// Represents an error reported by an API operation
 #[derive(Clone, PartialEq, Eq)]
@@ -8,6 +10,17 @@ pub struct ApiResponseError {
     pub problem_details: ProblemDetails,
 }
 
+impl ApiResponseError {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::from_status(self.status_code)
+    }
+
+    /// The fields the API rejected, keyed by name, if it said which.
+    pub fn fields(&self) -> Option<&HashMap<String, String>> {
+        self.problem_details.fields.as_ref()
+    }
+}
+
 impl std::fmt::Display for ApiResponseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
         write!(f, "{}: {}", self.status_code, self.problem_details)
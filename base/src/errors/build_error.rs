@@ -0,0 +1,47 @@
+/// Represents a client which couldn't be built, because its HTTP client
+/// couldn't be set up or it couldn't get hold of an access token.
+pub struct BuildError {
+    pub message: String,
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl BuildError {
+    pub fn new(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn source(mut self, source: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}: {}", self.message, source),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::fmt::Debug for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}\nCaused by:\n\t{:?}", self.message, source),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(err) => Some(err.as_ref()),
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use reqwest::StatusCode;
+
+/// Broad category of an [`EscError`](super::EscError), derived from the
+/// status code the API answered with. Lets callers tell "not found" apart
+/// from "already exists" or "the server is down" without matching on status
+/// codes themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The request was malformed or failed validation (400 or 422). The
+    /// offending fields, if any, are in `ProblemDetails.fields`.
+    Validation,
+    /// The credentials are missing or were rejected (401).
+    Unauthorized,
+    /// The credentials are valid but don't grant access (403).
+    Forbidden,
+    /// The resource doesn't exist (404 or 410).
+    NotFound,
+    /// The request conflicts with the resource's current state, for instance
+    /// because it already exists (409).
+    Conflict,
+    /// Too many requests were sent (429).
+    RateLimited,
+    /// The API failed to handle the request (5xx).
+    ServerError,
+    /// Any other status code the API answered with.
+    OtherStatus,
+    /// The request was abandoned because it took too long.
+    Timeout,
+    /// The API couldn't be reached, or its answer couldn't be read.
+    Transport,
+}
+
+impl ErrorKind {
+    pub fn from_status(status_code: StatusCode) -> Self {
+        match status_code {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => ErrorKind::Validation,
+            StatusCode::UNAUTHORIZED => ErrorKind::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorKind::Forbidden,
+            StatusCode::NOT_FOUND | StatusCode::GONE => ErrorKind::NotFound,
+            StatusCode::CONFLICT => ErrorKind::Conflict,
+            StatusCode::TOO_MANY_REQUESTS => ErrorKind::RateLimited,
+            status_code if status_code.is_server_error() => ErrorKind::ServerError,
+            _ => ErrorKind::OtherStatus,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        let name = match self {
+            ErrorKind::Validation => "validation",
+            ErrorKind::Unauthorized => "unauthorized",
+            ErrorKind::Forbidden => "forbidden",
+            ErrorKind::NotFound => "not found",
+            ErrorKind::Conflict => "conflict",
+            ErrorKind::RateLimited => "rate limited",
+            ErrorKind::ServerError => "server error",
+            ErrorKind::OtherStatus => "unexpected status",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Transport => "transport",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+
 use super::api_response_error::ApiResponseError;
 use super::communication_error::CommunicationError;
+use super::error_kind::ErrorKind;
+use super::timeout_error::TimeoutError;
 
 /// Represents any error that can come back from an API operation
 pub enum EscError {
     ApiResponse(Box<ApiResponseError>),
+    Timeout(TimeoutError),
     Other(CommunicationError),
 }
 
@@ -15,6 +20,9 @@ impl std::fmt::Display for EscError {
             EscError::ApiResponse(err) => {
                 write!(f, "Bad status code: {}", err)
             }
+            EscError::Timeout(err) => {
+                write!(f, "Timed out: {}", err)
+            }
             EscError::Other(err) => {
                 write!(f, "Unexpected failure: {}", err)
             }
@@ -28,6 +36,9 @@ impl std::fmt::Debug for EscError {
             EscError::ApiResponse(err) => {
                 write!(f, "Bad status code: {:?}", err)
             }
+            EscError::Timeout(err) => {
+                write!(f, "Timed out: {:?}", err)
+            }
             EscError::Other(err) => {
                 write!(f, "Unexpected failure: {:?}", err)
             }
@@ -39,6 +50,7 @@ impl std::error::Error for EscError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             EscError::ApiResponse(_) => None,
+            EscError::Timeout(err) => Some(err),
             EscError::Other(err) => Some(err),
         }
     }
@@ -52,9 +64,41 @@ impl EscError {
     pub fn api_response(self) -> std::result::Result<Box<ApiResponseError>, EscError> {
         match self {
             EscError::ApiResponse(err) => Ok(err),
-            EscError::Other(_) => Err(self),
+            EscError::Timeout(_) | EscError::Other(_) => Err(self),
+        }
+    }
+}
+
+impl EscError {
+    /// True if the request was abandoned because it took too long.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, EscError::Timeout(_))
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            EscError::ApiResponse(err) => err.kind(),
+            EscError::Timeout(_) => ErrorKind::Timeout,
+            EscError::Other(_) => ErrorKind::Transport,
         }
     }
+
+    /// The fields the API rejected, if this is a validation error which
+    /// named them.
+    pub fn validation_fields(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            EscError::ApiResponse(err) if err.kind() == ErrorKind::Validation => err.fields(),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.kind() == ErrorKind::NotFound
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        self.kind() == ErrorKind::Conflict
+    }
 }
 
 impl From<CommunicationError> for EscError {
@@ -63,6 +107,12 @@ impl From<CommunicationError> for EscError {
     }
 }
 
+impl From<TimeoutError> for EscError {
+    fn from(err: TimeoutError) -> Self {
+        Self::Timeout(err)
+    }
+}
+
 impl From<ApiResponseError> for EscError {
     fn from(err: ApiResponseError) -> Self {
         Self::ApiResponse(Box::new(err))
@@ -77,7 +127,7 @@ impl PartialEq for EscError {
                 EscError::ApiResponse(other_err) => err == other_err,
                 _ => false,
             },
-            EscError::Other(_) => false,
+            EscError::Timeout(_) | EscError::Other(_) => false,
         }
     }
 }
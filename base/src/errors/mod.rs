@@ -1,10 +1,16 @@
 mod api_response_error;
+mod build_error;
 mod communication_error;
+mod error_kind;
 mod esc_error;
 mod problem_details;
+mod timeout_error;
 
 pub use api_response_error::ApiResponseError;
+pub use build_error::BuildError;
 pub use communication_error::CommunicationError;
+pub use error_kind::ErrorKind;
 pub use esc_error::EscError;
 pub use esc_error::Result;
 pub use problem_details::ProblemDetails;
+pub use timeout_error::TimeoutError;
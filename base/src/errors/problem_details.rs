@@ -13,6 +13,28 @@ pub struct ProblemDetails {
     pub _type: String,
 }
 
+impl ProblemDetails {
+    /// Creates problem details for an error response which didn't include
+    /// any, using the status code for the title and the body as the detail.
+    pub fn from_status(status_code: reqwest::StatusCode, url: &str, body: &str) -> Self {
+        Self {
+            detail: if body.trim().is_empty() {
+                None
+            } else {
+                Some(body.to_string())
+            },
+            fields: None,
+            instance: url.to_string(),
+            status: status_code.as_u16() as i32,
+            title: status_code
+                .canonical_reason()
+                .unwrap_or("Unknown status")
+                .to_string(),
+            _type: "about:blank".to_string(),
+        }
+    }
+}
+
 impl std::fmt::Display for ProblemDetails {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
         write!(f, r#"{{"detail": {:?}, "fields": {{"#, self.detail)?;
@@ -0,0 +1,30 @@
+/// Represents a request which was abandoned because the API didn't answer it
+/// within the configured time limit. Whether or not the API acted on the
+/// request is unknown.
+pub struct TimeoutError {
+    pub debug: String,
+    pub message: String,
+    pub source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl std::fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Debug for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        writeln!(
+            f,
+            "{}\nDetails: {}\nCaused by:\n\t{:?}",
+            self.message, self.debug, self.source
+        )
+    }
+}
+
+impl std::error::Error for TimeoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
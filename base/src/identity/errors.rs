@@ -1,9 +1,11 @@
+use crate::transport::TransportError;
 use http::StatusCode;
-use reqwest::Error as ReqwestError;
 
 pub struct IdentityError {
     pub message: String,
     pub status_code: Option<StatusCode>,
+    /// Set when identity couldn't be reached, to tell timeouts apart.
+    pub transport_error: Option<TransportError>,
 }
 
 impl std::fmt::Display for IdentityError {
@@ -21,13 +23,31 @@ impl std::fmt::Debug for IdentityError {
     }
 }
 
-impl std::error::Error for IdentityError {}
+impl std::error::Error for IdentityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.transport_error {
+            Some(err) => Some(err),
+            None => None,
+        }
+    }
+}
+
+impl From<TransportError> for IdentityError {
+    fn from(err: TransportError) -> Self {
+        Self {
+            message: format!("transport error: {}", err),
+            status_code: None,
+            transport_error: Some(err),
+        }
+    }
+}
 
-impl From<ReqwestError> for IdentityError {
-    fn from(re: ReqwestError) -> Self {
+impl From<serde_json::Error> for IdentityError {
+    fn from(err: serde_json::Error) -> Self {
         Self {
-            message: format!("reqwest error: {}", re),
+            message: format!("error reading identity response: {}", err),
             status_code: None,
+            transport_error: None,
         }
     }
 }
@@ -1,23 +1,50 @@
 use super::config::TokenConfig;
 use super::errors::IdentityError;
 use super::tokens::Token;
+use crate::transport::{Transport, TransportRequest, TransportResponse};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 pub type Result<T> = core::result::Result<T, IdentityError>;
 
-async fn parse_result<T>(resp: reqwest::Response) -> Result<T>
+async fn post_form(
+    transport: &(dyn Transport + Send + Sync),
+    url: String,
+    form: &std::collections::HashMap<&str, &str>,
+) -> Result<TransportResponse> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(form.iter())
+        .finish();
+    let req = TransportRequest::new(reqwest::Method::POST, url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body);
+    Ok(transport.send(req).await?)
+}
+
+async fn post_json<B: Serialize>(
+    transport: &(dyn Transport + Send + Sync),
+    url: String,
+    body: &B,
+) -> Result<TransportResponse> {
+    let body = serde_json::to_string(body)?;
+    let req = TransportRequest::new(reqwest::Method::POST, url)
+        .header("Content-Type", "application/json")
+        .body(body);
+    Ok(transport.send(req).await?)
+}
+
+fn parse_result<T>(resp: TransportResponse) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    let status = resp.status();
-    if status.is_success() {
-        let result: T = resp.json().await?;
+    if resp.status.is_success() {
+        let result: T = serde_json::from_str(&resp.body)?;
         return Ok(result);
     }
-    let message = resp.text().await?;
     Err(IdentityError {
-        message,
-        status_code: Some(status),
+        message: resp.body,
+        status_code: Some(resp.status),
+        transport_error: None,
     })
 }
 
@@ -30,7 +57,7 @@ struct MfaRequiredError {
 pub type OtpPrompt = fn() -> std::result::Result<String, String>;
 
 pub async fn create(
-    client: &reqwest::Client,
+    transport: &(dyn Transport + Send + Sync),
     config: &TokenConfig,
     user_name: &str,
     password: &str,
@@ -46,38 +73,36 @@ pub async fn create(
     form.insert("audience", &config.audience);
 
     let url = format!("{}/oauth/token", &config.identity_url);
-    let req = client.post(url.as_str()).form(&form);
-
-    let resp = req.send().await?;
+    let resp = post_form(transport, url, &form).await?;
 
-    handle_initial_oauth_token_resp(client, config, otp_prompt, resp).await
+    handle_initial_oauth_token_resp(transport, config, otp_prompt, resp).await
 }
 
 // interprets the response from oauth/token. May take other actions if needed,
 // such as handling an MFA challenge
 async fn handle_initial_oauth_token_resp(
-    client: &reqwest::Client,
+    transport: &(dyn Transport + Send + Sync),
     config: &TokenConfig,
     otp_prompt: Option<OtpPrompt>,
-    resp: reqwest::Response,
+    resp: TransportResponse,
 ) -> Result<Token> {
-    if resp.status().is_success() {
-        let result: Token = resp.json().await?;
-        Ok(result)
+    if resp.status.is_success() {
+        parse_result(resp)
     } else {
-        let mfa_token = get_mfa_token_or_error(resp).await?;
-        challenge_mfa_and_confirm_otp(client, config, &mfa_token).await?;
+        let mfa_token = get_mfa_token_or_error(resp)?;
+        challenge_mfa_and_confirm_otp(transport, config, &mfa_token).await?;
         match otp_prompt {
             Some(prompt_for_otp) => {
                 let result = prompt_for_otp();
                 match result {
                     Ok(otp) => {
-                        create_with_otp(client, config, mfa_token, otp).await
+                        create_with_otp(transport, config, mfa_token, otp).await
                     },
                     Err(err) => {
                         Err(IdentityError {
             message: format!("Error reading one time password: {}", err),
             status_code: None,
+            transport_error: None,
         })
                     }
                 }
@@ -86,16 +111,18 @@ async fn handle_initial_oauth_token_resp(
                 Err(IdentityError {
                                     message: "This account has MFA enabled but the ability for this client to interactively prompt for a one time password was not enabled for this call.".to_string(),
                                     status_code: None,
+                                    transport_error: None,
                                 })
             }
         }
     }
 }
 
-async fn get_mfa_token_or_error(resp: reqwest::Response) -> Result<String> {
-    let status = resp.status();
+fn get_mfa_token_or_error(resp: TransportResponse) -> Result<String> {
+    let status = resp.status;
     if status == 403 {
-        let result: std::result::Result<MfaRequiredError, reqwest::Error> = resp.json().await;
+        let result: std::result::Result<MfaRequiredError, serde_json::Error> =
+            serde_json::from_str(&resp.body);
         match result {
             Ok(error) => {
                 if error.error == "mfa_required" {
@@ -104,6 +131,7 @@ async fn get_mfa_token_or_error(resp: reqwest::Response) -> Result<String> {
                             Err(IdentityError {
                                     message: "Identity returned a 403 with an mfa_required error code, but no token.".to_string(),
                                     status_code: Some(status),
+                                    transport_error: None,
                                 })
                         }
                         Some(mfa_token) => Ok(mfa_token),
@@ -112,6 +140,7 @@ async fn get_mfa_token_or_error(resp: reqwest::Response) -> Result<String> {
                     Err(IdentityError {
                         message: "not authorized".to_string(),
                         status_code: Some(status),
+                        transport_error: None,
                     })
                 }
             }
@@ -119,6 +148,7 @@ async fn get_mfa_token_or_error(resp: reqwest::Response) -> Result<String> {
                 Err(IdentityError {
                         message: format!("Identity returned a 403 which could not be converted into a known error format: {}", err),
                         status_code: Some(status),
+                        transport_error: None,
                     })
             }
         }
@@ -126,6 +156,7 @@ async fn get_mfa_token_or_error(resp: reqwest::Response) -> Result<String> {
         Err(IdentityError {
             message: "not authorized".to_string(),
             status_code: Some(status),
+            transport_error: None,
         })
     }
 }
@@ -143,7 +174,7 @@ struct MfaChallengeResp {
 }
 
 async fn challenge_mfa_and_confirm_otp(
-    client: &reqwest::Client,
+    transport: &(dyn Transport + Send + Sync),
     config: &TokenConfig,
     mfa_token: &str,
 ) -> Result<()> {
@@ -154,22 +185,21 @@ async fn challenge_mfa_and_confirm_otp(
     };
 
     let url = format!("{}/mfa/challenge", &config.identity_url);
-    let req = client.post(url.as_str()).json(&args);
-
-    let resp = req.send().await?;
-    let resp: MfaChallengeResp = parse_result(resp).await?;
+    let resp = post_json(transport, url, &args).await?;
+    let resp: MfaChallengeResp = parse_result(resp)?;
     if resp.challenge_type == "otp" {
         Ok(())
     } else {
         Err(IdentityError {
             message: "Challenge type for this user's MFA was not OTP.".to_string(),
             status_code: None,
+            transport_error: None,
         })
     }
 }
 
 pub async fn create_with_otp(
-    client: &reqwest::Client,
+    transport: &(dyn Transport + Send + Sync),
     config: &TokenConfig,
     mfa_token: String,
     otp: String,
@@ -182,15 +212,13 @@ pub async fn create_with_otp(
     form.insert("otp", otp.as_ref());
 
     let url = format!("{}/oauth/token", &config.identity_url);
-    let req = client.post(url.as_str()).form(&form);
+    let resp = post_form(transport, url, &form).await?;
 
-    let resp = req.send().await?;
-
-    parse_result(resp).await
+    parse_result(resp)
 }
 
 pub async fn refresh(
-    client: &reqwest::Client,
+    transport: &(dyn Transport + Send + Sync),
     config: &TokenConfig,
     refresh_token: &str,
     otp_prompt: Option<OtpPrompt>,
@@ -202,11 +230,39 @@ pub async fn refresh(
     form.insert("client_id", config.client_id.as_ref());
     form.insert("refresh_token", refresh_token);
 
-    let req = client.post(url.as_str()).form(&form);
+    debug!("Token refresh on : {}", url);
 
-    debug!("Token refresh on : {:?}", req);
+    let resp = post_form(transport, url, &form).await?;
 
-    let resp = req.send().await?;
+    handle_initial_oauth_token_resp(transport, config, otp_prompt, resp).await
+}
 
-    handle_initial_oauth_token_resp(client, config, otp_prompt, resp).await
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RevokeArgs {
+    client_id: String,
+    token: String,
+}
+
+/// Revokes the refresh token, so it can't be exchanged for access tokens
+/// anymore.
+pub async fn revoke(
+    transport: &(dyn Transport + Send + Sync),
+    config: &TokenConfig,
+    refresh_token: &str,
+) -> Result<()> {
+    let args = RevokeArgs {
+        client_id: config.client_id.clone(),
+        token: refresh_token.to_string(),
+    };
+
+    let url = format!("{}/oauth/revoke", &config.identity_url);
+    let resp = post_json(transport, url, &args).await?;
+    if resp.status.is_success() {
+        return Ok(());
+    }
+    Err(IdentityError {
+        message: resp.body,
+        status_code: Some(resp.status),
+        transport_error: None,
+    })
 }
@@ -1,4 +1,6 @@
 #[macro_use]
+extern crate async_trait;
+#[macro_use]
 extern crate log;
 #[macro_use]
 extern crate serde_derive;
@@ -7,13 +9,19 @@ pub mod client;
 pub mod errors;
 pub mod identity;
 pub mod requests;
+pub mod transport;
 pub mod utils;
 
 pub use client::Authorization;
 pub use client::Client;
+pub use client::ClientBuilder;
+pub use errors::ErrorKind;
 pub use errors::EscError as Error;
 pub use errors::Result;
 pub use identity::tokens::Token;
+pub use requests::HttpConfig;
 pub use requests::RequestObserver;
 pub use requests::RequestSender;
+pub use requests::RetryPolicy;
+pub use transport::Transport;
 pub use utils::urlencode;
@@ -1,5 +1,16 @@
+mod http_config;
 mod observer;
+mod redact;
+mod retry;
 mod sender;
+#[cfg(feature = "tracing")]
+mod tracing_observer;
 
+pub use http_config::HttpConfig;
 pub use observer::Observer as RequestObserver;
+pub use observer::{redact_headers, FailureEvent, RequestEvent, ResponseEvent};
+pub use redact::Redactor;
+pub use retry::RetryPolicy;
 pub use sender::Sender as RequestSender;
+#[cfg(feature = "tracing")]
+pub use tracing_observer::TracingObserver;
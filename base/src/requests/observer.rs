@@ -1,5 +1,113 @@
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
 /// Used to spy on requests and responses.
 pub trait Observer {
-    fn on_request(&self, method: &str, url: &str, body: &str);
-    fn on_response(&self, status: &str, body: &str);
+    /// Called once before a request is sent for the first time.
+    fn on_request(&self, event: &RequestEvent);
+    /// Called once a response came back, after any retries.
+    fn on_response(&self, event: &ResponseEvent);
+    /// Called when no response came back at all, after any retries.
+    fn on_failure(&self, event: &FailureEvent);
+}
+
+/// A request about to be sent to the API.
+#[derive(Clone, Debug, Serialize)]
+pub struct RequestEvent {
+    /// Identifies the request, and the response or failure it ends with.
+    pub request_id: String,
+    pub method: String,
+    pub url: String,
+    /// Headers sent along, with credentials redacted.
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+}
+
+/// A response to a request sent to the API.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResponseEvent {
+    pub request_id: String,
+    pub method: String,
+    pub url: String,
+    #[serde(serialize_with = "serialize_status")]
+    pub status: StatusCode,
+    /// Headers of the response, with credentials redacted.
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    /// Size of the response body in bytes.
+    pub size: usize,
+    /// Time from sending the request to getting this response, including
+    /// failed attempts and the time waited between them.
+    #[serde(serialize_with = "serialize_millis", rename = "elapsed_ms")]
+    pub elapsed: Duration,
+    pub attempts: u32,
+}
+
+/// A request to the API which never got a response.
+#[derive(Clone, Debug, Serialize)]
+pub struct FailureEvent {
+    pub request_id: String,
+    pub method: String,
+    pub url: String,
+    pub error: String,
+    #[serde(serialize_with = "serialize_millis", rename = "elapsed_ms")]
+    pub elapsed: Duration,
+    pub attempts: u32,
+}
+
+impl ResponseEvent {
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+}
+
+const REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+];
+
+/// Turns headers into something fit for logs, keeping only the scheme of
+/// credentials (such as "Bearer").
+pub fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.to_str().unwrap_or("<binary>");
+            let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                match value.split_once(' ') {
+                    Some((scheme, _)) => format!("{} [redacted]", scheme),
+                    None => "[redacted]".to_string(),
+                }
+            } else {
+                value.to_string()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// A random id to tell requests apart, such as "3f9c0b7a1e2d4c58".
+pub(crate) fn new_request_id() -> String {
+    format!("{:016x}", RandomState::new().build_hasher().finish())
+}
+
+fn serialize_status<S: serde::Serializer>(
+    status: &StatusCode,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(status.as_u16())
+}
+
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
 }
@@ -0,0 +1,106 @@
+use serde_json::Value;
+
+// Secrets which can be part of request bodies: those of the integration
+// sinks (Slack, OpsGenie, PagerDuty, AWS and GCP) and refresh tokens. A
+// bare "token" is too common a name, only the Slack one is a secret.
+const SECRET_FIELDS: &[&str] = &[
+    "data.token",
+    "apiKey",
+    "authToken",
+    "secretAccessKey",
+    "gcpServiceAccountPrivateKey",
+    "refreshToken",
+    "password",
+    "clientSecret",
+];
+
+const REDACTED: &str = "[redacted]";
+
+/// Masks secrets in JSON bodies before they're shown or logged.
+///
+/// A field is either a name, which matches a key anywhere in the body, or a
+/// dotted path such as `data.token`, which matches a key reached through
+/// those keys (array indices aren't part of the path). Names are compared
+/// regardless of case and underscores, so `secret_access_key` and
+/// `secretAccessKey` are the same field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redactor {
+    fields: Vec<Vec<String>>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        SECRET_FIELDS
+            .iter()
+            .fold(Self::none(), |redactor, field| redactor.field(field))
+    }
+}
+
+impl Redactor {
+    /// A redactor which doesn't mask anything until told which fields to.
+    pub fn none() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    pub fn field(mut self, field: &str) -> Self {
+        let path: Vec<String> = field
+            .split('.')
+            .map(normalize)
+            .filter(|key| !key.is_empty())
+            .collect();
+        if !path.is_empty() {
+            self.fields.push(path);
+        }
+        self
+    }
+
+    /// Returns the body with the value of every secret field replaced.
+    /// Bodies which aren't JSON, or hold no secrets, are returned as is.
+    pub fn redact(&self, body: &str) -> String {
+        let mut value = match serde_json::from_str::<Value>(body) {
+            Ok(value) => value,
+            Err(_) => return body.to_string(),
+        };
+        if self.redact_value(&mut value, &mut Vec::new()) {
+            value.to_string()
+        } else {
+            body.to_string()
+        }
+    }
+
+    fn redact_value(&self, value: &mut Value, path: &mut Vec<String>) -> bool {
+        let mut redacted = false;
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    path.push(normalize(key));
+                    if self.is_secret(path) && !value.is_null() {
+                        *value = Value::String(REDACTED.to_string());
+                        redacted = true;
+                    } else {
+                        redacted |= self.redact_value(value, path);
+                    }
+                    path.pop();
+                }
+            }
+            Value::Array(values) => {
+                for value in values.iter_mut() {
+                    redacted |= self.redact_value(value, path);
+                }
+            }
+            _ => {}
+        }
+        redacted
+    }
+
+    fn is_secret(&self, path: &[String]) -> bool {
+        self.fields.iter().any(|field| path.ends_with(field))
+    }
+}
+
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,124 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+
+use crate::transport::{TransportError, TransportErrorKind};
+
+/// Decides whether a request which failed for a transient reason (being rate
+/// limited, a bad gateway, a dropped connection) is sent again, and how long
+/// to wait before doing so.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts made for a request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry. It doubles for each retry after that.
+    pub initial_backoff: Duration,
+    /// Upper bound for the computed delay between two attempts.
+    pub max_backoff: Duration,
+    /// Also retry methods which aren't idempotent, such as POST. Requests
+    /// the API rate limited or which never reached it are always retried.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends every request exactly once.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Retries transient failures up to `retries` times.
+    pub fn with_retries(retries: u32) -> Self {
+        Self {
+            max_attempts: retries.saturating_add(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    /// True if a request which got the given status back should be retried.
+    pub fn should_retry_status(&self, method: &Method, status: StatusCode) -> bool {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => true,
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => self.allows(method),
+            _ => false,
+        }
+    }
+
+    /// True if a request which failed without getting a response should be
+    /// retried.
+    pub fn should_retry_error(&self, method: &Method, err: &TransportError) -> bool {
+        match err.kind {
+            TransportErrorKind::Connect => true,
+            TransportErrorKind::Timeout | TransportErrorKind::Request => self.allows(method),
+            TransportErrorKind::Other => false,
+        }
+    }
+
+    fn allows(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || is_idempotent(method)
+    }
+
+    /// How long to wait before the given retry (starting at 1). A delay
+    /// asked for by the server through Retry-After takes precedence, up to
+    /// the maximum backoff so a server can't stall the client for hours.
+    pub fn backoff(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_backoff);
+        }
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        // "Equal jitter": wait at least half of the delay, so retries still
+        // back off, plus a random part so clients don't retry in lockstep.
+        let half = delay / 2;
+        half + half.mul_f64(random_fraction())
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+
+// Avoids pulling in a random number crate for the sake of jitter: every
+// RandomState is seeded with fresh random keys.
+fn random_fraction() -> f64 {
+    let value = RandomState::new().build_hasher().finish();
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Parses the value of a Retry-After header, which is either a number of
+/// seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.signed_duration_since(chrono::Utc::now());
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
@@ -1,23 +1,52 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use super::observer::Observer;
-use crate::errors::{ApiResponseError, CommunicationError, EscError, ProblemDetails, Result};
+use super::observer::{
+    new_request_id, redact_headers, FailureEvent, Observer, RequestEvent, ResponseEvent,
+};
+use super::retry::{parse_retry_after, RetryPolicy};
+use crate::errors::{
+    ApiResponseError, CommunicationError, EscError, ProblemDetails, Result, TimeoutError,
+};
+use crate::transport::{Transport, TransportError, TransportRequest};
 
-/// Wraps a reqwest::Client and an observer with a helper function which accepts
+/// Wraps a transport and an observer with a helper function which accepts
 /// a token to make authenticated requests. This makes it possible to observe
 /// all parts of the request / response apart from authentication.
+/// Requests failing for transient reasons are retried per the retry policy.
 #[derive(Clone)]
 pub struct Sender {
-    pub client: reqwest::Client,
+    pub transport: Arc<dyn Transport + Send + Sync>,
     pub observer: Option<Arc<dyn Observer + Send + Sync>>,
+    pub retry_policy: RetryPolicy,
+    /// Time limit for each request, overriding the one set on the transport.
+    pub timeout: Option<Duration>,
 }
 
 impl Sender {
-    pub fn new(client: reqwest::Client, observer: Option<Arc<dyn Observer + Send + Sync>>) -> Self {
-        Self { client, observer }
+    pub fn new(
+        transport: Arc<dyn Transport + Send + Sync>,
+        observer: Option<Arc<dyn Observer + Send + Sync>>,
+    ) -> Self {
+        Self {
+            transport,
+            observer,
+            retry_policy: RetryPolicy::none(),
+            timeout: None,
+        }
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
     pub async fn send_request<B: Serialize + ?Sized, R: DeserializeOwned>(
@@ -28,155 +57,203 @@ impl Sender {
         body: Option<&B>,
         use_return_value: Option<R>,
     ) -> Result<R> {
+        self.send(authorization_header, method, url, body)
+            .await?
+            .decode(use_return_value)
+    }
+
+    /// Sends a request and returns the response if it's successful, without
+    /// deserializing it yet. Any other response is turned into an error.
+    pub async fn send<B: Serialize + ?Sized>(
+        &self,
+        authorization_header: &str,
+        method: reqwest::Method,
+        url: String,
+        body: Option<&B>,
+    ) -> Result<SuccessfulResponse> {
+        let body_string = match body {
+            Some(b) => Some(serde_json::to_string(b).map_err(|err| CommunicationError {
+                debug: format!("method={}, url={}", method, url),
+                message: "Error serializing request body".to_string(),
+                source: Box::new(err),
+            })?),
+            None => None,
+        };
+        let request_id = new_request_id();
+        let new_request = || {
+            let mut req = TransportRequest::new(method.clone(), url.clone())
+                .header("Authorization", authorization_header)
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json");
+            req.body = body_string.clone();
+            req.timeout = self.timeout;
+            req
+        };
         if let Some(o) = &self.observer {
-            let body_string: String = match body {
-                Some(b) => match serde_json::to_string(b) {
-                    Ok(s) => s,
-                    _ => "<err!>".to_string(),
-                },
-                None => "".to_string(),
-            };
-            o.on_request(method.as_str(), &url, &body_string);
+            o.on_request(&RequestEvent {
+                request_id: request_id.clone(),
+                method: method.to_string(),
+                url: url.clone(),
+                headers: redact_headers(&new_request().headers),
+                body: body_string.clone(),
+            });
         }
 
-        let req = self
-            .client
-            .request(method.clone(), url.as_str())
-            .header("Authorization", authorization_header)
-            .header("Content-Type", "application/json")
-            .header("Accept", "application/json");
-        let req = match body {
-            Some(b) => req.json(b),
-            None => req,
-        };
+        let started = Instant::now();
+        let mut retry = 0;
+        let resp = loop {
+            let result = self.transport.send(new_request()).await;
+            let should_retry = match &result {
+                Ok(resp) => self.retry_policy.should_retry_status(&method, resp.status),
+                Err(err) => self.retry_policy.should_retry_error(&method, err),
+            };
+            if !should_retry || retry + 1 >= self.retry_policy.max_attempts {
+                match result {
+                    Ok(resp) => break resp,
+                    Err(err) => {
+                        if let Some(o) = &self.observer {
+                            o.on_failure(&FailureEvent {
+                                request_id,
+                                method: method.to_string(),
+                                url: url.clone(),
+                                error: err.to_string(),
+                                elapsed: started.elapsed(),
+                                attempts: retry + 1,
+                            });
+                        }
+                        return Err(request_failure(
+                            "Error making request",
+                            format!("method={}, url={}, attempts={}", method, url, retry + 1),
+                            err,
+                        ));
+                    }
+                }
+            }
 
-        let resp = req.send().await.map_err(|r| CommunicationError {
-            debug: format!("method={}, url={}", method, url),
-            message: "Error making request".to_string(),
-            source: Box::new(r),
-        })?;
+            retry += 1;
+            let retry_after = match &result {
+                Ok(resp) => resp
+                    .headers
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_retry_after),
+                Err(_) => None,
+            };
+            let delay = self.retry_policy.backoff(retry, retry_after);
+            match &result {
+                Ok(resp) => info!(
+                    "{} {} returned {}, retrying in {:?}",
+                    method, url, resp.status, delay
+                ),
+                Err(err) => info!(
+                    "{} {} failed: {}, retrying in {:?}",
+                    method, url, err, delay
+                ),
+            }
+            tokio::time::sleep(delay).await;
+        };
 
-        let status_code = resp.status();
+        let status_code = resp.status;
+        if let Some(o) = &self.observer {
+            o.on_response(&ResponseEvent {
+                request_id,
+                method: method.to_string(),
+                url: url.clone(),
+                status: status_code,
+                headers: redact_headers(&resp.headers),
+                size: resp.body.len(),
+                body: resp.body.clone(),
+                elapsed: started.elapsed(),
+                attempts: retry + 1,
+            });
+        }
+        let text = resp.body;
 
         if status_code.is_success() {
-            return match &self.observer {
-                Some(o) => {
-                    let text = resp.text().await.map_err(|r| CommunicationError {
-                        debug: format!(
-                            "method={}, url={}, status code={}",
-                            method, url, status_code
-                        ),
-                        message: "Error reading request response".to_string(),
-                        source: Box::new(r),
-                    })?;
-                    o.on_response(status_code.as_str(), &text);
-                    let r: R = match use_return_value {
-                        Some(r) => r,
-                        None => {
-                            // `from_str` must use an intermediary serde_json::Value
-                            // here to avoid an esoteric failure which is seen
-                            // in some cases, see:
-                            // https://github.com/serde-rs/json/issues/505
-                            // let r: R = serde_json::from_str(&text)?;
-                            // When revisiting the code generator this work around
-                            // should only be applied to problematic models as
-                            // it's a little inefficient.
-                            let from_str_result: serde_json::Result<serde_json::Value> =
-                                serde_json::from_str(&text);
-                            match from_str_result {
-                                Err(err) => {
-                                    return Err(EscError::Other(CommunicationError {
-                                        debug: format!(
-                                            "method={}, url={}, status code={} response text={}",
-                                            method, url, status_code, text
-                                        ),
-                                        message: "Error deserializing response text".to_string(),
-                                        source: Box::new(err),
-                                    }));
-                                }
-                                Ok(d) => {
-                                    let from_value_result: serde_json::Result<R> =
-                                        serde_json::from_value(d);
-                                    match from_value_result {
-                                        Err(err) => {
-                                            return Err(EscError::Other(CommunicationError{
-                                                debug: format!("method={}, url={}, status code={} response text={}", method, url, status_code, text),
-                                                message: "Error deserializing response text into specific client type".to_string(),
-                                                source: Box::new(err),
-                                            }));
-                                        }
-                                        Ok(r) => r,
-                                    }
-                                }
-                            }
-                        }
-                    };
-                    Ok(r)
-                }
-                None => {
-                    let r: R = match use_return_value {
-                        Some(r) => r,
-                        None => {
-                            let d: serde_json::Value =
-                                resp.json().await.map_err(|s| CommunicationError {
-                                    debug: format!(
-                                        "method={}, url={}, status code={}",
-                                        method, url, status_code
-                                    ),
-                                    message: "Error reading or deserializing the request response"
-                                        .to_string(),
-                                    source: Box::new(s),
-                                })?;
-                            let r: R = serde_json::from_value(d).map_err(|s| CommunicationError {
-                                debug: format!(
-                                    "method={}, url={}, status code={}",
-                                    method, url, status_code
-                                ),
-                                message: "Error deserializing response text into specific client type"
-                                    .to_string(),
-                                source: Box::new(s),
-                            })?;
-                            r
-                        }
-                    };
-                    Ok(r)
-                }
-            };
+            return Ok(SuccessfulResponse {
+                method,
+                url,
+                status_code,
+                text,
+            });
         }
 
-        let problem_details: ProblemDetails = match &self.observer {
-            Some(o) => {
-                let text = resp.text().await.map_err(|r| CommunicationError {
-                    debug: format!(
-                        "method={}, url={}, status code={}",
-                        method, url, status_code
-                    ),
-                    message: "Error reading request response".to_string(),
-                    source: Box::new(r),
-                })?;
-                o.on_response(status_code.as_str(), &text);
-                serde_json::from_str(&text).map_err(|s| CommunicationError {
-                    debug: format!(
-                        "method={}, url={}, status code={}, response text={}",
-                        method, url, status_code, text
-                    ),
-                    message: "Error deserializing response text into specific client type"
-                        .to_string(),
-                    source: Box::new(s),
-                })?
-            }
-            None => resp.json().await.map_err(|s| CommunicationError {
-                debug: format!(
-                    "method={}, url={}, status code={}",
-                    method, url, status_code
-                ),
-                message: "Error reading or deserializing the request response".to_string(),
-                source: Box::new(s),
-            })?,
-        };
+        // Not every error comes from the API itself (gateways in front of it
+        // may reject an expired token with an empty body, for instance) so
+        // when there are no problem details one is made from the status code.
+        let problem_details: ProblemDetails = serde_json::from_str(&text)
+            .unwrap_or_else(|_| ProblemDetails::from_status(status_code, &url, &text));
         Err(EscError::ApiResponse(Box::new(ApiResponseError {
             problem_details,
             status_code,
         })))
     }
 }
+
+/// The body of a successful response, along with what's needed to explain a
+/// failure to deserialize it.
+pub struct SuccessfulResponse {
+    method: reqwest::Method,
+    url: String,
+    status_code: reqwest::StatusCode,
+    text: String,
+}
+
+impl SuccessfulResponse {
+    /// Deserializes the body, unless the caller has a value to use instead.
+    pub fn decode<R: DeserializeOwned>(self, use_return_value: Option<R>) -> Result<R> {
+        let SuccessfulResponse {
+            method,
+            url,
+            status_code,
+            text,
+        } = self;
+        if let Some(r) = use_return_value {
+            return Ok(r);
+        }
+        // `from_str` must use an intermediary serde_json::Value
+        // here to avoid an esoteric failure which is seen
+        // in some cases, see:
+        // https://github.com/serde-rs/json/issues/505
+        // let r: R = serde_json::from_str(&text)?;
+        // When revisiting the code generator this work around
+        // should only be applied to problematic models as
+        // it's a little inefficient.
+        let value: serde_json::Value =
+            serde_json::from_str(&text).map_err(|err| CommunicationError {
+                debug: format!(
+                    "method={}, url={}, status code={} response text={}",
+                    method, url, status_code, text
+                ),
+                message: "Error deserializing response text".to_string(),
+                source: Box::new(err),
+            })?;
+        let r: R = serde_json::from_value(value).map_err(|err| CommunicationError {
+            debug: format!(
+                "method={}, url={}, status code={} response text={}",
+                method, url, status_code, text
+            ),
+            message: "Error deserializing response text into specific client type".to_string(),
+            source: Box::new(err),
+        })?;
+        Ok(r)
+    }
+}
+
+// Transport errors are either the request timing out, which gets its own
+// error so callers can tell it apart, or a general communication failure.
+fn request_failure(message: &str, debug: String, err: TransportError) -> EscError {
+    if err.is_timeout() {
+        EscError::Timeout(TimeoutError {
+            debug,
+            message: format!("{}: the request timed out", message),
+            source: Box::new(err),
+        })
+    } else {
+        EscError::Other(CommunicationError {
+            debug,
+            message: message.to_string(),
+            source: Box::new(err),
+        })
+    }
+}
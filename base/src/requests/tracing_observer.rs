@@ -0,0 +1,62 @@
+use super::observer::{FailureEvent, Observer, RequestEvent, ResponseEvent};
+
+/// Reports requests as `tracing` events under the `esc_client_base::http`
+/// target, so they end up wherever the application's subscriber sends
+/// them. Bodies are only reported at the trace level.
+#[derive(Clone, Debug, Default)]
+pub struct TracingObserver;
+
+impl Observer for TracingObserver {
+    fn on_request(&self, event: &RequestEvent) {
+        tracing::debug!(
+            target: "esc_client_base::http",
+            request_id = %event.request_id,
+            method = %event.method,
+            url = %event.url,
+            headers = ?event.headers,
+            "sending request"
+        );
+        if let Some(body) = event.body.as_deref() {
+            tracing::trace!(
+                target: "esc_client_base::http",
+                request_id = %event.request_id,
+                body,
+                "request body"
+            );
+        }
+    }
+
+    fn on_response(&self, event: &ResponseEvent) {
+        tracing::debug!(
+            target: "esc_client_base::http",
+            request_id = %event.request_id,
+            method = %event.method,
+            url = %event.url,
+            status = event.status.as_u16(),
+            size = event.size,
+            elapsed_ms = event.elapsed.as_millis() as u64,
+            attempts = event.attempts,
+            headers = ?event.headers,
+            "received response"
+        );
+        tracing::trace!(
+            target: "esc_client_base::http",
+            request_id = %event.request_id,
+            body = %event.body,
+            "response body"
+        );
+    }
+
+    fn on_failure(&self, event: &FailureEvent) {
+        tracing::warn!(
+            target: "esc_client_base::http",
+            request_id = %event.request_id,
+            method = %event.method,
+            url = %event.url,
+            error = %event.error,
+            elapsed_ms = event.elapsed.as_millis() as u64,
+            attempts = event.attempts,
+            "request failed"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+
+use crate::requests::Redactor;
+
+use super::{Transport, TransportError, TransportErrorKind, TransportRequest, TransportResponse};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Cassette {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+// Request headers and bodies are left out on purpose: they carry the access
+// token, and for identity the password or refresh token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+}
+
+// Response bodies are kept, with the tokens identity answers with masked
+// along with the other secrets the redactor knows about.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+enum Mode {
+    Record(Arc<dyn Transport + Send + Sync>),
+    Replay { next: usize },
+}
+
+/// Records the requests sent through another transport, along with the
+/// responses, to a JSON file. The file can later be replayed without a
+/// server: requests must then come in the same order, with the same method
+/// and URL, as when they were recorded.
+pub struct CassetteTransport {
+    path: PathBuf,
+    mode: Mutex<Mode>,
+    cassette: Mutex<Cassette>,
+    redactor: Redactor,
+}
+
+fn redactor() -> Redactor {
+    Redactor::default().field("access_token").field("id_token")
+}
+
+impl CassetteTransport {
+    /// Sends requests through `inner`, saving every exchange to `path`. Any
+    /// existing file at `path` is overwritten.
+    pub fn record(inner: Arc<dyn Transport + Send + Sync>, path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mutex::new(Mode::Record(inner)),
+            cassette: Mutex::new(Cassette::default()),
+            redactor: redactor(),
+        }
+    }
+
+    /// Answers requests with the exchanges saved in the file at `path`.
+    pub fn replay(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let cassette: Cassette = serde_json::from_str(&content)?;
+        Ok(Self {
+            path: path.as_ref().to_path_buf(),
+            mode: Mutex::new(Mode::Replay { next: 0 }),
+            cassette: Mutex::new(cassette),
+            redactor: redactor(),
+        })
+    }
+
+    fn save(
+        &self,
+        request: &TransportRequest,
+        response: &TransportResponse,
+    ) -> std::io::Result<()> {
+        let mut cassette = self.cassette.lock().expect("cassette lock was poisoned");
+        cassette.interactions.push(Interaction {
+            request: RecordedRequest {
+                method: request.method.to_string(),
+                url: request.url.clone(),
+            },
+            response: RecordedResponse {
+                status: response.status.as_u16(),
+                headers: response
+                    .headers
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        value
+                            .to_str()
+                            .ok()
+                            .map(|value| (name.to_string(), value.to_string()))
+                    })
+                    .collect(),
+                body: self.redactor.redact(&response.body),
+            },
+        });
+        let content = serde_json::to_string_pretty(&*cassette)?;
+        std::fs::write(&self.path, content)
+    }
+
+    fn play(
+        &self,
+        next: usize,
+        request: &TransportRequest,
+    ) -> Result<TransportResponse, TransportError> {
+        let cassette = self.cassette.lock().expect("cassette lock was poisoned");
+        let interaction = cassette.interactions.get(next).ok_or_else(|| {
+            self.error(&format!(
+                "no recorded response left for {} {}",
+                request.method, request.url
+            ))
+        })?;
+        if interaction.request.method != request.method.as_str()
+            || interaction.request.url != request.url
+        {
+            return Err(self.error(&format!(
+                "expected {} {} as request #{} but got {} {}",
+                interaction.request.method,
+                interaction.request.url,
+                next + 1,
+                request.method,
+                request.url
+            )));
+        }
+
+        let recorded = &interaction.response;
+        let status = StatusCode::from_u16(recorded.status)
+            .map_err(|_| self.error(&format!("invalid status code {}", recorded.status)))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &recorded.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        Ok(TransportResponse {
+            status,
+            headers,
+            body: recorded.body.clone(),
+        })
+    }
+
+    fn error(&self, message: &str) -> TransportError {
+        TransportError::new(
+            TransportErrorKind::Other,
+            &format!("cassette {}: {}", self.path.display(), message),
+        )
+    }
+}
+
+#[async_trait]
+impl Transport for CassetteTransport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        // The lock can't be held across the await below, so in record mode
+        // the inner transport is cloned out first.
+        let inner = {
+            let mut mode = self.mode.lock().expect("mode lock was poisoned");
+            match &mut *mode {
+                Mode::Record(inner) => inner.clone(),
+                Mode::Replay { next } => {
+                    let current = *next;
+                    *next += 1;
+                    drop(mode);
+                    return self.play(current, &request);
+                }
+            }
+        };
+
+        let response = inner.send(request.clone()).await?;
+        self.save(&request, &response)
+            .map_err(|err| TransportError {
+                kind: TransportErrorKind::Other,
+                message: format!("cassette {}: could not be saved", self.path.display()),
+                source: Some(Box::new(err)),
+            })?;
+        Ok(response)
+    }
+}
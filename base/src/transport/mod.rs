@@ -0,0 +1,132 @@
+//! The layer which actually moves requests and responses over the wire.
+//! Everything above it (the request sender, identity operations) only deals
+//! with the types here, so a transport which never touches the network can
+//! be swapped in for testing.
+
+mod cassette;
+mod reqwest_transport;
+mod scripted;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+pub use cassette::CassetteTransport;
+pub use scripted::ScriptedTransport;
+
+/// Sends a request and reads the entire response.
+#[async_trait]
+pub trait Transport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError>;
+}
+
+#[derive(Clone, Debug)]
+pub struct TransportRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+    /// Time limit for this request, overriding any the transport may have.
+    pub timeout: Option<Duration>,
+}
+
+impl TransportRequest {
+    pub fn new(method: Method, url: String) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: None,
+            timeout: None,
+        }
+    }
+
+    /// Adds a header. Panics if the name or value are not valid in a header,
+    /// so they should only be used with names and values known up front.
+    pub fn header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers
+            .insert(name, value.parse().expect("header value should be valid"));
+        self
+    }
+
+    pub fn body(mut self, body: String) -> Self {
+        self.body = Some(body);
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TransportResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl TransportResponse {
+    pub fn new(status: StatusCode, body: &str) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransportErrorKind {
+    /// No connection could be made, so the request never reached the server.
+    Connect,
+    /// The request took longer than it was allowed to.
+    Timeout,
+    /// The request couldn't be sent.
+    Request,
+    /// Anything else, such as the response body being unreadable.
+    Other,
+}
+
+/// A failure to complete an HTTP exchange. Responses with an error status
+/// are not transport errors.
+pub struct TransportError {
+    pub kind: TransportErrorKind,
+    pub message: String,
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl TransportError {
+    pub fn new(kind: TransportErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            source: None,
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind == TransportErrorKind::Timeout
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::fmt::Debug for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)?;
+        if let Some(source) = &self.source {
+            write!(f, "\nCaused by:\n\t{:?}", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
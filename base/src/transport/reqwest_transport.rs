@@ -0,0 +1,46 @@
+use super::{Transport, TransportError, TransportErrorKind, TransportRequest, TransportResponse};
+
+#[async_trait]
+impl Transport for reqwest::Client {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let mut req = self
+            .request(request.method, request.url.as_str())
+            .headers(request.headers);
+        if let Some(body) = request.body {
+            req = req.body(body);
+        }
+        if let Some(timeout) = request.timeout {
+            req = req.timeout(timeout);
+        }
+
+        let resp = req.send().await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.text().await?;
+        Ok(TransportResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(err: reqwest::Error) -> Self {
+        // A connect timeout is also a connect error, so timeouts go first.
+        let kind = if err.is_timeout() {
+            TransportErrorKind::Timeout
+        } else if err.is_connect() {
+            TransportErrorKind::Connect
+        } else if err.is_request() {
+            TransportErrorKind::Request
+        } else {
+            TransportErrorKind::Other
+        };
+        Self {
+            kind,
+            message: err.to_string(),
+            source: Some(Box::new(err)),
+        }
+    }
+}
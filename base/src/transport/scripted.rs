@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::{Transport, TransportError, TransportErrorKind, TransportRequest, TransportResponse};
+
+/// Answers requests with responses scripted up front, in order, without
+/// touching the network. Every request it receives is kept so tests can
+/// check what was sent.
+///
+/// ```
+/// use esc_client_base::transport::ScriptedTransport;
+/// use reqwest::StatusCode;
+///
+/// let transport = ScriptedTransport::new()
+///     .respond(StatusCode::SERVICE_UNAVAILABLE, "")
+///     .respond(StatusCode::OK, r#"{"id": "cluster-1"}"#);
+/// ```
+#[derive(Default)]
+pub struct ScriptedTransport {
+    script: Mutex<VecDeque<Result<TransportResponse, TransportError>>>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl ScriptedTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a response with the given status code and body.
+    pub fn respond(self, status: reqwest::StatusCode, body: &str) -> Self {
+        self.then(Ok(TransportResponse::new(status, body)))
+    }
+
+    /// Queues a response with a JSON body.
+    pub fn respond_json<T: serde::Serialize>(self, status: reqwest::StatusCode, body: &T) -> Self {
+        let body = serde_json::to_string(body).expect("scripted body should serialize");
+        self.respond(status, &body)
+    }
+
+    /// Queues a failure to complete the request, such as a timeout.
+    pub fn fail(self, kind: TransportErrorKind, message: &str) -> Self {
+        self.then(Err(TransportError::new(kind, message)))
+    }
+
+    /// Queues an arbitrary outcome.
+    pub fn then(self, outcome: Result<TransportResponse, TransportError>) -> Self {
+        self.script
+            .lock()
+            .expect("script lock was poisoned")
+            .push_back(outcome);
+        self
+    }
+
+    /// The requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests
+            .lock()
+            .expect("requests lock was poisoned")
+            .clone()
+    }
+
+    /// True once every scripted outcome has been used.
+    pub fn is_done(&self) -> bool {
+        self.script
+            .lock()
+            .expect("script lock was poisoned")
+            .is_empty()
+    }
+}
+
+#[async_trait]
+impl Transport for ScriptedTransport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, TransportError> {
+        let description = format!("{} {}", request.method, request.url);
+        self.requests
+            .lock()
+            .expect("requests lock was poisoned")
+            .push(request);
+        match self
+            .script
+            .lock()
+            .expect("script lock was poisoned")
+            .pop_front()
+        {
+            Some(outcome) => outcome,
+            None => Err(TransportError::new(
+                TransportErrorKind::Other,
+                &format!("no scripted response left for {}", description),
+            )),
+        }
+    }
+}
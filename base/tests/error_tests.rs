@@ -1,6 +1,8 @@
 #![allow(clippy::result_large_err)]
 
 use esc_client_base::errors::ApiResponseError;
+use esc_client_base::errors::ErrorKind;
+use esc_client_base::errors::EscError;
 use esc_client_base::errors::ProblemDetails;
 use esc_client_base::errors::Result;
 
@@ -67,3 +69,59 @@ fn test_checking_api_response_from_error() {
 async fn test_async_function_error_handling() {
     assert_eq!(Ok(500u16), async_expect_error().await);
 }
+
+fn api_error(status_code: reqwest::StatusCode, fields: Option<Vec<(&str, &str)>>) -> EscError {
+    let problem_details = ProblemDetails {
+        detail: None,
+        fields: fields.map(|fields| {
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        }),
+        instance: "Instance".to_string(),
+        status: status_code.as_u16() as i32,
+        title: "Title".to_string(),
+        _type: "Type".to_string(),
+    };
+    ApiResponseError {
+        problem_details,
+        status_code,
+    }
+    .into()
+}
+
+#[test]
+fn test_error_kind_from_status_code() {
+    use reqwest::StatusCode;
+    let cases = [
+        (StatusCode::BAD_REQUEST, ErrorKind::Validation),
+        (StatusCode::UNAUTHORIZED, ErrorKind::Unauthorized),
+        (StatusCode::FORBIDDEN, ErrorKind::Forbidden),
+        (StatusCode::NOT_FOUND, ErrorKind::NotFound),
+        (StatusCode::CONFLICT, ErrorKind::Conflict),
+        (StatusCode::TOO_MANY_REQUESTS, ErrorKind::RateLimited),
+        (StatusCode::BAD_GATEWAY, ErrorKind::ServerError),
+        (StatusCode::IM_A_TEAPOT, ErrorKind::OtherStatus),
+    ];
+    for (status_code, kind) in cases {
+        assert_eq!(kind, api_error(status_code, None).kind());
+    }
+}
+
+#[test]
+fn test_validation_fields() {
+    let err = api_error(
+        reqwest::StatusCode::BAD_REQUEST,
+        Some(vec![("cidrBlock", "overlaps an existing network")]),
+    );
+    assert_eq!(
+        Some("overlaps an existing network"),
+        err.validation_fields()
+            .and_then(|fields| fields.get("cidrBlock"))
+            .map(String::as_str)
+    );
+    let err = api_error(reqwest::StatusCode::CONFLICT, Some(vec![("id", "taken")]));
+    assert!(err.validation_fields().is_none());
+    assert!(err.is_conflict());
+}
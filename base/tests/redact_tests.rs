@@ -0,0 +1,41 @@
+use esc_client_base::requests::Redactor;
+use serde_json::{json, Value};
+
+fn redact(redactor: &Redactor, body: Value) -> Value {
+    serde_json::from_str(&redactor.redact(&body.to_string())).unwrap()
+}
+
+#[test]
+fn test_integration_secrets_are_redacted() {
+    let redactor = Redactor::default();
+
+    let slack = redact(
+        &redactor,
+        json!({"description": "alerts", "data": {"sink": "slack", "channelId": "C1", "token": "xoxb-1"}}),
+    );
+    let aws = redact(
+        &redactor,
+        json!({"data": {"sink": "awsCloudWatchLogs", "accessKeyId": "AKIA", "secretAccessKey": "s3cr3t"}}),
+    );
+
+    assert_eq!("[redacted]", slack["data"]["token"]);
+    assert_eq!("C1", slack["data"]["channelId"]);
+    assert_eq!("alerts", slack["description"]);
+    assert_eq!("[redacted]", aws["data"]["secretAccessKey"]);
+    assert_eq!("AKIA", aws["data"]["accessKeyId"]);
+}
+
+#[test]
+fn test_extra_fields_match_by_name_or_path() {
+    let redactor = Redactor::none().field("cidr_block").field("peering.routes");
+
+    let body = redact(
+        &redactor,
+        json!({"cidrBlock": "10.0.0.0/16", "routes": ["a"], "peering": [{"routes": ["b"]}]}),
+    );
+
+    assert_eq!("[redacted]", body["cidrBlock"]);
+    assert_eq!(json!(["a"]), body["routes"]);
+    assert_eq!("[redacted]", body["peering"][0]["routes"]);
+    assert_eq!("not json", redactor.redact("not json"));
+}
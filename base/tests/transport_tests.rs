@@ -0,0 +1,355 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use esc_client_base::client::{RefreshTokenAuthorizer, StaticTokenAuthorizer};
+use esc_client_base::identity::{Token, TokenConfig};
+use esc_client_base::requests::{FailureEvent, RequestEvent, ResponseEvent};
+use esc_client_base::transport::{
+    CassetteTransport, ScriptedTransport, Transport, TransportErrorKind, TransportRequest,
+    TransportResponse,
+};
+use esc_client_base::{
+    Client, ClientBuilder, HttpConfig, RequestObserver, RequestSender, RetryPolicy,
+};
+use reqwest::{Method, StatusCode};
+use serde_derive::Deserialize;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct Cluster {
+    id: String,
+}
+
+fn token(access_token: &str) -> Token {
+    serde_json::from_value(serde_json::json!({
+        "access_token": access_token,
+        "refresh_token": "refresh",
+        "scope": "cloud:access",
+        "expires_in": 3600,
+        "token_type": "Bearer",
+    }))
+    .unwrap()
+}
+
+fn client(transport: Arc<ScriptedTransport>, retry_policy: RetryPolicy) -> Client {
+    Client {
+        authorization: Arc::new(StaticTokenAuthorizer {
+            token: token("access"),
+        }),
+        base_url: "https://api.example.com".to_string(),
+        sender: RequestSender::new(transport, None).retry_policy(retry_policy),
+    }
+}
+
+async fn get_cluster(client: &Client) -> esc_client_base::Result<Cluster> {
+    client
+        .send_request::<(), Cluster>(Method::GET, "/clusters/1".to_string(), None, None)
+        .await
+}
+
+#[tokio::test]
+async fn test_transient_failures_are_retried() {
+    let transport = Arc::new(
+        ScriptedTransport::new()
+            .fail(TransportErrorKind::Connect, "connection refused")
+            .respond(StatusCode::SERVICE_UNAVAILABLE, "")
+            .respond(StatusCode::OK, r#"{"id": "1"}"#),
+    );
+    let mut retry_policy = RetryPolicy::with_retries(2);
+    retry_policy.initial_backoff = Duration::from_millis(1);
+    let client = client(transport.clone(), retry_policy);
+
+    let cluster = get_cluster(&client).await.unwrap();
+
+    assert_eq!("1", cluster.id);
+    let requests = transport.requests();
+    assert_eq!(3, requests.len());
+    assert_eq!("https://api.example.com/clusters/1", requests[2].url);
+    assert_eq!("Bearer access", requests[2].headers["Authorization"]);
+}
+
+#[tokio::test]
+async fn test_retry_after_is_capped_by_the_maximum_backoff() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::RETRY_AFTER,
+        reqwest::header::HeaderValue::from_static("86400"),
+    );
+    let transport = Arc::new(
+        ScriptedTransport::new()
+            .then(Ok(TransportResponse {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                headers,
+                body: String::new(),
+            }))
+            .respond(StatusCode::OK, r#"{"id": "1"}"#),
+    );
+    let mut retry_policy = RetryPolicy::with_retries(1);
+    retry_policy.max_backoff = Duration::from_millis(10);
+    let client = client(transport.clone(), retry_policy.clone());
+
+    let cluster = tokio::time::timeout(Duration::from_secs(5), get_cluster(&client))
+        .await
+        .expect("the Retry-After delay wasn't capped")
+        .unwrap();
+
+    assert_eq!("1", cluster.id);
+    assert!(transport.is_done());
+    assert_eq!(
+        Duration::from_millis(10),
+        retry_policy.backoff(1, Some(Duration::from_secs(86400)))
+    );
+}
+
+#[tokio::test]
+async fn test_timeouts_are_reported_as_such() {
+    let transport =
+        Arc::new(ScriptedTransport::new().fail(TransportErrorKind::Timeout, "took too long"));
+    let client = client(transport, RetryPolicy::none());
+
+    let err = get_cluster(&client).await.unwrap_err();
+
+    assert!(err.is_timeout());
+}
+
+#[tokio::test]
+async fn test_connect_timeouts_are_reported_as_timeouts() {
+    // Nothing accepts on this socket, so once its backlog is full further
+    // connections are never answered.
+    let socket = tokio::net::TcpSocket::new_v4().unwrap();
+    socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let listener = socket.listen(0).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut backlog = Vec::new();
+    for _ in 0..4 {
+        let connect = tokio::net::TcpStream::connect(addr);
+        if let Ok(Ok(stream)) = tokio::time::timeout(Duration::from_millis(200), connect).await {
+            backlog.push(stream);
+        }
+    }
+    let transport = HttpConfig::default()
+        .connect_timeout(Duration::from_millis(100))
+        .build_client()
+        .unwrap();
+
+    let err = transport
+        .send(TransportRequest::new(
+            Method::POST,
+            format!("http://{}/clusters", addr),
+        ))
+        .await
+        .unwrap_err();
+
+    assert_eq!(TransportErrorKind::Timeout, err.kind, "{}", err);
+}
+
+#[tokio::test]
+async fn test_access_token_is_refreshed_after_401() {
+    let transport = Arc::new(
+        ScriptedTransport::new()
+            .respond(StatusCode::UNAUTHORIZED, "")
+            .respond_json(
+                StatusCode::OK,
+                &serde_json::json!({
+                    "access_token": "new-access",
+                    "scope": "cloud:access",
+                    "expires_in": 3600,
+                    "token_type": "Bearer",
+                }),
+            )
+            .respond(StatusCode::OK, r#"{"id": "1"}"#),
+    );
+    let config = TokenConfig {
+        identity_url: "https://identity.example.com".to_string(),
+        ..TokenConfig::default()
+    };
+    let client = Client {
+        authorization: Arc::new(RefreshTokenAuthorizer::new(
+            transport.clone(),
+            config,
+            "refresh".to_string(),
+            token("expired"),
+        )),
+        base_url: "https://api.example.com".to_string(),
+        sender: RequestSender::new(transport.clone(), None),
+    };
+
+    get_cluster(&client).await.unwrap();
+
+    let requests = transport.requests();
+    assert_eq!("https://identity.example.com/oauth/token", requests[1].url);
+    assert!(requests[1]
+        .body
+        .as_deref()
+        .unwrap()
+        .contains("grant_type=refresh_token"));
+    assert_eq!("Bearer new-access", requests[2].headers["Authorization"]);
+    assert!(transport.is_done());
+}
+
+#[tokio::test]
+async fn test_cassette_replays_what_was_recorded() {
+    let path = std::env::temp_dir().join(format!("esc-cassette-{}.json", std::process::id()));
+    let recorded = Arc::new(CassetteTransport::record(
+        Arc::new(ScriptedTransport::new().respond(StatusCode::OK, r#"{"id": "1"}"#)),
+        &path,
+    ));
+    let client = Client {
+        sender: RequestSender::new(recorded, None),
+        ..client(Arc::new(ScriptedTransport::new()), RetryPolicy::none())
+    };
+    let original = get_cluster(&client).await.unwrap();
+
+    let replayed = Arc::new(CassetteTransport::replay(&path).unwrap());
+    let client = Client {
+        sender: RequestSender::new(replayed, None),
+        ..client
+    };
+    let replay = get_cluster(&client).await.unwrap();
+    let exhausted = get_cluster(&client).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(original, replay);
+    assert!(exhausted.is_err());
+}
+
+#[tokio::test]
+async fn test_cassette_leaves_out_tokens() {
+    let path =
+        std::env::temp_dir().join(format!("esc-cassette-tokens-{}.json", std::process::id()));
+    let transport = Arc::new(CassetteTransport::record(
+        Arc::new(
+            ScriptedTransport::new()
+                .respond(StatusCode::UNAUTHORIZED, "")
+                .respond_json(
+                    StatusCode::OK,
+                    &serde_json::json!({
+                        "access_token": "new-access",
+                        "refresh_token": "new-refresh",
+                        "id_token": "new-id",
+                        "scope": "cloud:access",
+                        "expires_in": 3600,
+                        "token_type": "Bearer",
+                    }),
+                )
+                .respond(StatusCode::OK, r#"{"id": "1"}"#),
+        ),
+        &path,
+    ));
+    let config = TokenConfig {
+        identity_url: "https://identity.example.com".to_string(),
+        ..TokenConfig::default()
+    };
+    let client = Client {
+        authorization: Arc::new(RefreshTokenAuthorizer::new(
+            transport.clone(),
+            config,
+            "refresh".to_string(),
+            token("expired"),
+        )),
+        base_url: "https://api.example.com".to_string(),
+        sender: RequestSender::new(transport, None),
+    };
+
+    let cluster = get_cluster(&client).await.unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!("1", cluster.id);
+    assert!(saved.contains("https://identity.example.com/oauth/token"));
+    assert!(saved.contains("cloud:access"));
+    for secret in &["new-access", "new-refresh", "new-id"] {
+        assert!(!saved.contains(secret), "{} was saved", secret);
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    requests: Mutex<Vec<RequestEvent>>,
+    responses: Mutex<Vec<ResponseEvent>>,
+}
+
+impl RequestObserver for Recorder {
+    fn on_request(&self, event: &RequestEvent) {
+        self.requests.lock().unwrap().push(event.clone());
+    }
+
+    fn on_response(&self, event: &ResponseEvent) {
+        self.responses.lock().unwrap().push(event.clone());
+    }
+
+    fn on_failure(&self, _event: &FailureEvent) {}
+}
+
+#[tokio::test]
+async fn test_observer_gets_one_event_per_request_and_response() {
+    let transport = Arc::new(
+        ScriptedTransport::new()
+            .respond(StatusCode::BAD_GATEWAY, "")
+            .respond(StatusCode::OK, r#"{"id": "1"}"#),
+    );
+    let recorder = Arc::new(Recorder::default());
+    let mut retry_policy = RetryPolicy::with_retries(1);
+    retry_policy.initial_backoff = Duration::from_millis(1);
+    let client = Client {
+        sender: RequestSender::new(transport.clone(), Some(recorder.clone()))
+            .retry_policy(retry_policy.clone()),
+        ..client(transport, retry_policy)
+    };
+
+    get_cluster(&client).await.unwrap();
+
+    let requests = recorder.requests.lock().unwrap();
+    let responses = recorder.responses.lock().unwrap();
+    assert_eq!(1, requests.len());
+    assert_eq!(1, responses.len());
+    assert_eq!(requests[0].request_id, responses[0].request_id);
+    assert_eq!("Bearer [redacted]", requests[0].headers["authorization"]);
+    assert_eq!(StatusCode::OK, responses[0].status);
+    assert_eq!(11, responses[0].size);
+    assert_eq!(2, responses[0].attempts);
+}
+
+#[tokio::test]
+async fn test_builder_gets_a_token_for_the_refresh_token() {
+    let transport = Arc::new(
+        ScriptedTransport::new()
+            .respond_json(
+                StatusCode::OK,
+                &serde_json::json!({
+                    "access_token": "access",
+                    "scope": "cloud:access",
+                    "expires_in": 3600,
+                    "token_type": "Bearer",
+                }),
+            )
+            .respond(StatusCode::OK, r#"{"id": "1"}"#),
+    );
+    let client = ClientBuilder::new()
+        .base_url("https://api.example.com/")
+        .token_config(TokenConfig {
+            identity_url: "https://identity.example.com".to_string(),
+            ..TokenConfig::default()
+        })
+        .refresh_token("refresh")
+        .transport(transport.clone())
+        .build()
+        .await
+        .unwrap();
+
+    get_cluster(&client).await.unwrap();
+
+    let requests = transport.requests();
+    assert_eq!("https://identity.example.com/oauth/token", requests[0].url);
+    assert_eq!("https://api.example.com/clusters/1", requests[1].url);
+    assert_eq!("Bearer access", requests[1].headers["Authorization"]);
+}
+
+#[tokio::test]
+async fn test_builder_needs_a_token_source() {
+    let result = ClientBuilder::new()
+        .transport(Arc::new(ScriptedTransport::new()))
+        .build()
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,123 @@
+//! The settings of an organization, by the names the CLI knows them by, with
+//! the values each one accepts so mistakes are caught before anything is
+//! sent.
+
+use std::collections::BTreeMap;
+
+use esc_api::access::{Settings, UpdateSettingsRequest};
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    RequireMfa,
+    RestrictInviteDomain,
+}
+
+impl Setting {
+    pub const ALL: &'static [Setting] = &[Setting::RequireMfa, Setting::RestrictInviteDomain];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::RequireMfa => "require-mfa",
+            Setting::RestrictInviteDomain => "restrict-invite-domain",
+        }
+    }
+
+    pub fn get(self, settings: &Settings) -> Value {
+        match self {
+            Setting::RequireMfa => Value::Bool(settings.require_mfa),
+            Setting::RestrictInviteDomain => Value::String(settings.restrict_invite_domain.clone()),
+        }
+    }
+
+    /// The request which sets this setting, and only this one, to `value`.
+    pub fn update(self, value: &str) -> Result<UpdateSettingsRequest, String> {
+        let mut request = UpdateSettingsRequest {
+            require_mfa: None,
+            restrict_invite_domain: None,
+        };
+        match self {
+            Setting::RequireMfa => {
+                request.require_mfa = Some(match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => {
+                        return Err(format!(
+                            "invalid value for {}: {}, expected true or false",
+                            self, value
+                        ))
+                    }
+                })
+            }
+            Setting::RestrictInviteDomain => {
+                request.restrict_invite_domain = Some(
+                    parse_domain(value)
+                        .map_err(|err| format!("invalid value for {}: {}, {}", self, value, err))?,
+                )
+            }
+        }
+        Ok(request)
+    }
+}
+
+impl std::str::FromStr for Setting {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Setting::ALL
+            .iter()
+            .find(|setting| setting.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Setting::ALL.iter().map(|s| s.name()).collect();
+                format!(
+                    "unknown setting: {}, expected one of {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for Setting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What `access settings get` shows: the value of the setting asked for,
+/// or all of them by name.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum Shown {
+    One(Value),
+    All(BTreeMap<&'static str, Value>),
+}
+
+/// All the settings, by name.
+pub fn dump(settings: &Settings) -> BTreeMap<&'static str, Value> {
+    Setting::ALL
+        .iter()
+        .map(|setting| (setting.name(), setting.get(settings)))
+        .collect()
+}
+
+/// Accepts a domain name such as example.com, or nothing to lift the
+/// restriction.
+fn parse_domain(value: &str) -> Result<String, &'static str> {
+    if value.is_empty() {
+        return Ok(String::new());
+    }
+    let domain = value.to_lowercase();
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_label = |label: &&str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if domain.len() > 253 || labels.len() < 2 || !labels.iter().all(valid_label) {
+        return Err("expected a domain name such as example.com, or an empty value for none");
+    }
+    Ok(domain)
+}
@@ -0,0 +1,527 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use esc_api::mesdb::{self, Backup, Cluster, ClusterStatus};
+use esc_api::resources::ProjectId;
+use esc_api::{BackupId, Client, ClusterId, OrgId};
+use structopt::StructOpt;
+
+use crate::confirm;
+use crate::versions;
+use crate::wait::{self, ClusterTarget};
+
+#[derive(Debug, StructOpt)]
+pub struct ClusterSelector {
+    #[structopt(
+        long,
+        help = "Selects every cluster of the project, instead of the one given by --id"
+    )]
+    pub all: bool,
+
+    #[structopt(
+        long,
+        help = "Selects the clusters whose description matches the glob, where * matches any text and ? one character"
+    )]
+    pub description_glob: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Selects the clusters with the status, such as available or stopping"
+    )]
+    pub status: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Selects the clusters with the health, such as ok or degraded"
+    )]
+    pub health: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Selects the clusters running the server version, such as 22.10, or the exact tag, such as 22.10.1"
+    )]
+    pub server_version: Option<String>,
+
+    #[structopt(flatten)]
+    pub bulk: BulkOpts,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BackupSelector {
+    #[structopt(
+        long,
+        help = "Selects every backup of the project, instead of the one given by --id"
+    )]
+    pub all: bool,
+
+    #[structopt(
+        long,
+        help = "Selects the backups whose description matches the glob, where * matches any text and ? one character"
+    )]
+    pub description_glob: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Selects the backups with the status, such as available or defunct"
+    )]
+    pub status: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Selects the backups of the server version, such as 22.10, or the exact tag, such as 22.10.1"
+    )]
+    pub server_version: Option<String>,
+
+    #[structopt(flatten)]
+    pub bulk: BulkOpts,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BulkOpts {
+    #[structopt(
+        long,
+        default_value = "4",
+        help = "How many of the selected resources are worked on at once"
+    )]
+    pub concurrency: usize,
+
+    #[structopt(long, help = "Only shows which resources the selectors pick")]
+    pub dry_run: bool,
+}
+
+/// Names of the selector arguments, for the `--id` arguments they replace.
+pub const CLUSTER_SELECTORS: &[&str] = &[
+    "all",
+    "description-glob",
+    "status",
+    "health",
+    "server-version",
+];
+pub const BACKUP_SELECTORS: &[&str] = &["all", "description-glob", "status", "server-version"];
+
+/// What the selectors of either kind of resource have in common.
+struct Filter<'a> {
+    description_glob: Option<&'a str>,
+    status: Option<&'a str>,
+    health: Option<&'a str>,
+    server_version: Option<&'a str>,
+}
+
+impl Filter<'_> {
+    fn matches(
+        &self,
+        description: &str,
+        status: &str,
+        health: Option<&str>,
+        server_version: &str,
+        server_version_tag: &str,
+    ) -> bool {
+        let same = |wanted: Option<&str>, actual: Option<&str>| match (wanted, actual) {
+            (None, _) => true,
+            (Some(wanted), Some(actual)) => normalize(wanted) == normalize(actual),
+            (Some(_), None) => false,
+        };
+        self.description_glob
+            .map_or(true, |glob| glob_matches(glob, description))
+            && same(self.status, Some(status))
+            && same(self.health, health)
+            && self.server_version.map_or(true, |version| {
+                version == server_version || version == server_version_tag
+            })
+    }
+}
+
+impl ClusterSelector {
+    fn filter(&self) -> Filter {
+        Filter {
+            description_glob: self.description_glob.as_deref(),
+            status: self.status.as_deref(),
+            health: self.health.as_deref(),
+            server_version: self.server_version.as_deref(),
+        }
+    }
+
+    pub fn matches(&self, cluster: &Cluster) -> bool {
+        self.filter().matches(
+            &cluster.description,
+            &cluster.status.to_string(),
+            Some(&cluster.health.to_string()),
+            &cluster.server_version,
+            &cluster.server_version_tag,
+        )
+    }
+}
+
+impl BackupSelector {
+    pub fn matches(&self, backup: &Backup) -> bool {
+        let filter = Filter {
+            description_glob: self.description_glob.as_deref(),
+            status: self.status.as_deref(),
+            health: None,
+            server_version: self.server_version.as_deref(),
+        };
+        filter.matches(
+            &backup.description,
+            &backup.status.to_string(),
+            None,
+            &backup.server_version,
+            &backup.server_version_tag,
+        )
+    }
+}
+
+/// Statuses are written in several ways, such as "disks available" and
+/// "disksAvailable".
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Matches text with a glob where `*` is any text and `?` any character.
+pub fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Where the last star was, and the text it has matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, t));
+                g += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_g, star_t)) => {
+                    g = star_g + 1;
+                    t = star_t + 1;
+                    star = Some((star_g, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|c| *c == '*')
+}
+
+/// A selected resource.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub id: String,
+    pub description: String,
+    pub status: String,
+    pub protected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Outcome {
+    pub id: String,
+    pub description: String,
+    pub succeeded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What happened to each of the selected resources.
+#[derive(Serialize)]
+pub struct Summary {
+    pub results: Vec<Outcome>,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl std::fmt::Debug for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for outcome in &self.results {
+            write!(f, "{} {:?}", outcome.id, outcome.description)?;
+            match &outcome.error {
+                None => writeln!(f, ": done")?,
+                Some(err) => writeln!(f, ": failed: {}", err)?,
+            }
+        }
+        write!(f, "{} succeeded, {} failed", self.succeeded, self.failed)
+    }
+}
+
+impl Summary {
+    /// The error of a bulk command where some operations failed, for after
+    /// the summary is shown.
+    pub fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.failed > 0 {
+            return Err(format!(
+                "{} of {} operations failed",
+                self.failed,
+                self.results.len()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Lists the selected resources to stderr before they're worked on.
+pub fn preview(kind: &str, targets: &[Target]) {
+    if targets.is_empty() {
+        eprintln!("No {}s match the selectors", kind);
+        return;
+    }
+    eprintln!("Selected {} {}(s):", targets.len(), kind);
+    for target in targets {
+        eprintln!(
+            "  {}  {:?}  {}",
+            target.id, target.description, target.status
+        );
+    }
+}
+
+/// Runs the operation on every target, at most `concurrency` at a time.
+pub async fn run<F, Fut>(targets: Vec<Target>, concurrency: usize, operation: F) -> Summary
+where
+    F: Fn(Target) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>> + Send,
+{
+    let queue = Arc::new(Mutex::new(
+        targets.into_iter().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let operation = Arc::new(operation);
+    let workers: Vec<_> = (0..concurrency.max(1))
+        .map(|_| {
+            let (queue, operation) = (queue.clone(), operation.clone());
+            tokio::spawn(async move {
+                let mut outcomes = Vec::new();
+                loop {
+                    let next = queue.lock().unwrap().pop_front();
+                    let (index, target) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    let result = operation(target.clone()).await;
+                    outcomes.push((
+                        index,
+                        Outcome {
+                            id: target.id,
+                            description: target.description,
+                            succeeded: result.is_ok(),
+                            error: result.err(),
+                        },
+                    ));
+                }
+                outcomes
+            })
+        })
+        .collect();
+    let mut outcomes = Vec::new();
+    for worker in workers {
+        outcomes.extend(worker.await.expect("bulk worker panicked"));
+    }
+    outcomes.sort_by_key(|(index, _)| *index);
+    let results: Vec<Outcome> = outcomes.into_iter().map(|(_, outcome)| outcome).collect();
+    let succeeded = results.iter().filter(|outcome| outcome.succeeded).count();
+    Summary {
+        failed: results.len() - succeeded,
+        succeeded,
+        results,
+    }
+}
+
+/// What bulk commands do to clusters.
+#[derive(Debug, Clone)]
+pub enum ClusterOperation {
+    Stop,
+    Start,
+    Delete,
+    Upgrade {
+        /// The tag each cluster goes to, by id, or why it can't be upgraded.
+        tags: BTreeMap<String, Result<String, String>>,
+    },
+    Update {
+        acl_id: Option<String>,
+        protected: Option<bool>,
+    },
+}
+
+pub async fn select_clusters(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    selector: &ClusterSelector,
+) -> Result<Vec<Target>, esc_api::Error> {
+    Ok(mesdb::list_clusters(client, org_id, project_id)
+        .await?
+        .clusters
+        .into_iter()
+        .filter(|cluster| selector.matches(cluster))
+        .map(|cluster| Target {
+            id: cluster.id.0,
+            description: cluster.description,
+            status: cluster.status.to_string(),
+            protected: cluster.protected,
+        })
+        .collect())
+}
+
+pub async fn select_backups(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    selector: &BackupSelector,
+) -> Result<Vec<Target>, esc_api::Error> {
+    Ok(mesdb::list_backups(client, org_id, project_id)
+        .await?
+        .backups
+        .into_iter()
+        .filter(|backup| selector.matches(backup))
+        .map(|backup| Target {
+            id: backup.id.0,
+            description: backup.description,
+            status: backup.status.to_string(),
+            protected: false,
+        })
+        .collect())
+}
+
+/// Works out what each of the clusters goes to before any is upgraded, so
+/// that all the warnings can be confirmed at once. A cluster which can't be
+/// upgraded only fails on its own.
+pub async fn resolve_upgrades(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    targets: &[Target],
+    target: &versions::Target,
+    allowed: versions::Allowed,
+) -> BTreeMap<String, Result<versions::Upgrade, String>> {
+    let mut upgrades = BTreeMap::new();
+    for cluster in targets {
+        let upgrade = versions::resolve(
+            client,
+            org_id.clone(),
+            project_id.clone(),
+            ClusterId(cluster.id.clone()),
+            target,
+            allowed,
+        )
+        .await
+        .map_err(|err| err.to_string());
+        upgrades.insert(cluster.id.clone(), upgrade);
+    }
+    upgrades
+}
+
+/// Runs the operation on the clusters, waiting for each to be done if a
+/// timeout is given. The client shouldn't print responses.
+pub async fn clusters(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    targets: Vec<Target>,
+    concurrency: usize,
+    operation: ClusterOperation,
+    wait_timeout: Option<Duration>,
+) -> Summary {
+    let client = client.clone();
+    run(targets, concurrency, move |target| {
+        let (client, org_id, project_id, operation) = (
+            client.clone(),
+            org_id.clone(),
+            project_id.clone(),
+            operation.clone(),
+        );
+        async move {
+            let id = ClusterId(target.id);
+            let (org, project) = (org_id.clone(), project_id.clone());
+            let target = match operation {
+                ClusterOperation::Stop => {
+                    mesdb::stop_cluster(&client, org, project, id.clone())
+                        .await
+                        .map(|_| ())
+                        .map_err(|err| err.to_string())?;
+                    ClusterTarget::Status(ClusterStatus::DisksAvailable)
+                }
+                ClusterOperation::Start => {
+                    mesdb::start_cluster(&client, org, project, id.clone())
+                        .await
+                        .map(|_| ())
+                        .map_err(|err| err.to_string())?;
+                    ClusterTarget::Status(ClusterStatus::Available)
+                }
+                ClusterOperation::Delete => {
+                    if target.protected {
+                        return Err(confirm::protected(&id.0));
+                    }
+                    mesdb::delete_cluster(&client, org, project, id.clone())
+                        .await
+                        .map_err(|err| err.to_string())?;
+                    ClusterTarget::Deleted
+                }
+                ClusterOperation::Upgrade { tags } => {
+                    let target_tag = tags[&id.0].clone()?;
+                    mesdb::upgrade_cluster(
+                        &client,
+                        org,
+                        project,
+                        id.clone(),
+                        mesdb::UpgradeClusterRequest {
+                            target_tag: target_tag.clone(),
+                        },
+                    )
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())?;
+                    ClusterTarget::ServerVersionTag(target_tag)
+                }
+                ClusterOperation::Update { acl_id, protected } => {
+                    mesdb::update_cluster(
+                        &client,
+                        org,
+                        project,
+                        id.clone(),
+                        mesdb::UpdateClusterRequest {
+                            acl_id,
+                            description: None,
+                            protected,
+                        },
+                    )
+                    .await
+                    .map_err(|err| err.to_string())?;
+                    ClusterTarget::Status(ClusterStatus::Available)
+                }
+            };
+            if let Some(timeout) = wait_timeout {
+                wait::wait_for_cluster(&client, org_id, project_id, id, target, timeout)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok(())
+        }
+    })
+    .await
+}
+
+/// Deletes the backups. The client shouldn't print responses.
+pub async fn delete_backups(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    targets: Vec<Target>,
+    concurrency: usize,
+) -> Summary {
+    let client = client.clone();
+    run(targets, concurrency, move |target| {
+        let (client, org_id, project_id) = (client.clone(), org_id.clone(), project_id.clone());
+        async move {
+            mesdb::delete_backup(&client, org_id, project_id, BackupId(target.id))
+                .await
+                .map_err(|err| err.to_string())
+        }
+    })
+    .await
+}
@@ -0,0 +1,214 @@
+use esc_api::mesdb::{self, Cluster, ClusterStatus};
+use esc_api::orchestrate::{self, JobData};
+use esc_api::resources::{self, ProjectId};
+use esc_api::{infra, BackupId, Client, ClusterId, OrgId};
+
+/// A resource about to be deleted, with what the user needs to know to be
+/// sure it's the right one.
+pub struct Deletion {
+    pub kind: &'static str,
+    pub id: String,
+    pub description: String,
+    pub created: Option<String>,
+    /// Resources which still rely on this one, such as the clusters of a
+    /// network.
+    pub dependents: Vec<String>,
+}
+
+impl Deletion {
+    /// Shows the resource on stderr and asks whether to go on, unless `yes`
+    /// already said so. Prompting is refused in noninteractive mode.
+    pub fn confirm(&self, yes: bool, noninteractive: bool) -> Result<(), String> {
+        eprintln!("About to delete {} {}", self.kind, self.id);
+        eprintln!("  description: {}", self.description);
+        if let Some(created) = &self.created {
+            eprintln!("  created:     {}", created);
+        }
+        if !self.dependents.is_empty() {
+            eprintln!("  still used by:");
+            for dependent in &self.dependents {
+                eprintln!("    {}", dependent);
+            }
+        }
+        let what = format!("{} {}", self.kind, self.id);
+        ask(&what, yes, noninteractive)
+    }
+}
+
+/// Asks whether to delete the resources described by `what`.
+pub fn ask(what: &str, yes: bool, noninteractive: bool) -> Result<(), String> {
+    proceed(&format!("delete {}", what), yes, noninteractive)
+}
+
+/// Asks whether to go on with `action`, such as "delete cluster abc".
+pub fn proceed(action: &str, yes: bool, noninteractive: bool) -> Result<(), String> {
+    if yes {
+        return Ok(());
+    }
+    if noninteractive {
+        return Err(format!(
+            "Refusing to {} without --yes in --noninteractive mode",
+            action
+        ));
+    }
+    let mut editor = rustyline::Editor::<()>::new();
+    let answer = editor
+        .readline(&format!("Go on and {}? [y/N] ", action))
+        .map_err(|err| format!("Error reading line interactively: {}", err))?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err("Cancelled".to_string()),
+    }
+}
+
+/// The refusal to delete a protected cluster.
+pub fn protected(cluster_id: &str) -> String {
+    format!(
+        "Cluster {} is protected from deletion. If it really should go, turn the protection off first with `esc mesdb clusters update --id {} --protected false`",
+        cluster_id, cluster_id
+    )
+}
+
+fn describe(kind: &str, id: &str, description: &str) -> String {
+    format!("{} {} {:?}", kind, id, description)
+}
+
+fn is_deleted(cluster: &Cluster) -> bool {
+    cluster.status == ClusterStatus::Deleted
+}
+
+pub async fn cluster(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    cluster_id: ClusterId,
+) -> Result<Deletion, Box<dyn std::error::Error>> {
+    let cluster = mesdb::get_cluster(client, org_id.clone(), project_id.clone(), cluster_id)
+        .await?
+        .cluster;
+    if cluster.protected {
+        return Err(protected(&cluster.id.0).into());
+    }
+    let dependents = orchestrate::list_jobs(client, org_id, project_id)
+        .await?
+        .jobs
+        .into_iter()
+        .filter(|job| match &job.data {
+            JobData::ScheduledBackup(data) => data.cluster_id == cluster.id,
+        })
+        .map(|job| describe("backup job", &job.id.0, &job.description))
+        .collect();
+    Ok(Deletion {
+        kind: "cluster",
+        id: cluster.id.0,
+        description: cluster.description,
+        created: Some(cluster.created.to_rfc3339()),
+        dependents,
+    })
+}
+
+pub async fn network(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    network_id: infra::NetworkId,
+) -> Result<Deletion, Box<dyn std::error::Error>> {
+    let network = infra::get_network(client, org_id.clone(), project_id.clone(), network_id)
+        .await?
+        .network;
+    let mut dependents: Vec<String> =
+        mesdb::list_clusters(client, org_id.clone(), project_id.clone())
+            .await?
+            .clusters
+            .into_iter()
+            .filter(|cluster| cluster.network_id == network.id && !is_deleted(cluster))
+            .map(|cluster| describe("cluster", &cluster.id.0, &cluster.description))
+            .collect();
+    dependents.extend(
+        infra::list_peerings(client, org_id, project_id)
+            .await?
+            .peerings
+            .into_iter()
+            .filter(|peering| peering.network_id == network.id)
+            .map(|peering| describe("peering", &peering.id.0, &peering.description)),
+    );
+    Ok(Deletion {
+        kind: "network",
+        id: network.id.0,
+        description: network.description,
+        created: Some(network.created),
+        dependents,
+    })
+}
+
+pub async fn backup(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    backup_id: BackupId,
+) -> Result<Deletion, Box<dyn std::error::Error>> {
+    let backup = mesdb::get_backup(client, org_id, project_id, backup_id)
+        .await?
+        .backup;
+    Ok(Deletion {
+        kind: "backup",
+        id: backup.id.0,
+        description: backup.description,
+        created: Some(backup.created.to_rfc3339()),
+        dependents: Vec::new(),
+    })
+}
+
+pub async fn project(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+) -> Result<Deletion, Box<dyn std::error::Error>> {
+    let project = resources::get_project(client, org_id.clone(), project_id)
+        .await?
+        .project;
+    let mut dependents: Vec<String> =
+        infra::list_networks(client, org_id.clone(), project.id.clone())
+            .await?
+            .networks
+            .into_iter()
+            .map(|network| describe("network", &network.id.0, &network.description))
+            .collect();
+    dependents.extend(
+        mesdb::list_clusters(client, org_id, project.id.clone())
+            .await?
+            .clusters
+            .into_iter()
+            .filter(|cluster| !is_deleted(cluster))
+            .map(|cluster| describe("cluster", &cluster.id.0, &cluster.description)),
+    );
+    Ok(Deletion {
+        kind: "project",
+        id: project.id.0,
+        description: project.name,
+        created: Some(project.created.to_rfc3339()),
+        dependents,
+    })
+}
+
+pub async fn organization(
+    client: &Client,
+    org_id: OrgId,
+) -> Result<Deletion, Box<dyn std::error::Error>> {
+    let organization = resources::get_organization(client, org_id.clone())
+        .await?
+        .organization;
+    let dependents = resources::list_projects(client, org_id)
+        .await?
+        .projects
+        .into_iter()
+        .map(|project| describe("project", &project.id.0, &project.name))
+        .collect();
+    Ok(Deletion {
+        kind: "organization",
+        id: organization.id.0,
+        description: organization.name,
+        created: Some(organization.created.to_rfc3339()),
+        dependents,
+    })
+}
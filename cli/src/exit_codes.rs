@@ -0,0 +1,69 @@
+//! Exit codes of the `esc` binary. These are documented in the README and
+//! scripts depend on them, so existing values must never change.
+
+use esc_api::{ErrorKind, WaitError};
+use esc_client_base::transport::TransportError;
+
+pub const FAILURE: i32 = 1;
+/// `esc plan` found differences between the project and the spec.
+pub const DRIFT: i32 = 2;
+pub const VALIDATION: i32 = 10;
+pub const UNAUTHORIZED: i32 = 11;
+pub const FORBIDDEN: i32 = 12;
+pub const NOT_FOUND: i32 = 13;
+pub const CONFLICT: i32 = 14;
+pub const RATE_LIMITED: i32 = 15;
+pub const SERVER_ERROR: i32 = 20;
+pub const TRANSPORT: i32 = 21;
+pub const TIMEOUT: i32 = 22;
+pub const WAIT_TIMEOUT: i32 = 23;
+pub const WAIT_FAILED: i32 = 24;
+
+pub fn from_kind(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::Validation => VALIDATION,
+        ErrorKind::Unauthorized => UNAUTHORIZED,
+        ErrorKind::Forbidden => FORBIDDEN,
+        ErrorKind::NotFound => NOT_FOUND,
+        ErrorKind::Conflict => CONFLICT,
+        ErrorKind::RateLimited => RATE_LIMITED,
+        ErrorKind::ServerError => SERVER_ERROR,
+        ErrorKind::Transport => TRANSPORT,
+        ErrorKind::Timeout => TIMEOUT,
+        ErrorKind::OtherStatus => FAILURE,
+    }
+}
+
+/// The exit code for an error, going through what caused it until one of
+/// them tells the kind of failure. That way a timeout, for instance, exits
+/// the same way whether it happened talking to the API or to identity.
+pub fn from_error(err: &(dyn std::error::Error + 'static)) -> i32 {
+    let mut cause = Some(err);
+    while let Some(err) = cause {
+        if let Some(code) = classify(err) {
+            return code;
+        }
+        cause = err.source();
+    }
+    FAILURE
+}
+
+fn classify(err: &(dyn std::error::Error + 'static)) -> Option<i32> {
+    if let Some(err) = err.downcast_ref::<esc_api::Error>() {
+        return Some(from_kind(err.kind()));
+    }
+    if err.is::<crate::spec::plan::Drift>() {
+        return Some(DRIFT);
+    }
+    if let Some(err) = err.downcast_ref::<TransportError>() {
+        return Some(match err.is_timeout() {
+            true => TIMEOUT,
+            false => TRANSPORT,
+        });
+    }
+    match err.downcast_ref::<WaitError>()? {
+        WaitError::TimedOut { .. } => Some(WAIT_TIMEOUT),
+        WaitError::Failed { .. } => Some(WAIT_FAILED),
+        WaitError::Api(err) => Some(from_kind(err.kind())),
+    }
+}
@@ -0,0 +1,37 @@
+use esc_api::integrate::SourceSummary;
+
+/// The sources which can feed the sink, among those enabled.
+pub fn sources_for<'a>(options: &'a [SourceSummary], sink: &str) -> Vec<&'a str> {
+    options
+        .iter()
+        .filter(|source| source.enabled)
+        .filter(|source| {
+            source
+                .sinks
+                .iter()
+                .any(|candidate| candidate.enabled && candidate.id == sink)
+        })
+        .map(|source| source.id.as_str())
+        .collect()
+}
+
+/// Checks an integration from `source` (or the API's default one) to `sink`
+/// is enabled, so a bad combination fails before anything is sent.
+pub fn check(options: &[SourceSummary], sink: &str, source: Option<&str>) -> Result<(), String> {
+    let sources = sources_for(options, sink);
+    if sources.is_empty() {
+        return Err(format!(
+            "{} integrations aren't enabled for this organization",
+            sink
+        ));
+    }
+    match source {
+        Some(source) if !sources.contains(&source) => Err(format!(
+            "{} can't be sent to {}, valid --source values are: {}",
+            source,
+            sink,
+            sources.join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
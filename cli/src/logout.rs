@@ -0,0 +1,39 @@
+//! Ending the session on this machine. Deleting the local token file isn't
+//! enough, as a copy of the refresh token would still work, so it's revoked
+//! first.
+
+use esc_api::{Token, TokenConfig, Transport};
+use esc_client_base::identity::operations;
+
+/// The refresh token to revoke: the one given with `--refresh-token`, or
+/// else the one saved on this machine. Nothing else is a safe guess, as the
+/// user's other tokens may be in use elsewhere.
+pub fn token_to_revoke(given: Option<&str>, saved: Option<&Token>) -> Result<String, String> {
+    given
+        .or_else(|| saved.and_then(|token| token.refresh_token().map(String::as_str)))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            "There's no saved refresh token and none was given with --refresh-token, so there's nothing to revoke".to_string()
+        })
+}
+
+/// Revokes the refresh token, then deletes the saved token if it's the one
+/// which was revoked.
+pub async fn logout(
+    transport: &(dyn Transport + Send + Sync),
+    token_config: &TokenConfig,
+    store: &esc_client_store::TokenStore,
+    given: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let saved = store.show().await?;
+    let refresh_token = token_to_revoke(given, saved.as_ref())?;
+    operations::revoke(transport, token_config, &refresh_token).await?;
+    match saved.as_ref().and_then(Token::refresh_token) {
+        Some(saved) if *saved == refresh_token => {
+            store.remove().await?;
+        }
+        Some(_) => eprintln!("The token saved on this machine isn't the one revoked, so it's kept"),
+        None => {}
+    }
+    Ok(())
+}
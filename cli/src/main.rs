@@ -6,21 +6,38 @@ extern crate lazy_static;
 #[macro_use]
 extern crate serde_derive;
 
+mod access_settings;
+mod bulk;
 mod config;
+mod confirm;
 mod constants;
+mod exit_codes;
+mod integrations;
+mod logout;
 mod output;
+mod policies;
+mod request_log;
+mod restore;
+mod spec;
 mod utils;
 mod v1;
+mod versions;
+mod wait;
+mod watch;
 
 use cidr::Cidr;
 use esc_api::resources::MfaStatus;
 use esc_api::{GroupId, MemberId, OrgId};
 use output::OutputFormat;
+use request_log::{LogFormat, RequestLog};
 use serde::Serialize;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use structopt::StructOpt;
+use wait::ClusterTarget;
 
 #[derive(StructOpt, Debug)]
 #[structopt(
@@ -60,6 +77,87 @@ pub struct Opt {
     )]
     noninteractive: bool,
 
+    #[structopt(
+        long,
+        help = "How many times a request failing for a transient reason (rate limiting, bad gateway, dropped connection) is retried. Only idempotent requests are retried on server errors. Defaults to the profile's retries setting, or 0",
+        global = true
+    )]
+    retries: Option<u32>,
+
+    #[structopt(
+        long,
+        help = "Seconds after which a request to the API or identity is abandoned. Defaults to the profile's request-timeout setting, or no limit",
+        global = true
+    )]
+    request_timeout: Option<u64>,
+
+    #[structopt(
+        long,
+        help = "Seconds after which connecting to the API or identity is abandoned. Defaults to the profile's connect-timeout setting, or no limit",
+        global = true
+    )]
+    connect_timeout: Option<u64>,
+
+    #[structopt(
+        long,
+        help = "Logs every request to the API and its response, as text or json lines. Logs go to stderr unless --log-file is given",
+        global = true
+    )]
+    log_format: Option<LogFormat>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "File requests to the API and their responses are logged to",
+        global = true
+    )]
+    log_file: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Logs secrets in request and response bodies, such as integration tokens, instead of masking them. Only meant for debugging",
+        global = true
+    )]
+    no_redact: bool,
+
+    #[structopt(
+        long,
+        help = "URL of a proxy requests to the API and identity are sent through. Defaults to the profile's proxy setting",
+        global = true
+    )]
+    proxy: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Comma separated hosts, domains or CIDR blocks reached without going through the proxy. Defaults to the profile's no-proxy setting",
+        global = true
+    )]
+    no_proxy: Option<String>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "PEM file with root certificates to trust besides the system's. Defaults to the profile's ca-cert setting",
+        global = true
+    )]
+    ca_cert: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "PEM file with the client certificate to identify with, and its private key unless --client-key is given. Defaults to the profile's client-cert setting",
+        global = true
+    )]
+    client_cert: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "PEM file with the private key of the client certificate. Defaults to the profile's client-key setting",
+        global = true
+    )]
+    client_key: Option<PathBuf>,
+
     #[structopt(subcommand)]
     cmd: Command,
 }
@@ -74,6 +172,13 @@ enum Command {
     Profiles(Profiles),
     Mesdb(Mesdb),
     Orchestrate(Orchestrate),
+    Apply(ApplySpec),
+    Plan(PlanSpec),
+    Export(ExportSpec),
+    #[structopt(
+        about = "Revokes the refresh token saved on this machine, or the one given with --refresh-token, and deletes the saved token if it's the one revoked"
+    )]
+    Logout,
     #[structopt(about = "Prints Bash completion script in STDOUT")]
     GenerateBashCompletion,
     #[structopt(about = "Prints Zsh completion script in STDOUT")]
@@ -82,6 +187,73 @@ enum Command {
     GeneratePowershellCompletion,
 }
 
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Makes the networks, ACLs, peerings, clusters, backup jobs and integrations of a project match a spec file"
+)]
+struct ApplySpec {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "An organization's id")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "An project id that belongs to an organization pointed by --org-id")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "The spec file, in TOML or, if its name ends with .yaml or .yml, in YAML"
+    )]
+    file: PathBuf,
+
+    #[structopt(
+        long,
+        help = "Deletes the resources which aren't in the spec, and replaces those which can't be changed in place"
+    )]
+    prune: bool,
+
+    #[structopt(
+        long,
+        help = "Seconds to wait for each resource to be ready before giving up. Defaults to 30 minutes"
+    )]
+    timeout: Option<u64>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Shows how the resources of a project differ from a spec file, without changing anything. Exits with 2 if they differ"
+)]
+struct PlanSpec {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "An organization's id")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "An project id that belongs to an organization pointed by --org-id")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "The spec file, in TOML or, if its name ends with .yaml or .yml, in YAML"
+    )]
+    file: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Prints a spec file describing the networks, ACLs, peerings, clusters, backup jobs and integrations of a project"
+)]
+struct ExportSpec {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "An organization's id")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "An project id that belongs to an organization pointed by --org-id")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(long, help = "Prints the spec in YAML instead of TOML")]
+    yaml: bool,
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(
     about = "Gathers tokens, groups, members, invites, policies and settings management commands"
@@ -98,6 +270,7 @@ enum AccessCommand {
     Invites(Invites),
     Policies(Policies),
     Members(Members),
+    Settings(AccessSettings),
 }
 
 #[derive(Debug, StructOpt)]
@@ -186,6 +359,8 @@ struct Tokens {
 enum TokensCommand {
     Create(CreateToken),
     Display(Display),
+    List(ListTokens),
+    Revoke(RevokeToken),
 }
 
 #[derive(StructOpt, Debug)]
@@ -205,6 +380,19 @@ struct CreateToken {
 #[structopt(about = "Display your current refresh token")]
 struct Display {}
 
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "List your refresh tokens, with the client they were issued to and when they were last used"
+)]
+struct ListTokens {}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Revoke one of your refresh tokens")]
+struct RevokeToken {
+    #[structopt(long, parse(try_from_str = parse_token_id), help = "Id of the refresh token, as listed by `esc access tokens list`")]
+    id: esc_api::access::TokenId,
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(about = "Gathers groups management commands")]
 struct Groups {
@@ -246,6 +434,7 @@ enum PoliciesCommand {
     Get(GetPolicy),
     Delete(DeletePolicy),
     List(ListPolicies),
+    Check(CheckPolicy),
 }
 
 #[derive(StructOpt, Debug)]
@@ -306,6 +495,25 @@ struct ListPolicies {
     org_id: OrgId,
 }
 
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Check whether an action on a resource is allowed, and show the policies which decide it"
+)]
+struct CheckPolicy {
+    #[structopt(long, short, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the policies relate to")]
+    org_id: OrgId,
+    #[structopt(long, short, help = "The action: create, read, modify or delete")]
+    action: String,
+    #[structopt(long, short, help = "The resource the action is on")]
+    resource: String,
+    #[structopt(
+        long,
+        short,
+        help = "A member's email or id to check for, instead of yourself. The policies are then evaluated locally"
+    )]
+    subject: Option<String>,
+}
+
 #[derive(StructOpt, Debug)]
 enum GroupsCommand {
     Create(CreateGroup),
@@ -474,6 +682,51 @@ struct ListInvites {
     org_id: esc_api::resources::OrganizationId,
 }
 
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Gathers organization access settings commands")]
+struct AccessSettings {
+    #[structopt(subcommand)]
+    settings_command: SettingsCommand,
+}
+
+#[derive(StructOpt, Debug)]
+enum SettingsCommand {
+    Get(GetSetting),
+    Set(SetSetting),
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Read an access setting of an organization, or all of them as JSON")]
+struct GetSetting {
+    #[structopt(long, short, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the settings relate to")]
+    org_id: OrgId,
+
+    #[structopt(
+        long,
+        help = "The setting to read: require-mfa or restrict-invite-domain"
+    )]
+    name: Option<access_settings::Setting>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Change an access setting of an organization")]
+struct SetSetting {
+    #[structopt(long, short, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the settings relate to")]
+    org_id: OrgId,
+
+    #[structopt(
+        long,
+        help = "The setting to change: require-mfa or restrict-invite-domain"
+    )]
+    name: access_settings::Setting,
+
+    #[structopt(
+        long,
+        help = "true or false for require-mfa, a domain name or an empty value for restrict-invite-domain"
+    )]
+    value: String,
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(about = "Gathers networks and peering management commands")]
 struct Infra {
@@ -630,6 +883,9 @@ struct DeleteNetwork {
 
     #[structopt(long, short, parse(try_from_str = parse_network_id), help = "A network's id")]
     id: esc_api::infra::NetworkId,
+
+    #[structopt(long, help = "Deletes without asking for confirmation")]
+    yes: bool,
 }
 
 #[derive(StructOpt, Debug)]
@@ -846,6 +1102,15 @@ enum ProfilePropName {
     ProjectId,
     ApiBaseUrl,
     Fmt,
+    Retries,
+    RequestTimeout,
+    ConnectTimeout,
+    Proxy,
+    NoProxy,
+    CaCert,
+    ClientCert,
+    ClientKey,
+    RedactFields,
 }
 
 #[derive(Debug, StructOpt)]
@@ -930,6 +1195,9 @@ struct UpdateOrganizationMfaStatus {
 struct DeleteOrganization {
     #[structopt(short, long, parse(try_from_str = parse_org_id), help = "The id of the organization you want to delete")]
     id: OrgId,
+
+    #[structopt(long, help = "Deletes without asking for confirmation")]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -993,6 +1261,9 @@ struct DeleteProject {
 
     #[structopt(long, short, parse(try_from_str = parse_project_id), help = "The id of the project you want to delete")]
     id: esc_api::resources::ProjectId,
+
+    #[structopt(long, help = "Deletes without asking for confirmation")]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1013,6 +1284,7 @@ struct Mesdb {
 enum MesdbCommand {
     Clusters(Clusters),
     Backups(Backups),
+    Versions(Versions),
 }
 
 #[derive(Debug, StructOpt)]
@@ -1022,6 +1294,29 @@ struct Clusters {
     clusters_command: ClustersCommand,
 }
 
+#[derive(Debug, Default, StructOpt)]
+struct WaitOpts {
+    #[structopt(
+        long,
+        help = "Waits until the operation has completed, showing the status of the resource as it changes"
+    )]
+    wait: bool,
+
+    #[structopt(
+        long,
+        requires = "wait",
+        help = "Seconds to wait for with --wait before giving up. Defaults to 30 minutes"
+    )]
+    timeout: Option<u64>,
+}
+
+impl WaitOpts {
+    fn timeout(&self) -> Duration {
+        self.timeout
+            .map(Duration::from_secs)
+            .unwrap_or(wait::DEFAULT_TIMEOUT)
+    }
+}
 #[derive(Debug, StructOpt)]
 enum ClustersCommand {
     Create(CreateCluster),
@@ -1034,6 +1329,8 @@ enum ClustersCommand {
     Start(StartCluster),
     Resize(ResizeCluster),
     Upgrade(UpgradeCluster),
+    Watch(WatchClusters),
+    Clone(CloneCluster),
 }
 
 #[derive(Debug, StructOpt)]
@@ -1102,6 +1399,9 @@ struct CreateCluster {
 
     #[structopt(long, help = "The protected flag prevents from accidental deletion")]
     protected: Option<bool>,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1127,6 +1427,73 @@ struct ListClusters {
     project_id: esc_api::resources::ProjectId,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Copy a cluster into another project, by backing it up and restoring the backup there"
+)]
+struct CloneCluster {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the cluster relates to")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to clone")]
+    id: esc_api::ClusterId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), help = "The project id the copy will relate to")]
+    target_project: esc_api::resources::ProjectId,
+
+    #[structopt(long, parse(try_from_str = parse_network_id), help = "The network id the copy will be set on")]
+    target_network: esc_api::infra::NetworkId,
+
+    #[structopt(
+        long,
+        help = "The ID of an ACL of the target project for the copy to use. Required if the cluster uses an ACL"
+    )]
+    acl_id: Option<String>,
+
+    #[structopt(long, help = "A human-readable description of the copy")]
+    description: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Deletes the backup the copy is restored from once the copy is available. The backup is kept if the copy fails"
+    )]
+    ephemeral_backup: bool,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Watch the clusters of a project, printing a JSON line whenever one is added, removed or changes status, health or patch availability"
+)]
+struct WatchClusters {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "An organization's id")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "An project id that belongs to an organization pointed by --org-id")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(
+        long,
+        default_value = "10",
+        help = "Seconds between two looks at the clusters"
+    )]
+    interval: u64,
+
+    #[structopt(long, help = "Also watch the backups of the project")]
+    backups: bool,
+
+    #[structopt(
+        long,
+        help = "Stop after looking this many times instead of running until interrupted"
+    )]
+    max_polls: Option<u64>,
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Update a cluster")]
 struct UpdateCluster {
@@ -1136,8 +1503,11 @@ struct UpdateCluster {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to update")]
-    id: esc_api::ClusterId,
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), required_unless_one = bulk::CLUSTER_SELECTORS, conflicts_with_all = bulk::CLUSTER_SELECTORS, help = "Id of the cluster you want to update")]
+    id: Option<esc_api::ClusterId>,
+
+    #[structopt(flatten)]
+    select: bulk::ClusterSelector,
 
     #[structopt(long, help = "The ACL id used by a cluster")]
     acl_id: Option<String>,
@@ -1147,6 +1517,12 @@ struct UpdateCluster {
 
     #[structopt(long, help = "The protected flag prevents from accidental deletion")]
     protected: Option<bool>,
+
+    #[structopt(
+        long,
+        help = "Updates the selected clusters without asking for confirmation"
+    )]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1158,8 +1534,17 @@ struct DeleteCluster {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to delete")]
-    id: esc_api::ClusterId,
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), required_unless_one = bulk::CLUSTER_SELECTORS, conflicts_with_all = bulk::CLUSTER_SELECTORS, help = "Id of the cluster you want to delete")]
+    id: Option<esc_api::ClusterId>,
+
+    #[structopt(flatten)]
+    select: bulk::ClusterSelector,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
+
+    #[structopt(long, help = "Deletes without asking for confirmation")]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1185,6 +1570,9 @@ struct ExpandCluster {
 
     #[structopt(long, help = "Optional disk type")]
     disk_type: Option<String>,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1201,6 +1589,9 @@ struct ResizeCluster {
 
     #[structopt(long, help = "The target instance size. (C4, M8, etc)")]
     target_size: String,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1212,8 +1603,20 @@ struct StopCluster {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to resize")]
-    id: esc_api::ClusterId,
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), required_unless_one = bulk::CLUSTER_SELECTORS, conflicts_with_all = bulk::CLUSTER_SELECTORS, help = "Id of the cluster you want to stop")]
+    id: Option<esc_api::ClusterId>,
+
+    #[structopt(flatten)]
+    select: bulk::ClusterSelector,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
+
+    #[structopt(
+        long,
+        help = "Stops the selected clusters without asking for confirmation"
+    )]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1225,8 +1628,20 @@ struct StartCluster {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to resize")]
-    id: esc_api::ClusterId,
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), required_unless_one = bulk::CLUSTER_SELECTORS, conflicts_with_all = bulk::CLUSTER_SELECTORS, help = "Id of the cluster you want to start")]
+    id: Option<esc_api::ClusterId>,
+
+    #[structopt(flatten)]
+    select: bulk::ClusterSelector,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
+
+    #[structopt(
+        long,
+        help = "Starts the selected clusters without asking for confirmation"
+    )]
+    yes: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1238,14 +1653,37 @@ struct UpgradeCluster {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_cluster_id), help = "Id of the cluster you want to upgrade")]
-    id: esc_api::ClusterId,
+    #[structopt(long, short, parse(try_from_str = parse_cluster_id), required_unless_one = bulk::CLUSTER_SELECTORS, conflicts_with_all = bulk::CLUSTER_SELECTORS, help = "Id of the cluster you want to upgrade")]
+    id: Option<esc_api::ClusterId>,
+
+    #[structopt(flatten)]
+    select: bulk::ClusterSelector,
 
     #[structopt(
         long,
+        required_unless = "to",
         help = "The target tag you want to upgrade to. This must include the full version (23.10.1)."
     )]
-    target_tag: String,
+    target_tag: Option<String>,
+
+    #[structopt(
+        long,
+        conflicts_with = "target-tag",
+        help = "Upgrades to the recommended, latest-lts or latest-patch version instead of a given tag"
+    )]
+    to: Option<versions::UpgradeTo>,
+
+    #[structopt(long, help = "Allows going to a new major version")]
+    allow_major: bool,
+
+    #[structopt(long, help = "Allows going to an older version")]
+    allow_downgrade: bool,
+
+    #[structopt(long, help = "Upgrades without asking to confirm the warnings")]
+    yes: bool,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1261,6 +1699,7 @@ enum BackupsCommand {
     Get(GetBackup),
     List(ListBackups),
     Delete(DeleteBackup),
+    Restore(RestoreBackup),
 }
 
 #[derive(Debug, StructOpt)]
@@ -1280,15 +1719,72 @@ struct CreateBackup {
 }
 
 #[derive(Debug, StructOpt)]
-#[structopt(about = "Get information about a single backup")]
-struct GetBackup {
+#[structopt(
+    about = "Create a cluster from a backup, like the cluster the backup was taken from unless told otherwise"
+)]
+struct RestoreBackup {
     #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the backup relates to")]
     org_id: OrgId,
 
-    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the backup relates to")]
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the cluster will relate to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_backup_id), help = "Backup's id")]
+    #[structopt(long, parse(try_from_str = parse_project_id), help = "The project id the backup relates to, if it isn't the one of --project-id")]
+    backup_project_id: Option<esc_api::resources::ProjectId>,
+
+    #[structopt(long, parse(try_from_str = parse_backup_id), help = "Id of the backup to restore")]
+    backup_id: esc_api::BackupId,
+
+    #[structopt(long, parse(try_from_str = parse_network_id), help = "The network id the cluster will be set on. Defaults to the network of the backed up cluster")]
+    network_id: Option<esc_api::infra::NetworkId>,
+
+    #[structopt(
+        long,
+        help = "The ID of an ACL for the cluster to use. Defaults to the ACL of the backed up cluster when restoring into its project, and is required when restoring one with an ACL into another"
+    )]
+    acl_id: Option<String>,
+
+    #[structopt(long, help = "A human-readable description of the cluster")]
+    description: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Type of instance. Defaults to the one of the backed up cluster"
+    )]
+    instance_type: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Total disk capacity in Gigabytes (GB). Defaults to the one of the backed up cluster"
+    )]
+    disk_size_in_gb: Option<i32>,
+
+    #[structopt(
+        long,
+        help = "Type of disk. Defaults to the one of the backed up cluster"
+    )]
+    disk_type: Option<String>,
+
+    #[structopt(long, parse(try_from_str = parse_topology), help = "Either single-node or three-node-multi-zone. Defaults to the one of the backed up cluster")]
+    topology: Option<esc_api::mesdb::Topology>,
+
+    #[structopt(long, parse(try_from_str = parse_projection_level), help = "The projection level of your database. Defaults to the one of the backed up cluster")]
+    projection_level: Option<esc_api::mesdb::ProjectionLevel>,
+
+    #[structopt(flatten)]
+    wait: WaitOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Get information about a single backup")]
+struct GetBackup {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "The organization id the backup relates to")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the backup relates to")]
+    project_id: esc_api::resources::ProjectId,
+
+    #[structopt(long, short, parse(try_from_str = parse_backup_id), help = "Backup's id")]
     id: esc_api::BackupId,
 }
 
@@ -1311,8 +1807,41 @@ struct DeleteBackup {
     #[structopt(long, parse(try_from_str = parse_project_id), default_value = "", help = "The project id the backup relates to")]
     project_id: esc_api::resources::ProjectId,
 
-    #[structopt(long, short, parse(try_from_str = parse_backup_id), help = "Id of the backup you want to delete")]
-    id: esc_api::BackupId,
+    #[structopt(long, short, parse(try_from_str = parse_backup_id), required_unless_one = bulk::BACKUP_SELECTORS, conflicts_with_all = bulk::BACKUP_SELECTORS, help = "Id of the backup you want to delete")]
+    id: Option<esc_api::BackupId>,
+
+    #[structopt(flatten)]
+    select: bulk::BackupSelector,
+
+    #[structopt(long, help = "Deletes without asking for confirmation")]
+    yes: bool,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Gathers server version commands")]
+struct Versions {
+    #[structopt(subcommand)]
+    versions_command: VersionsCommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum VersionsCommand {
+    List(ListVersions),
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "List the server versions new clusters can run, or those a cluster can upgrade to"
+)]
+struct ListVersions {
+    #[structopt(long, parse(try_from_str = parse_org_id), default_value = "", help = "An organization's id")]
+    org_id: OrgId,
+
+    #[structopt(long, parse(try_from_str = parse_project_id), help = "The project id of the cluster given by --for-cluster")]
+    project_id: Option<esc_api::resources::ProjectId>,
+
+    #[structopt(long, parse(try_from_str = parse_cluster_id), help = "Lists the versions this cluster can upgrade to")]
+    for_cluster: Option<esc_api::ClusterId>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1447,6 +1976,7 @@ enum IntegrationsCommand {
     Get(GetIntegration),
     Update(UpdateIntegration),
     TestIntegration(TestIntegration),
+    Options(IntegrationsOptions),
 }
 
 #[derive(Debug, StructOpt)]
@@ -1473,6 +2003,8 @@ pub enum CreateIntegrationData {
 pub struct CreateOpsGenieIntegrationData {
     #[structopt(long, help = "API key used with the Ops Genie integration API")]
     pub api_key: String,
+    #[structopt(long, help = "Integration source")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -1486,6 +2018,13 @@ pub struct CreateSlackIntegrationData {
     pub token: String,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(about = "lists the sources integrations can have, and the sinks each can send to")]
+pub struct IntegrationsOptions {
+    #[structopt(long, help="The id of the organization",  parse(try_from_str = parse_org_id), default_value = "")]
+    pub organization_id: OrgId,
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = "deletes a integration")]
 pub struct DeleteIntegration {
@@ -1571,6 +2110,15 @@ lazy_static! {
         map.insert("org-id", ProfilePropName::OrgId);
         map.insert("api-base-url", ProfilePropName::ApiBaseUrl);
         map.insert("fmt", ProfilePropName::Fmt);
+        map.insert("retries", ProfilePropName::Retries);
+        map.insert("request-timeout", ProfilePropName::RequestTimeout);
+        map.insert("connect-timeout", ProfilePropName::ConnectTimeout);
+        map.insert("proxy", ProfilePropName::Proxy);
+        map.insert("no-proxy", ProfilePropName::NoProxy);
+        map.insert("ca-cert", ProfilePropName::CaCert);
+        map.insert("client-cert", ProfilePropName::ClientCert);
+        map.insert("client-key", ProfilePropName::ClientKey);
+        map.insert("redact-fields", ProfilePropName::RedactFields);
         map
     };
 }
@@ -1744,6 +2292,10 @@ fn parse_email(src: &str) -> Result<String, String> {
     Err("Invalid email".to_string())
 }
 
+fn parse_token_id(src: &str) -> Result<esc_api::access::TokenId, String> {
+    Ok(esc_api::access::TokenId(src.to_string()))
+}
+
 fn parse_invite_id(src: &str) -> Result<esc_api::access::InviteId, String> {
     Ok(esc_api::access::InviteId(src.to_string()))
 }
@@ -1784,6 +2336,19 @@ impl Printer {
         Ok(())
     }
 
+    /// Prints the output of a command which isn't a response of the API.
+    /// In the API output formats it's shown as JSON.
+    pub fn print_result<A: std::fmt::Debug + Serialize + v1::ToV1>(
+        &self,
+        value: A,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.render_as_v1 {
+            return self.print(value);
+        }
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        Ok(())
+    }
+
     pub fn print_json_only<A: std::fmt::Debug + Serialize>(
         &self,
         value: A,
@@ -1795,101 +2360,98 @@ impl Printer {
     }
 }
 
-pub struct StaticAuthorization {
-    pub authorization_header: String,
+fn read_pem(path: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    std::fs::read(path).map_err(|err| format!("Could not read {}: {}", path.display(), err).into())
 }
 
-impl esc_api::Authorization for StaticAuthorization {
-    fn authorization_header(&self) -> String {
-        self.authorization_header.clone()
-    }
-
-    fn refresh(&mut self) -> bool {
-        false
-    }
+/// Shows the traffic with the API. In the API output formats the body of a
+/// successful response is the output of a command, so it goes to stdout,
+/// while everything else goes to stderr or the request log.
+struct TrafficSpy {
+    print_responses: bool,
+    log: Option<Arc<RequestLog>>,
 }
 
-async fn get_token(
-    token_config: esc_api::TokenConfig,
-    refresh_token: Option<String>,
-    noninteractive: bool,
-) -> Result<esc_api::Token, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    match refresh_token {
-        Some(refresh_token) => {
-            let otp_prompt: Option<esc_client_base::identity::operations::OtpPrompt> =
-                match noninteractive {
-                    true => None,
-                    false => Some(esc_client_store::prompt_for_otp),
-                };
-            let refreshed_token = esc_client_base::identity::operations::refresh(
-                &client,
-                &token_config,
-                &refresh_token,
-                otp_prompt,
-            )
-            .await?;
-            Ok(refreshed_token)
-        }
-        None => {
-            let mut store = esc_client_store::token_store(token_config).await?;
-            let token = store.access(&client, noninteractive).await?;
-            Ok(token)
+impl esc_api::RequestObserver for TrafficSpy {
+    fn on_request(&self, event: &esc_api::RequestEvent) {
+        if let Some(log) = self.log.as_ref() {
+            log.request(event);
         }
     }
-}
-
-struct TrafficSpy {
-    verbose: bool,
-}
 
-impl esc_api::RequestObserver for TrafficSpy {
-    fn on_request(&self, method: &str, url: &str, body: &str) {
-        if self.verbose {
-            println!("{} {}", method, url);
+    fn on_response(&self, event: &esc_api::ResponseEvent) {
+        if let Some(log) = self.log.as_ref() {
+            log.response(event);
+        }
+        if !self.print_responses {
+            return;
         }
-        if !body.is_empty() {
-            println!("{}", body);
+        if event.is_success() {
+            if !event.body.is_empty() {
+                println!("{}", event.body);
+            }
+        } else {
+            eprintln!("status: {}", event.status.as_str());
+            if !event.body.is_empty() {
+                eprintln!("{}", event.body);
+            }
         }
     }
 
-    fn on_response(&self, status: &str, body: &str) {
-        if self.verbose || !(status.len() == 3 && status.starts_with('2')) {
-            println!("status: {}", status);
+    fn on_failure(&self, event: &esc_api::FailureEvent) {
+        if let Some(log) = self.log.as_ref() {
+            log.failure(event);
         }
-        if !body.is_empty() {
-            println!("{}", body);
-        };
     }
 }
 
-struct ClientBuilder {
-    base_url: String,
+/// Sends the requests of a command whose responses aren't its output.
+struct Lookups {
     observer: Option<Arc<dyn esc_api::RequestObserver + Send + Sync>>,
-    refresh_token: Option<String>,
-    token_config: esc_api::TokenConfig,
-    noninteractive: bool,
 }
 
-impl ClientBuilder {
-    pub async fn create(self) -> Result<esc_api::Client, Box<dyn std::error::Error>> {
-        let token = get_token(self.token_config, self.refresh_token, self.noninteractive).await?;
-        let authorization = StaticAuthorization {
-            authorization_header: token.authorization_header(),
-        };
-        let sender = esc_api::RequestSender {
-            client: reqwest::Client::new(),
-            observer: self.observer,
-        };
-        let client = esc_api::Client {
-            authorization: std::sync::Arc::new(authorization),
-            base_url: self.base_url,
-            sender,
-        };
-        Ok(client)
+impl Lookups {
+    fn client(&self, client: &esc_api::Client) -> esc_api::Client {
+        let mut client = client.clone();
+        client.sender.observer = self.observer.clone();
+        client
     }
 }
 
+/// Runs a cluster command on every cluster picked by the selectors, once
+/// `confirm` has accepted the selection.
+#[allow(clippy::too_many_arguments)]
+async fn bulk_clusters(
+    client: &esc_api::Client,
+    printer: &Printer,
+    org_id: OrgId,
+    project_id: esc_api::resources::ProjectId,
+    select: &bulk::ClusterSelector,
+    operation: bulk::ClusterOperation,
+    wait: &WaitOpts,
+    confirm: impl FnOnce(&[bulk::Target]) -> Result<(), String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = bulk::select_clusters(client, org_id.clone(), project_id.clone(), select).await?;
+    bulk::preview("cluster", &targets);
+    if select.bulk.dry_run || targets.is_empty() {
+        return Ok(());
+    }
+    confirm(&targets)?;
+    let summary = bulk::clusters(
+        client,
+        org_id,
+        project_id,
+        targets,
+        select.bulk.concurrency,
+        operation,
+        Some(wait.timeout()).filter(|_| wait.wait),
+    )
+    .await;
+    let result = summary.check();
+    printer.print_result(summary)?;
+    result
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let clap_app = Opt::clap();
@@ -1898,24 +2460,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let base_url = config::SETTINGS
         .get_current_profile()
         .and_then(|profile| {
-            profile.api_base_url.as_ref().map(|url| {
-                format!(
-                    "{}://{}",
-                    url.scheme(),
-                    url.host_str().expect("Pre-validated it has a host")
-                )
-            })
+            // Keeps the port, so the API can be served from somewhere
+            // other than 443, such as a local esc-mock.
+            profile
+                .api_base_url
+                .as_ref()
+                .map(|url| url.origin().ascii_serialization())
         })
         .unwrap_or_else(|| constants::ES_CLOUD_API_URL.to_string());
 
+    // The verbose output format shows all the traffic, bodies included.
+    let verbose = matches!(opt.output_format, OutputFormat::ApiVerbose);
+    let log_format = opt.log_format.unwrap_or(LogFormat::Text);
+    let log = match opt.log_file.as_ref() {
+        Some(path) => Some(RequestLog::file(path, log_format, verbose)?),
+        None if verbose || opt.log_format.is_some() => {
+            Some(RequestLog::stderr(log_format, verbose))
+        }
+        None => None,
+    };
+    let log = match log {
+        Some(log) if !opt.no_redact => {
+            let extra_fields = config::SETTINGS
+                .get_current_profile()
+                .and_then(|profile| profile.redact_fields.as_deref())
+                .unwrap_or_default();
+            let redactor = extra_fields
+                .split(',')
+                .fold(esc_api::Redactor::default(), |redactor, field| {
+                    redactor.field(field.trim())
+                });
+            Some(log.redact(redactor))
+        }
+        log => log,
+    };
+    let log = log.map(Arc::new);
     let observer: Option<Arc<dyn esc_api::RequestObserver + Send + Sync>> =
-        if !opt.output_format.is_v1() {
+        if !opt.output_format.is_v1() || log.is_some() {
             Some(Arc::new(TrafficSpy {
-                verbose: matches!(opt.output_format, OutputFormat::ApiVerbose),
+                print_responses: !opt.output_format.is_v1(),
+                log: log.clone(),
             }))
         } else {
             None
         };
+    // Requests which only gather what a command needs, or follow what it did,
+    // are logged but their responses aren't the output of the command.
+    let lookup_observer: Option<Arc<dyn esc_api::RequestObserver + Send + Sync>> =
+        log.map(|log| -> Arc<dyn esc_api::RequestObserver + Send + Sync> {
+            Arc::new(TrafficSpy {
+                print_responses: false,
+                log: Some(log),
+            })
+        });
 
     let printer = Printer {
         render_in_json: match opt.output_format {
@@ -1953,36 +2550,123 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    let client_builder = ClientBuilder {
-        base_url,
-        observer,
-        refresh_token: opt.refresh_token.clone(),
-        token_config: token_config.clone(),
-        noninteractive: opt.noninteractive,
+    let retries = opt
+        .retries
+        .or_else(|| profile_opt.and_then(|p| p.retries))
+        .unwrap_or(0);
+
+    let mut http_config = esc_api::HttpConfig::default();
+    if let Some(seconds) = opt
+        .request_timeout
+        .or_else(|| profile_opt.and_then(|p| p.request_timeout))
+    {
+        http_config = http_config.timeout(Duration::from_secs(seconds));
+    }
+    if let Some(seconds) = opt
+        .connect_timeout
+        .or_else(|| profile_opt.and_then(|p| p.connect_timeout))
+    {
+        http_config = http_config.connect_timeout(Duration::from_secs(seconds));
+    }
+    if let Some(proxy) = opt
+        .proxy
+        .clone()
+        .or_else(|| profile_opt.and_then(|p| p.proxy.clone()))
+    {
+        http_config = http_config.proxy(proxy);
+    }
+    if let Some(no_proxy) = opt
+        .no_proxy
+        .as_ref()
+        .or_else(|| profile_opt.and_then(|p| p.no_proxy.as_ref()))
+    {
+        http_config = http_config.no_proxy(
+            no_proxy
+                .split(',')
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        );
+    }
+    if let Some(path) = opt
+        .ca_cert
+        .as_ref()
+        .or_else(|| profile_opt.and_then(|p| p.ca_cert.as_ref()))
+    {
+        http_config = http_config.root_certificate(read_pem(path)?);
+    }
+    if let Some(path) = opt
+        .client_cert
+        .as_ref()
+        .or_else(|| profile_opt.and_then(|p| p.client_cert.as_ref()))
+    {
+        let mut identity = read_pem(path)?;
+        if let Some(path) = opt
+            .client_key
+            .as_ref()
+            .or_else(|| profile_opt.and_then(|p| p.client_key.as_ref()))
+        {
+            identity.push(b'\n');
+            identity.extend(read_pem(path)?);
+        }
+        http_config = http_config.identity(identity);
+    }
+
+    let mut client_builder = esc_api::ClientBuilder::new()
+        .base_url(&base_url)
+        .token_config(token_config.clone())
+        .retries(retries)
+        .http_config(http_config.user_agent(format!("esc/{}", env!("CARGO_PKG_VERSION"))));
+    if let Some(observer) = observer {
+        client_builder = client_builder.observer(observer);
+    }
+    client_builder = match opt.refresh_token.as_ref() {
+        Some(refresh_token) if opt.noninteractive => client_builder.refresh_token(refresh_token),
+        Some(refresh_token) => {
+            client_builder.refresh_token_with_otp(refresh_token, esc_client_store::prompt_for_otp)
+        }
+        None => client_builder.token_source(esc_client_store::TokenStoreSource {
+            noninteractive: opt.noninteractive,
+        }),
     };
 
     let silence_errors = !opt.output_format.is_v1();
-    let result = call_api(clap_app, opt, client_builder, printer, token_config).await;
-    if !silence_errors {
-        result
-    } else if let Err(err) = result {
-        if let Some(esc_api::Error::ApiResponse(resp)) = err.downcast_ref::<esc_api::Error>() {
-            if !resp.status_code.is_success() {
-                // The traffic observer has already shown the error to the user, so don't show
-                // additional error information.
-                std::process::exit(1);
-            }
+    let lookups = Lookups {
+        observer: lookup_observer,
+    };
+    let result = call_api(
+        clap_app,
+        opt,
+        client_builder,
+        lookups,
+        printer,
+        token_config,
+    )
+    .await;
+    if let Err(err) = result {
+        // In the API output formats the traffic observer has already shown
+        // error responses to the user, so don't show them a second time.
+        let already_shown = (silence_errors
+            && matches!(
+                err.downcast_ref::<esc_api::Error>(),
+                Some(esc_api::Error::ApiResponse(_))
+            ))
+            // The plan was printed, and the exit code tells it differs.
+            || err.is::<spec::plan::Drift>();
+        if !already_shown {
+            eprintln!("Error: {:?}", err);
         }
-        Err(err)
-    } else {
-        result
+        std::process::exit(exit_codes::from_error(err.as_ref()));
     }
+
+    Ok(())
 }
 
 async fn call_api<'a, 'b>(
     mut clap_app: clap::App<'a, 'b>,
     opt: Opt,
-    client_builder: ClientBuilder,
+    client_builder: esc_api::ClientBuilder,
+    lookups: Lookups,
     printer: Printer,
     token_config: esc_api::TokenConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -1990,7 +2674,7 @@ async fn call_api<'a, 'b>(
         Command::Access(access) => match access.access_command {
             AccessCommand::Groups(groups) => match groups.groups_command {
                 GroupsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let create_params = esc_api::access::CreateGroupRequest {
                         name: params.name,
                         members: Some(
@@ -2008,7 +2692,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 GroupsCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let body = esc_api::access::UpdateGroupRequest {
                         members: params.members,
                         name: params.name,
@@ -2017,19 +2701,19 @@ async fn call_api<'a, 'b>(
                 }
 
                 GroupsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::access::get_group(&client, params.org_id, params.id).await?;
                     printer.print(resp)?;
                 }
 
                 GroupsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::delete_group(&client, params.org_id, params.id).await?;
                 }
 
                 GroupsCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let linked_resource = None; // TODO: add this as a parameter
                     let resp =
                         esc_api::access::list_groups(&client, params.org_id, linked_resource)
@@ -2040,7 +2724,7 @@ async fn call_api<'a, 'b>(
 
             AccessCommand::Invites(invites) => match invites.invites_command {
                 InvitesCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::access::create_invite(
                         &client,
                         params.org_id,
@@ -2055,7 +2739,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 InvitesCommand::Resend(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::resend_invite(
                         &client,
                         params.org_id,
@@ -2065,12 +2749,12 @@ async fn call_api<'a, 'b>(
                 }
 
                 InvitesCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::delete_invite(&client, params.org_id, params.id).await?;
                 }
 
                 InvitesCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::access::list_invites(&client, params.org_id).await?;
                     printer.print(resp)?;
                 }
@@ -2078,10 +2762,10 @@ async fn call_api<'a, 'b>(
 
             AccessCommand::Tokens(tokens) => match tokens.tokens_command {
                 TokensCommand::Create(params) => {
-                    let client = reqwest::Client::new();
+                    let client = client_builder.get_http_config().build_client()?;
                     let mut store = esc_client_store::token_store(token_config).await?;
 
-                    match client_builder.noninteractive {
+                    match opt.noninteractive {
                         true => {
                             println!("--noninteractive mode set, cannot prompt for password");
                             std::process::exit(-1)
@@ -2115,11 +2799,22 @@ async fn call_api<'a, 'b>(
                         std::process::exit(-1)
                     }
                 }
+
+                TokensCommand::List(_params) => {
+                    let client = client_builder.build().await?;
+                    let resp = esc_api::access::list_user_tokens(&client).await?;
+                    printer.print(resp)?;
+                }
+
+                TokensCommand::Revoke(params) => {
+                    let client = client_builder.build().await?;
+                    esc_api::access::delete_user_token(&client, params.id).await?;
+                }
             },
 
             AccessCommand::Policies(policies) => match policies.policies_command {
                 PoliciesCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::access::create_policy(
                         &client,
                         params.org_id,
@@ -2138,7 +2833,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 PoliciesCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::update_policy(
                         &client,
                         params.org_id,
@@ -2157,40 +2852,53 @@ async fn call_api<'a, 'b>(
                 }
 
                 PoliciesCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::delete_policy(&client, params.org_id, params.policy).await?;
                 }
 
                 PoliciesCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::access::get_policy(&client, params.org_id, params.policy).await?;
                     printer.print(resp)?;
                 }
 
                 PoliciesCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::access::list_policies(&client, params.org_id).await?;
                     printer.print(resp)?;
                 }
+
+                PoliciesCommand::Check(params) => {
+                    let client = client_builder.build().await?;
+                    let evaluation = policies::check(
+                        &lookups.client(&client),
+                        params.org_id,
+                        params.subject,
+                        utils::action_from_str(&params.action),
+                        params.resource,
+                    )
+                    .await?;
+                    printer.print_result(evaluation)?;
+                }
             },
 
             AccessCommand::Members(members) => match members.members_command {
                 MembersCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::access::list_members(&client, params.org_id).await?;
                     printer.print(resp)?;
                 }
 
                 MembersCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::access::get_member(&client, params.org_id, params.id).await?;
                     printer.print(resp)?;
                 }
 
                 MembersCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::update_member(
                         &client,
                         params.org_id,
@@ -2203,10 +2911,33 @@ async fn call_api<'a, 'b>(
                 }
 
                 MembersCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::access::delete_member(&client, params.org_id, params.id).await?;
                 }
             },
+
+            AccessCommand::Settings(settings) => match settings.settings_command {
+                SettingsCommand::Get(params) => {
+                    let client = client_builder.build().await?;
+                    // The settings are shown by the names the CLI takes,
+                    // rather than as the API returns them.
+                    let settings =
+                        esc_api::access::get_settings(&lookups.client(&client), params.org_id)
+                            .await?
+                            .settings;
+                    let shown = match params.name {
+                        Some(name) => access_settings::Shown::One(name.get(&settings)),
+                        None => access_settings::Shown::All(access_settings::dump(&settings)),
+                    };
+                    printer.print_result(shown)?;
+                }
+
+                SettingsCommand::Set(params) => {
+                    let update = params.name.update(&params.value)?;
+                    let client = client_builder.build().await?;
+                    esc_api::access::update_setting(&client, params.org_id, update).await?;
+                }
+            },
         },
 
         Command::Audit(aud) => match aud.audit_command {
@@ -2228,7 +2959,7 @@ async fn call_api<'a, 'b>(
                         Some(x) => limit = x,
                         None => {}
                     }
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::audit::get_audit_by_org(
                         &client,
                         params.org_id,
@@ -2263,7 +2994,7 @@ async fn call_api<'a, 'b>(
                         Some(x) => org_id = x,
                         None => {}
                     }
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::audit::get_audit_by_user(&client, org_id, before, after, limit)
                             .await?;
@@ -2275,7 +3006,7 @@ async fn call_api<'a, 'b>(
         Command::Infra(infra) => match infra.infra_command {
             InfraCommand::Acls(acls) => match acls.acls_command {
                 AclsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::create_acl(
                         &client,
                         params.org_id,
@@ -2289,7 +3020,7 @@ async fn call_api<'a, 'b>(
                     printer.print_json_only(resp)?;
                 }
                 AclsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::infra::delete_acl(
                         &client,
                         params.org_id,
@@ -2299,7 +3030,7 @@ async fn call_api<'a, 'b>(
                     .await?;
                 }
                 AclsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::get_acl(
                         &client,
                         params.org_id,
@@ -2310,13 +3041,13 @@ async fn call_api<'a, 'b>(
                     printer.print_json_only(resp)?;
                 }
                 AclsCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::list_acls(&client, params.org_id, params.project_id)
                         .await?;
                     printer.print_json_only(resp)?;
                 }
                 AclsCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::infra::update_acl(
                         &client,
                         params.org_id,
@@ -2333,7 +3064,7 @@ async fn call_api<'a, 'b>(
             InfraCommand::Networks(networks) => match networks.networks_command {
                 NetworksCommand::Create(params) => {
                     let cidr_block = params.cidr_block.map(|cidr| cidr.to_string());
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::create_network(
                         &client,
                         params.org_id,
@@ -2351,7 +3082,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 NetworksCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::infra::update_network(
                         &client,
                         params.org_id,
@@ -2365,7 +3096,15 @@ async fn call_api<'a, 'b>(
                 }
 
                 NetworksCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
+                    confirm::network(
+                        &lookups.client(&client),
+                        params.org_id.clone(),
+                        params.project_id.clone(),
+                        params.id.clone(),
+                    )
+                    .await?
+                    .confirm(params.yes, opt.noninteractive)?;
                     esc_api::infra::delete_network(
                         &client,
                         params.org_id,
@@ -2376,7 +3115,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 NetworksCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::get_network(
                         &client,
                         params.org_id,
@@ -2388,7 +3127,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 NetworksCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::infra::list_networks(&client, params.org_id, params.project_id)
                             .await?;
@@ -2398,7 +3137,7 @@ async fn call_api<'a, 'b>(
 
             InfraCommand::Peerings(peerings) => match peerings.peerings_command {
                 PeeringsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let result = esc_api::infra::create_peering(
                         &client,
                         params.org_id.clone(),
@@ -2449,7 +3188,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 PeeringsCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::infra::update_peering(
                         &client,
                         params.org_id,
@@ -2463,7 +3202,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 PeeringsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::infra::delete_peering(
                         &client,
                         params.org_id,
@@ -2474,7 +3213,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 PeeringsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::infra::get_peering(
                         &client,
                         params.org_id,
@@ -2486,7 +3225,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 PeeringsCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::infra::list_peerings(&client, params.org_id, params.project_id)
                             .await?;
@@ -2518,6 +3257,45 @@ async fn call_api<'a, 'b>(
                         let fmt = OutputFormat::from_str(params.value.as_str())?;
                         profile.output_format = Some(fmt);
                     }
+
+                    ProfilePropName::Retries => {
+                        let retries = params.value.parse::<u32>()?;
+                        profile.retries = Some(retries);
+                    }
+
+                    ProfilePropName::RequestTimeout => {
+                        let seconds = params.value.parse::<u64>()?;
+                        profile.request_timeout = Some(seconds);
+                    }
+
+                    ProfilePropName::ConnectTimeout => {
+                        let seconds = params.value.parse::<u64>()?;
+                        profile.connect_timeout = Some(seconds);
+                    }
+
+                    ProfilePropName::Proxy => {
+                        profile.proxy = Some(params.value);
+                    }
+
+                    ProfilePropName::NoProxy => {
+                        profile.no_proxy = Some(params.value);
+                    }
+
+                    ProfilePropName::CaCert => {
+                        profile.ca_cert = Some(PathBuf::from(params.value));
+                    }
+
+                    ProfilePropName::ClientCert => {
+                        profile.client_cert = Some(PathBuf::from(params.value));
+                    }
+
+                    ProfilePropName::ClientKey => {
+                        profile.client_key = Some(PathBuf::from(params.value));
+                    }
+
+                    ProfilePropName::RedactFields => {
+                        profile.redact_fields = Some(params.value);
+                    }
                 }
 
                 settings.persist().await?;
@@ -2554,6 +3332,60 @@ async fn call_api<'a, 'b>(
                                     serde_json::to_writer_pretty(std::io::stdout(), fmt.as_str())?;
                                 }
                             }
+
+                            ProfilePropName::Retries => {
+                                if let Some(retries) = profile.retries.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), retries)?;
+                                }
+                            }
+
+                            ProfilePropName::RequestTimeout => {
+                                if let Some(seconds) = profile.request_timeout.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), seconds)?;
+                                }
+                            }
+
+                            ProfilePropName::ConnectTimeout => {
+                                if let Some(seconds) = profile.connect_timeout.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), seconds)?;
+                                }
+                            }
+
+                            ProfilePropName::Proxy => {
+                                if let Some(proxy) = profile.proxy.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), proxy)?;
+                                }
+                            }
+
+                            ProfilePropName::NoProxy => {
+                                if let Some(no_proxy) = profile.no_proxy.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), no_proxy)?;
+                                }
+                            }
+
+                            ProfilePropName::CaCert => {
+                                if let Some(path) = profile.ca_cert.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), path)?;
+                                }
+                            }
+
+                            ProfilePropName::ClientCert => {
+                                if let Some(path) = profile.client_cert.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), path)?;
+                                }
+                            }
+
+                            ProfilePropName::ClientKey => {
+                                if let Some(path) = profile.client_key.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), path)?;
+                                }
+                            }
+
+                            ProfilePropName::RedactFields => {
+                                if let Some(fields) = profile.redact_fields.as_ref() {
+                                    serde_json::to_writer_pretty(std::io::stdout(), fields)?;
+                                }
+                            }
                         }
                     } else {
                         serde_json::to_writer_pretty(std::io::stdout(), profile)?;
@@ -2585,6 +3417,42 @@ async fn call_api<'a, 'b>(
                     ProfilePropName::Fmt => {
                         profile.output_format = None;
                     }
+
+                    ProfilePropName::Retries => {
+                        profile.retries = None;
+                    }
+
+                    ProfilePropName::RequestTimeout => {
+                        profile.request_timeout = None;
+                    }
+
+                    ProfilePropName::ConnectTimeout => {
+                        profile.connect_timeout = None;
+                    }
+
+                    ProfilePropName::Proxy => {
+                        profile.proxy = None;
+                    }
+
+                    ProfilePropName::NoProxy => {
+                        profile.no_proxy = None;
+                    }
+
+                    ProfilePropName::CaCert => {
+                        profile.ca_cert = None;
+                    }
+
+                    ProfilePropName::ClientCert => {
+                        profile.client_cert = None;
+                    }
+
+                    ProfilePropName::ClientKey => {
+                        profile.client_key = None;
+                    }
+
+                    ProfilePropName::RedactFields => {
+                        profile.redact_fields = None;
+                    }
                 }
 
                 settings.persist().await?;
@@ -2616,7 +3484,7 @@ async fn call_api<'a, 'b>(
         Command::Resources(res) => match res.resources_command {
             ResourcesCommand::Organizations(orgs) => match orgs.organizations_command {
                 OrganizationsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::create_organization(
                         &client,
                         esc_api::resources::CreateOrganizationRequest { name: params.name },
@@ -2626,7 +3494,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 OrganizationsCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::resources::update_organization(
                         &client,
                         params.id,
@@ -2636,30 +3504,33 @@ async fn call_api<'a, 'b>(
                 }
 
                 OrganizationsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
+                    confirm::organization(&lookups.client(&client), params.id.clone())
+                        .await?
+                        .confirm(params.yes, opt.noninteractive)?;
                     esc_api::resources::delete_organization(&client, params.id).await?;
                 }
 
                 OrganizationsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::get_organization(&client, params.id).await?;
                     printer.print(resp)?;
                 }
 
                 OrganizationsCommand::List(_) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::list_organizations(&client).await?;
                     printer.print(resp)?;
                 }
 
                 OrganizationsCommand::GetMfaStatus(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::get_mfa_status(&client, params.id).await?;
                     printer.print(resp)?;
                 }
 
                 OrganizationsCommand::UpdateMfaStatus(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::update_mfa(
                         &client,
                         params.id,
@@ -2674,7 +3545,7 @@ async fn call_api<'a, 'b>(
 
             ResourcesCommand::Projects(projs) => match projs.projects_command {
                 ProjectsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::create_project(
                         &client,
                         params.org_id,
@@ -2685,7 +3556,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 ProjectsCommand::Update(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::resources::update_project(
                         &client,
                         params.org_id,
@@ -2696,19 +3567,26 @@ async fn call_api<'a, 'b>(
                 }
 
                 ProjectsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::resources::get_project(&client, params.org_id, params.id).await?;
                     printer.print(resp)?;
                 }
 
                 ProjectsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
+                    confirm::project(
+                        &lookups.client(&client),
+                        params.org_id.clone(),
+                        params.id.clone(),
+                    )
+                    .await?
+                    .confirm(params.yes, opt.noninteractive)?;
                     esc_api::resources::delete_project(&client, params.org_id, params.id).await?;
                 }
 
                 ProjectsCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::resources::list_projects(&client, params.org_id).await?;
                     printer.print(resp)?;
                 }
@@ -2719,11 +3597,11 @@ async fn call_api<'a, 'b>(
             match mesdb.mesdb_command {
                 MesdbCommand::Clusters(clusters) => match clusters.clusters_command {
                     ClustersCommand::Create(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp = esc_api::mesdb::create_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
                             esc_api::mesdb::CreateClusterRequest {
                                 acl_id: params.acl_id,
                                 description: params.description,
@@ -2744,11 +3622,23 @@ async fn call_api<'a, 'b>(
                             },
                         )
                         .await?;
+                        let cluster_id = resp.id.clone();
                         printer.print(resp)?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                cluster_id,
+                                ClusterTarget::Status(esc_api::mesdb::ClusterStatus::Available),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Get(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp = esc_api::mesdb::get_cluster(
                             &client,
                             params.org_id,
@@ -2760,23 +3650,92 @@ async fn call_api<'a, 'b>(
                     }
 
                     ClustersCommand::Delete(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                let (yes, noninteractive) = (params.yes, opt.noninteractive);
+                                return bulk_clusters(
+                                    &lookups.client(&client),
+                                    &printer,
+                                    params.org_id,
+                                    params.project_id,
+                                    &params.select,
+                                    bulk::ClusterOperation::Delete,
+                                    &params.wait,
+                                    |targets| {
+                                        let what = format!("{} cluster(s)", targets.len());
+                                        confirm::ask(&what, yes, noninteractive)
+                                    },
+                                )
+                                .await;
+                            }
+                        };
+                        confirm::cluster(
+                            &lookups.client(&client),
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
+                        )
+                        .await?
+                        .confirm(params.yes, opt.noninteractive)?;
                         esc_api::mesdb::delete_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                id,
+                                ClusterTarget::Deleted,
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Update(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                if params.description.is_some() {
+                                    return Err(
+                                        "--description can only be set on one cluster at a time"
+                                            .into(),
+                                    );
+                                }
+                                let operation = bulk::ClusterOperation::Update {
+                                    acl_id: params.acl_id,
+                                    protected: params.protected,
+                                };
+                                let (yes, noninteractive) = (params.yes, opt.noninteractive);
+                                return bulk_clusters(
+                                    &lookups.client(&client),
+                                    &printer,
+                                    params.org_id,
+                                    params.project_id,
+                                    &params.select,
+                                    operation,
+                                    &WaitOpts::default(),
+                                    |targets| {
+                                        let action = format!("update {} cluster(s)", targets.len());
+                                        confirm::proceed(&action, yes, noninteractive)
+                                    },
+                                )
+                                .await;
+                            }
+                        };
                         esc_api::mesdb::update_cluster(
                             &client,
                             params.org_id,
                             params.project_id,
-                            params.id,
+                            id,
                             esc_api::mesdb::UpdateClusterRequest {
                                 acl_id: params.acl_id,
                                 description: params.description,
@@ -2786,8 +3745,38 @@ async fn call_api<'a, 'b>(
                         .await?;
                     }
 
+                    ClustersCommand::Clone(params) => {
+                        let client = client_builder.build().await?;
+                        let clone = restore::ClusterClone {
+                            org_id: params.org_id,
+                            project_id: params.project_id,
+                            cluster_id: params.id,
+                            target_project_id: params.target_project,
+                            target_network_id: params.target_network,
+                            description: params.description,
+                            acl_id: params.acl_id,
+                            ephemeral_backup: params.ephemeral_backup,
+                            wait: params.wait.wait,
+                            timeout: params.wait.timeout(),
+                        };
+                        let resp = clone.run(&client, &lookups.client(&client)).await?;
+                        printer.print(resp)?;
+                    }
+
+                    ClustersCommand::Watch(params) => {
+                        let client = client_builder.build().await?;
+                        let watch = watch::Watch {
+                            org_id: params.org_id,
+                            project_id: params.project_id,
+                            interval: Duration::from_secs(params.interval),
+                            backups: params.backups,
+                            max_polls: params.max_polls,
+                        };
+                        watch.run(&lookups.client(&client)).await?;
+                    }
+
                     ClustersCommand::List(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp = esc_api::mesdb::list_clusters(
                             &client,
                             params.org_id,
@@ -2798,12 +3787,12 @@ async fn call_api<'a, 'b>(
                     }
 
                     ClustersCommand::Expand(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         esc_api::mesdb::expand_cluster_disk(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            params.id.clone(),
                             esc_api::mesdb::ExpandClusterDiskRequest {
                                 disk_iops: params.disk_iops,
                                 disk_size_gb: params.disk_size_in_gb,
@@ -2812,61 +3801,248 @@ async fn call_api<'a, 'b>(
                             },
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                params.id,
+                                ClusterTarget::DiskSize(params.disk_size_in_gb),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Resize(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         esc_api::mesdb::resize_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            params.id.clone(),
                             esc_api::mesdb::ResizeClusterRequest {
-                                target_size: params.target_size,
+                                target_size: params.target_size.clone(),
                             },
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                params.id,
+                                ClusterTarget::InstanceType(params.target_size),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Stop(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                let (yes, noninteractive) = (params.yes, opt.noninteractive);
+                                return bulk_clusters(
+                                    &lookups.client(&client),
+                                    &printer,
+                                    params.org_id,
+                                    params.project_id,
+                                    &params.select,
+                                    bulk::ClusterOperation::Stop,
+                                    &params.wait,
+                                    |targets| {
+                                        let action = format!("stop {} cluster(s)", targets.len());
+                                        confirm::proceed(&action, yes, noninteractive)
+                                    },
+                                )
+                                .await;
+                            }
+                        };
                         esc_api::mesdb::stop_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                id,
+                                ClusterTarget::Status(
+                                    esc_api::mesdb::ClusterStatus::DisksAvailable,
+                                ),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Start(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                let (yes, noninteractive) = (params.yes, opt.noninteractive);
+                                return bulk_clusters(
+                                    &lookups.client(&client),
+                                    &printer,
+                                    params.org_id,
+                                    params.project_id,
+                                    &params.select,
+                                    bulk::ClusterOperation::Start,
+                                    &params.wait,
+                                    |targets| {
+                                        let action = format!("start {} cluster(s)", targets.len());
+                                        confirm::proceed(&action, yes, noninteractive)
+                                    },
+                                )
+                                .await;
+                            }
+                        };
                         esc_api::mesdb::start_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                id,
+                                ClusterTarget::Status(esc_api::mesdb::ClusterStatus::Available),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
 
                     ClustersCommand::Upgrade(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let target = match (params.target_tag, params.to) {
+                            (Some(tag), _) => versions::Target::Tag(tag),
+                            (None, Some(to)) => versions::Target::To(to),
+                            (None, None) => unreachable!("--target-tag or --to is required"),
+                        };
+                        let allowed = versions::Allowed {
+                            major: params.allow_major,
+                            downgrade: params.allow_downgrade,
+                        };
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                let lookups = lookups.client(&client);
+                                let targets = bulk::select_clusters(
+                                    &lookups,
+                                    params.org_id.clone(),
+                                    params.project_id.clone(),
+                                    &params.select,
+                                )
+                                .await?;
+                                bulk::preview("cluster", &targets);
+                                if params.select.bulk.dry_run || targets.is_empty() {
+                                    return Ok(());
+                                }
+                                let upgrades = bulk::resolve_upgrades(
+                                    &lookups,
+                                    params.org_id.clone(),
+                                    params.project_id.clone(),
+                                    &targets,
+                                    &target,
+                                    allowed,
+                                )
+                                .await;
+                                let mut warned = false;
+                                for cluster in &targets {
+                                    if let Ok(upgrade) = &upgrades[&cluster.id] {
+                                        if !upgrade.warnings.is_empty() {
+                                            eprintln!(
+                                                "Upgrading cluster {} to {}:",
+                                                cluster.id, upgrade.tag
+                                            );
+                                            for warning in &upgrade.warnings {
+                                                eprintln!("  {}", warning);
+                                            }
+                                            warned = true;
+                                        }
+                                    }
+                                }
+                                if warned {
+                                    let action = format!("upgrade {} cluster(s)", targets.len());
+                                    confirm::proceed(&action, params.yes, opt.noninteractive)?;
+                                }
+                                let wait = &params.wait;
+                                let wait_timeout = Some(wait.timeout()).filter(|_| wait.wait);
+                                let tags = upgrades
+                                    .into_iter()
+                                    .map(|(id, upgrade)| (id, upgrade.map(|upgrade| upgrade.tag)))
+                                    .collect();
+                                let summary = bulk::clusters(
+                                    &lookups,
+                                    params.org_id,
+                                    params.project_id,
+                                    targets,
+                                    params.select.bulk.concurrency,
+                                    bulk::ClusterOperation::Upgrade { tags },
+                                    wait_timeout,
+                                )
+                                .await;
+                                let result = summary.check();
+                                printer.print_result(summary)?;
+                                return result;
+                            }
+                        };
+                        let upgrade = versions::resolve(
+                            &lookups.client(&client),
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
+                            &target,
+                            allowed,
+                        )
+                        .await?;
+                        if !upgrade.warnings.is_empty() {
+                            eprintln!("Upgrading cluster {} to {}:", id.0, upgrade.tag);
+                            for warning in &upgrade.warnings {
+                                eprintln!("  {}", warning);
+                            }
+                            let action = format!("upgrade cluster {} to {}", id.0, upgrade.tag);
+                            confirm::proceed(&action, params.yes, opt.noninteractive)?;
+                        }
                         esc_api::mesdb::upgrade_cluster(
                             &client,
-                            params.org_id,
-                            params.project_id,
-                            params.id,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
                             esc_api::mesdb::UpgradeClusterRequest {
-                                target_tag: params.target_tag,
+                                target_tag: upgrade.tag.clone(),
                             },
                         )
                         .await?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                id,
+                                ClusterTarget::ServerVersionTag(upgrade.tag),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
                     }
                 },
                 MesdbCommand::Backups(clusters) => match clusters.backups_command {
                     BackupsCommand::Create(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp = esc_api::mesdb::create_backup(
                             &client,
                             params.org_id,
@@ -2880,8 +4056,61 @@ async fn call_api<'a, 'b>(
                         printer.print(resp)?;
                     }
 
+                    BackupsCommand::Restore(params) => {
+                        let client = client_builder.build().await?;
+                        let backup_project_id = params
+                            .backup_project_id
+                            .clone()
+                            .unwrap_or_else(|| params.project_id.clone());
+                        let backup = esc_api::mesdb::get_backup(
+                            &lookups.client(&client),
+                            params.org_id.clone(),
+                            backup_project_id,
+                            params.backup_id,
+                        )
+                        .await?
+                        .backup;
+                        let request = restore::restore_request(
+                            &lookups.client(&client),
+                            params.org_id.clone(),
+                            &params.project_id,
+                            &backup,
+                            restore::ClusterSpec {
+                                network_id: params.network_id,
+                                acl_id: params.acl_id,
+                                description: params.description,
+                                instance_type: params.instance_type,
+                                disk_size_gb: params.disk_size_in_gb,
+                                disk_type: params.disk_type,
+                                topology: params.topology,
+                                projection_level: params.projection_level,
+                            },
+                        )
+                        .await?;
+                        let resp = esc_api::mesdb::create_cluster(
+                            &client,
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            request,
+                        )
+                        .await?;
+                        let cluster_id = resp.id.clone();
+                        printer.print(resp)?;
+                        if params.wait.wait {
+                            wait::wait_for_cluster(
+                                &lookups.client(&client),
+                                params.org_id,
+                                params.project_id,
+                                cluster_id,
+                                ClusterTarget::Status(esc_api::mesdb::ClusterStatus::Available),
+                                params.wait.timeout(),
+                            )
+                            .await?;
+                        }
+                    }
+
                     BackupsCommand::Get(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp = esc_api::mesdb::get_backup(
                             &client,
                             params.org_id,
@@ -2893,31 +4122,98 @@ async fn call_api<'a, 'b>(
                     }
 
                     BackupsCommand::Delete(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
+                        let id = match params.id {
+                            Some(id) => id,
+                            None => {
+                                let lookups = lookups.client(&client);
+                                let targets = bulk::select_backups(
+                                    &lookups,
+                                    params.org_id.clone(),
+                                    params.project_id.clone(),
+                                    &params.select,
+                                )
+                                .await?;
+                                bulk::preview("backup", &targets);
+                                if params.select.bulk.dry_run || targets.is_empty() {
+                                    return Ok(());
+                                }
+                                let what = format!("{} backup(s)", targets.len());
+                                confirm::ask(&what, params.yes, opt.noninteractive)?;
+                                let summary = bulk::delete_backups(
+                                    &lookups,
+                                    params.org_id,
+                                    params.project_id,
+                                    targets,
+                                    params.select.bulk.concurrency,
+                                )
+                                .await;
+                                let result = summary.check();
+                                printer.print_result(summary)?;
+                                return result;
+                            }
+                        };
+                        confirm::backup(
+                            &lookups.client(&client),
+                            params.org_id.clone(),
+                            params.project_id.clone(),
+                            id.clone(),
+                        )
+                        .await?
+                        .confirm(params.yes, opt.noninteractive)?;
                         esc_api::mesdb::delete_backup(
                             &client,
                             params.org_id,
                             params.project_id,
-                            params.id,
+                            id,
                         )
                         .await?;
                     }
 
                     BackupsCommand::List(params) => {
-                        let client = client_builder.create().await?;
+                        let client = client_builder.build().await?;
                         let resp =
                             esc_api::mesdb::list_backups(&client, params.org_id, params.project_id)
                                 .await?;
                         printer.print(resp)?;
                     }
                 },
+                MesdbCommand::Versions(versions) => match versions.versions_command {
+                    VersionsCommand::List(params) => {
+                        let client = client_builder.build().await?;
+                        match params.for_cluster {
+                            Some(cluster_id) => {
+                                let project_id = match params.project_id {
+                                    Some(project_id) => project_id,
+                                    None => parse_project_id("")?,
+                                };
+                                let resp = esc_api::mesdb::list_versions_for_cluster_upgrade(
+                                    &client,
+                                    params.org_id,
+                                    project_id,
+                                    cluster_id,
+                                )
+                                .await?;
+                                printer.print(resp)?;
+                            }
+                            None => {
+                                let resp = esc_api::mesdb::list_versions_for_cluster_create(
+                                    &client,
+                                    params.org_id,
+                                )
+                                .await?;
+                                printer.print(resp)?;
+                            }
+                        }
+                    }
+                },
             }
         }
 
         Command::Orchestrate(orchestrate) => match orchestrate.orchestrate_command {
             OrchestrateCommand::Jobs(jobs) => match jobs.jobs_command {
                 JobsCommand::Create(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let data = match params.job_type {
                         CreateJobType::ScheduledBackup(args) => {
                             esc_api::orchestrate::JobData::ScheduledBackup(
@@ -2944,7 +4240,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 JobsCommand::Get(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::orchestrate::get_job(
                         &client,
                         params.org_id,
@@ -2956,7 +4252,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 JobsCommand::Delete(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     esc_api::orchestrate::delete_job(
                         &client,
                         params.org_id,
@@ -2967,7 +4263,7 @@ async fn call_api<'a, 'b>(
                 }
 
                 JobsCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp =
                         esc_api::orchestrate::list_jobs(&client, params.org_id, params.project_id)
                             .await?;
@@ -2976,7 +4272,7 @@ async fn call_api<'a, 'b>(
             },
             OrchestrateCommand::History(history) => match history.history_command {
                 HistoryCommand::List(params) => {
-                    let client = client_builder.create().await?;
+                    let client = client_builder.build().await?;
                     let resp = esc_api::orchestrate::list_history(
                         &client,
                         params.org_id,
@@ -2991,7 +4287,7 @@ async fn call_api<'a, 'b>(
 
         Command::Integrations(cmd) => match cmd.integration_command {
             IntegrationsCommand::List(params) => {
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
                 let resp = esc_api::integrate::list_integrations(
                     &client,
                     params.organization_id,
@@ -3001,13 +4297,23 @@ async fn call_api<'a, 'b>(
                 printer.print(resp)?;
             }
             IntegrationsCommand::Create(params) => {
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
+                let (sink, source) = match &params.data {
+                    CreateIntegrationData::OpsGenie(args) => ("opsGenie", &args.source),
+                    CreateIntegrationData::Slack(args) => ("slack", &args.source),
+                };
+                let options = esc_api::integrate::get_integrations_options(
+                    &lookups.client(&client),
+                    params.organization_id.clone(),
+                )
+                .await?;
+                integrations::check(&options.sources, sink, source.as_deref())?;
                 let data: esc_api::integrate::CreateIntegrationData = match params.data {
                     CreateIntegrationData::OpsGenie(args) => {
                         esc_api::integrate::CreateIntegrationData::OpsGenie(
                             esc_api::integrate::CreateOpsGenieIntegrationData {
                                 api_key: args.api_key,
-                                source: None,
+                                source: args.source,
                             },
                         )
                     }
@@ -3033,8 +4339,15 @@ async fn call_api<'a, 'b>(
                 .await?;
                 printer.print(resp)?;
             }
+            IntegrationsCommand::Options(params) => {
+                let client = client_builder.build().await?;
+                let resp =
+                    esc_api::integrate::get_integrations_options(&client, params.organization_id)
+                        .await?;
+                printer.print(resp)?;
+            }
             IntegrationsCommand::Delete(params) => {
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
                 esc_api::integrate::delete_integration(
                     &client,
                     params.organization_id,
@@ -3044,7 +4357,7 @@ async fn call_api<'a, 'b>(
                 .await?;
             }
             IntegrationsCommand::Get(params) => {
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
                 let resp = esc_api::integrate::get_integration(
                     &client,
                     params.organization_id,
@@ -3082,7 +4395,7 @@ async fn call_api<'a, 'b>(
                     None => None,
                 };
 
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
                 esc_api::integrate::update_integration(
                     &client,
                     params.organization_id,
@@ -3096,7 +4409,7 @@ async fn call_api<'a, 'b>(
                 .await?;
             }
             IntegrationsCommand::TestIntegration(params) => {
-                let client = client_builder.create().await?;
+                let client = client_builder.build().await?;
                 esc_api::integrate::test_integration(
                     &client,
                     params.organization_id,
@@ -3107,6 +4420,72 @@ async fn call_api<'a, 'b>(
             }
         },
 
+        Command::Apply(params) => {
+            let spec = spec::Spec::load(&params.file)?;
+            let client = client_builder.build().await?;
+            let apply = spec::apply::Apply {
+                org_id: params.org_id,
+                project_id: params.project_id,
+                prune: params.prune,
+                timeout: params
+                    .timeout
+                    .map(Duration::from_secs)
+                    .unwrap_or(wait::DEFAULT_TIMEOUT),
+            };
+            let applied = apply.run(&lookups.client(&client), &spec).await?;
+            let replacements = applied.skipped_replacements();
+            printer.print_result(applied)?;
+            if replacements > 0 {
+                return Err(format!(
+                    "{} resource(s) differ from the spec in ways which need --prune to replace them",
+                    replacements
+                )
+                .into());
+            }
+        }
+
+        Command::Plan(params) => {
+            let spec = spec::Spec::load(&params.file)?;
+            let client = client_builder.build().await?;
+            let lookups = lookups.client(&client);
+            let live =
+                spec::live::Live::fetch(&lookups, &params.org_id, &params.project_id).await?;
+            let plan = spec::plan::plan(&spec, &live)?;
+            let changes = plan.actions.len();
+            printer.print_result(plan)?;
+            if changes > 0 {
+                return Err(spec::plan::Drift { changes }.into());
+            }
+        }
+
+        Command::Export(params) => {
+            let client = client_builder.build().await?;
+            let live = spec::live::Live::fetch(
+                &lookups.client(&client),
+                &params.org_id,
+                &params.project_id,
+            )
+            .await?;
+            let spec = spec::export::export(&live)?;
+            if params.yaml {
+                print!("{}", serde_yaml::to_string(&spec)?);
+            } else {
+                print!("{}", toml::to_string(&spec)?);
+            }
+        }
+
+        Command::Logout => {
+            let store = esc_client_store::token_store(token_config.clone()).await?;
+            let http_client = client_builder.get_http_config().build_client()?;
+            logout::logout(
+                &http_client,
+                &token_config,
+                &store,
+                opt.refresh_token.as_deref(),
+            )
+            .await?;
+        }
+
         Command::GenerateBashCompletion => {
             // clap_complete::generate_to(clap_complete::shells::Bashg, clap_app, "esc", out_dir)
             clap_app.gen_completions_to("esc", clap::Shell::Bash, &mut std::io::stdout());
@@ -0,0 +1,192 @@
+//! Working out whether a subject may act on a resource, and why. The API
+//! only answers for the caller and doesn't say which policies it used, so
+//! policies are also evaluated here, the way the API does: a policy applies
+//! when it names the subject, one of its groups or `*`, covers the resource
+//! (a trailing `*` matches any suffix) and lists the action. Any applying
+//! policy which denies wins, otherwise an applying one must allow.
+
+use esc_api::access::{self, Action, Effect, Group, Member, Policy, PolicyId};
+use esc_api::{Client, OrgId};
+
+/// A policy which applies, and what made it apply to the subject.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Match {
+    pub id: PolicyId,
+    pub name: String,
+    pub effect: Effect,
+    /// The entries of the policy's subjects which matched, or all of them
+    /// when no subject was given.
+    pub via: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evaluation {
+    pub action: Action,
+    pub resource: String,
+    /// Who the check is for. When it's not given the check is the API's
+    /// answer for the caller, and the matches are the policies covering the
+    /// action and resource whoever they're for.
+    pub subject: Option<String>,
+    pub allowed: bool,
+    pub matches: Vec<Match>,
+    /// True if a policy denied the action, overriding those which allowed
+    /// it.
+    pub deny_won: bool,
+}
+
+// The generated `PolicyAllowedRequest` has the types of its fields the wrong
+// way round, so the request is sent as the API expects it here.
+#[derive(Serialize)]
+struct PolicyAllowedRequest<'a> {
+    action: &'a Action,
+    resource: &'a str,
+}
+
+/// Asks the API whether the caller may do `action` on `resource`.
+pub async fn allowed(
+    client: &Client,
+    action: &Action,
+    resource: &str,
+) -> Result<bool, esc_api::Error> {
+    let request = PolicyAllowedRequest { action, resource };
+    let response = client
+        .send_request::<_, access::PolicyAllowedResponse>(
+            reqwest::Method::POST,
+            "/access/v1/policy/allowed".to_string(),
+            Some(&request),
+            None,
+        )
+        .await?;
+    Ok(response.allowed)
+}
+
+fn covers(pattern: &str, resource: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => resource.starts_with(prefix),
+        None => pattern == resource,
+    }
+}
+
+/// The names a policy may use for the subject: itself, as an email or a
+/// member id, the groups it belongs to and `*`.
+fn identities(subject: &str, members: &[Member], groups: &[Group]) -> Vec<(String, String)> {
+    let mut identities = vec![
+        ("*".to_string(), "*".to_string()),
+        (subject.to_string(), subject.to_string()),
+    ];
+    if let Some(member) = members
+        .iter()
+        .find(|m| m.id.0 == subject || m.email == subject)
+    {
+        for name in &[&member.id.0, &member.email] {
+            if name.as_str() != subject {
+                identities.push((name.to_string(), name.to_string()));
+            }
+        }
+        for group in groups.iter().filter(|g| g.members.contains(&member.id)) {
+            identities.push((
+                group.id.0.clone(),
+                format!("group {} {:?}", group.id.0, group.name),
+            ));
+        }
+    }
+    identities
+}
+
+/// Evaluates the policies of an organization for the subject, or lists the
+/// policies covering the action and resource when there is none.
+pub fn evaluate(
+    policies: &[Policy],
+    groups: &[Group],
+    members: &[Member],
+    subject: Option<&str>,
+    action: Action,
+    resource: &str,
+) -> Evaluation {
+    let identities = subject.map(|subject| identities(subject, members, groups));
+    let mut matches = Vec::new();
+    for policy in policies {
+        let effect = match &policy.effect {
+            Some(effect) => effect.clone(),
+            None => continue,
+        };
+        let covered = policy
+            .resources
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .any(|pattern| covers(pattern, resource))
+            && policy
+                .actions
+                .as_deref()
+                .unwrap_or_default()
+                .contains(&action);
+        if !covered {
+            continue;
+        }
+        let subjects = policy.subjects.clone().unwrap_or_default();
+        let via: Vec<String> = match &identities {
+            Some(identities) => subjects
+                .iter()
+                .filter_map(|s| {
+                    identities
+                        .iter()
+                        .find(|(name, _)| name == s)
+                        .map(|(_, shown)| shown.clone())
+                })
+                .collect(),
+            None => subjects,
+        };
+        if via.is_empty() {
+            continue;
+        }
+        matches.push(Match {
+            id: policy.id.clone(),
+            name: policy.name.clone(),
+            effect,
+            via,
+        });
+    }
+    let denied = matches.iter().any(|m| m.effect == Effect::Deny);
+    let allowed = !denied && matches.iter().any(|m| m.effect == Effect::Allow);
+    Evaluation {
+        action,
+        resource: resource.to_string(),
+        subject: subject.map(str::to_string),
+        allowed,
+        deny_won: denied && matches.iter().any(|m| m.effect == Effect::Allow),
+        matches,
+    }
+}
+
+/// Checks the action for the subject, or for the caller when there's none.
+pub async fn check(
+    client: &Client,
+    org_id: OrgId,
+    subject: Option<String>,
+    action: Action,
+    resource: String,
+) -> Result<Evaluation, esc_api::Error> {
+    let policies = access::list_policies(client, org_id.clone())
+        .await?
+        .policies;
+    let groups = access::list_groups(client, org_id.clone(), None)
+        .await?
+        .groups;
+    let members = access::list_members(client, org_id).await?.members;
+    let mut evaluation = evaluate(
+        &policies,
+        &groups,
+        &members,
+        subject.as_deref(),
+        action,
+        &resource,
+    );
+    if subject.is_none() {
+        evaluation.allowed = allowed(client, &evaluation.action, &resource).await?;
+        evaluation.deny_won = false;
+    }
+    Ok(evaluation)
+}
@@ -0,0 +1,164 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use esc_api::{FailureEvent, Redactor, RequestEvent, ResponseEvent};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format: {}, expected text or json", s)),
+        }
+    }
+}
+
+/// Writes a line for every request sent to the API and every response that
+/// came back, to stderr or to a file, so stdout only ever holds the output
+/// of the command.
+pub struct RequestLog {
+    format: LogFormat,
+    include_bodies: bool,
+    redactor: Option<Redactor>,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl RequestLog {
+    pub fn stderr(format: LogFormat, include_bodies: bool) -> Self {
+        Self {
+            format,
+            include_bodies,
+            redactor: None,
+            out: Mutex::new(Box::new(std::io::stderr())),
+        }
+    }
+
+    /// Appends to the given file, creating it if needed.
+    pub fn file(
+        path: &Path,
+        format: LogFormat,
+        include_bodies: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("Could not open {}: {}", path.display(), err))?;
+        Ok(Self {
+            format,
+            include_bodies,
+            redactor: None,
+            out: Mutex::new(Box::new(file)),
+        })
+    }
+
+    /// Masks secrets in the bodies which are logged.
+    pub fn redact(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    pub fn request(&self, event: &RequestEvent) {
+        match self.format {
+            LogFormat::Text => {
+                let mut text = format!("[{}] {} {}", event.request_id, event.method, event.url);
+                if self.include_bodies {
+                    for (name, value) in event.headers.iter() {
+                        text.push_str(&format!("\n{}: {}", name, value));
+                    }
+                    if let Some(body) = event.body.as_deref() {
+                        text.push_str(&format!("\n{}", self.redacted(body)));
+                    }
+                }
+                self.write(&text);
+            }
+            LogFormat::Json => {
+                let mut event = event.clone();
+                event.body = match event.body {
+                    Some(body) if self.include_bodies => Some(self.redacted(&body)),
+                    _ => None,
+                };
+                self.write_json("request", &event);
+            }
+        }
+    }
+
+    pub fn response(&self, event: &ResponseEvent) {
+        match self.format {
+            LogFormat::Text => {
+                let mut text = format!(
+                    "[{}] {} {} bytes in {}ms",
+                    event.request_id,
+                    event.status,
+                    event.size,
+                    event.elapsed.as_millis()
+                );
+                if event.attempts > 1 {
+                    text.push_str(&format!(" after {} attempts", event.attempts));
+                }
+                if self.include_bodies {
+                    for (name, value) in event.headers.iter() {
+                        text.push_str(&format!("\n{}: {}", name, value));
+                    }
+                    if !event.body.is_empty() {
+                        text.push_str(&format!("\n{}", self.redacted(&event.body)));
+                    }
+                }
+                self.write(&text);
+            }
+            LogFormat::Json => {
+                let mut event = event.clone();
+                event.body = if self.include_bodies {
+                    self.redacted(&event.body)
+                } else {
+                    String::new()
+                };
+                self.write_json("response", &event);
+            }
+        }
+    }
+
+    pub fn failure(&self, event: &FailureEvent) {
+        match self.format {
+            LogFormat::Text => self.write(&format!(
+                "[{}] failed after {}ms: {}",
+                event.request_id,
+                event.elapsed.as_millis(),
+                event.error
+            )),
+            LogFormat::Json => self.write_json("failure", event),
+        }
+    }
+
+    fn redacted(&self, body: &str) -> String {
+        match self.redactor.as_ref() {
+            Some(redactor) => redactor.redact(body),
+            None => body.to_string(),
+        }
+    }
+
+    fn write_json<E: Serialize>(&self, kind: &str, event: &E) {
+        let mut value = serde_json::to_value(event).expect("events serialize");
+        if let serde_json::Value::Object(fields) = &mut value {
+            fields.insert("event".to_string(), kind.into());
+            fields.insert("time".to_string(), chrono::Utc::now().to_rfc3339().into());
+        }
+        self.write(&value.to_string());
+    }
+
+    fn write(&self, line: &str) {
+        let mut out = self.out.lock().expect("request log lock was poisoned");
+        // Losing a log line isn't worth failing the command over.
+        let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+    }
+}
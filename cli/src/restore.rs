@@ -0,0 +1,246 @@
+use esc_api::infra::NetworkId;
+use esc_api::mesdb::{
+    self, Backup, ClusterStatus, CreateClusterRequest, CreateClusterResponse, ProjectionLevel,
+    Topology,
+};
+use esc_api::resources::ProjectId;
+use esc_api::{Client, ClusterId, OrgId};
+
+use crate::wait::{self, ClusterTarget};
+
+/// What the restored cluster looks like where it shouldn't be like the
+/// cluster the backup was taken from, or where that cluster is gone.
+#[derive(Debug, Default)]
+pub struct ClusterSpec {
+    pub network_id: Option<NetworkId>,
+    pub acl_id: Option<String>,
+    pub description: Option<String>,
+    pub instance_type: Option<String>,
+    pub disk_size_gb: Option<i32>,
+    pub disk_type: Option<String>,
+    pub topology: Option<Topology>,
+    pub projection_level: Option<ProjectionLevel>,
+}
+
+/// Builds the request creating a cluster from the backup in the target
+/// project, copying what isn't given from the backed up cluster.
+pub async fn restore_request(
+    client: &Client,
+    org_id: OrgId,
+    target_project_id: &ProjectId,
+    backup: &Backup,
+    spec: ClusterSpec,
+) -> Result<CreateClusterRequest, Box<dyn std::error::Error>> {
+    let source = mesdb::get_cluster(
+        client,
+        org_id,
+        backup.project_id.clone(),
+        backup.source_cluster_id.clone(),
+    )
+    .await;
+    let source = match source {
+        Ok(resp) => Some(resp.cluster),
+        Err(err) if err.is_not_found() => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let missing = |flag: &str| -> Box<dyn std::error::Error> {
+        format!(
+            "cluster {} the backup was taken from doesn't exist anymore, so {} must be given",
+            backup.source_cluster_id, flag
+        )
+        .into()
+    };
+    macro_rules! from_spec_or_source {
+        ($field:ident, $source_field:ident, $flag:expr) => {
+            match spec.$field {
+                Some(value) => value,
+                None => match source.as_ref() {
+                    Some(cluster) => cluster.$source_field.clone(),
+                    None => return Err(missing($flag)),
+                },
+            }
+        };
+    }
+    let network_id = from_spec_or_source!(network_id, network_id, "--network-id");
+    let instance_type = from_spec_or_source!(instance_type, instance_type, "--instance-type");
+    let disk_size_gb = from_spec_or_source!(disk_size_gb, disk_size_gb, "--disk-size-in-gb");
+    let disk_type = from_spec_or_source!(disk_type, disk_type, "--disk-type");
+    let topology = from_spec_or_source!(topology, topology, "--topology");
+    let projection_level =
+        from_spec_or_source!(projection_level, projection_level, "--projection-level");
+
+    // The source's disk performance only applies to its kind of disk.
+    let same_disk = source
+        .as_ref()
+        .filter(|cluster| cluster.disk_type == disk_type);
+    let same_project = backup.project_id == *target_project_id;
+    let source_backup_project_id = if same_project {
+        None
+    } else {
+        Some(backup.project_id.to_string())
+    };
+    // ACLs belong to a project, so the source's can only be reused in it.
+    // Elsewhere one must be given rather than letting the copy be reachable
+    // from anywhere.
+    let source_public_access = source.as_ref().and_then(|cluster| cluster.public_access);
+    let source_acl_id = source.as_ref().and_then(|cluster| cluster.acl_id.clone());
+    let (acl_id, public_access) = match spec.acl_id {
+        Some(acl_id) => (Some(acl_id), source_public_access),
+        None if same_project => (source_acl_id, source_public_access),
+        None => match source_acl_id {
+            Some(source_acl_id) => {
+                return Err(format!(
+                    "cluster {} uses ACL {} of project {}, which can't be used in project {}, so --acl-id must be given",
+                    backup.source_cluster_id, source_acl_id, backup.project_id, target_project_id
+                )
+                .into())
+            }
+            None => (None, None),
+        },
+    };
+    Ok(CreateClusterRequest {
+        acl_id,
+        description: spec.description.unwrap_or_else(|| {
+            format!(
+                "{} (restored from {})",
+                backup.source_cluster_description, backup.id
+            )
+        }),
+        disk_iops: same_disk.and_then(|cluster| cluster.disk_iops),
+        disk_size_gb,
+        disk_throughput: same_disk.and_then(|cluster| cluster.disk_throughput),
+        disk_type,
+        instance_type,
+        network_id,
+        projection_level,
+        server_version: backup.server_version.clone(),
+        source_backup_id: Some(backup.id.to_string()),
+        source_backup_project_id,
+        source_node_index: None,
+        topology,
+        protected: None,
+        public_access,
+    })
+}
+
+/// Copies a cluster into another project by backing it up and restoring the
+/// backup there.
+pub struct ClusterClone {
+    pub org_id: OrgId,
+    pub project_id: ProjectId,
+    pub cluster_id: ClusterId,
+    pub target_project_id: ProjectId,
+    pub target_network_id: NetworkId,
+    pub description: Option<String>,
+    /// The ACL of the copy, which must be given if the cluster has one.
+    pub acl_id: Option<String>,
+    /// Deletes the backup once the copy is available. If anything fails it's
+    /// kept, so the copy can be restored from it by hand.
+    pub ephemeral_backup: bool,
+    /// Waits for the copy to be available.
+    pub wait: bool,
+    pub timeout: std::time::Duration,
+}
+
+impl ClusterClone {
+    /// Runs the clone, printing progress to stderr. `lookups` sends the
+    /// requests whose responses aren't the output of the command.
+    pub async fn run(
+        self,
+        client: &Client,
+        lookups: &Client,
+    ) -> Result<CreateClusterResponse, Box<dyn std::error::Error>> {
+        let source = mesdb::get_cluster(
+            lookups,
+            self.org_id.clone(),
+            self.project_id.clone(),
+            self.cluster_id.clone(),
+        )
+        .await?
+        .cluster;
+        let backup_id = mesdb::create_backup(
+            lookups,
+            self.org_id.clone(),
+            self.project_id.clone(),
+            mesdb::CreateBackupRequest {
+                description: format!("Clone of {}", source.description),
+                source_cluster_id: self.cluster_id.clone(),
+            },
+        )
+        .await?
+        .id;
+        eprintln!("Backing up cluster {} as {}", self.cluster_id, backup_id);
+
+        let result = self.restore(client, lookups, &backup_id).await;
+
+        if self.ephemeral_backup {
+            if result.is_err() {
+                eprintln!("Keeping backup {} as the copy isn't available", backup_id);
+                return result;
+            }
+            eprintln!("Deleting backup {}", backup_id);
+            mesdb::delete_backup(lookups, self.org_id, self.project_id, backup_id).await?;
+        }
+        result
+    }
+
+    async fn restore(
+        &self,
+        client: &Client,
+        lookups: &Client,
+        backup_id: &esc_api::BackupId,
+    ) -> Result<CreateClusterResponse, Box<dyn std::error::Error>> {
+        wait::wait_for_backup(
+            lookups,
+            self.org_id.clone(),
+            self.project_id.clone(),
+            backup_id.clone(),
+            self.timeout,
+        )
+        .await?;
+        let backup = mesdb::get_backup(
+            lookups,
+            self.org_id.clone(),
+            self.project_id.clone(),
+            backup_id.clone(),
+        )
+        .await?
+        .backup;
+        let request = restore_request(
+            lookups,
+            self.org_id.clone(),
+            &self.target_project_id,
+            &backup,
+            ClusterSpec {
+                network_id: Some(self.target_network_id.clone()),
+                acl_id: self.acl_id.clone(),
+                description: self.description.clone(),
+                ..ClusterSpec::default()
+            },
+        )
+        .await?;
+        let resp = mesdb::create_cluster(
+            client,
+            self.org_id.clone(),
+            self.target_project_id.clone(),
+            request,
+        )
+        .await?;
+        eprintln!("Restoring backup {} as cluster {}", backup_id, resp.id);
+
+        // The backup can only go once the cluster restored from it is done.
+        if self.wait || self.ephemeral_backup {
+            wait::wait_for_cluster(
+                lookups,
+                self.org_id.clone(),
+                self.target_project_id.clone(),
+                resp.id.clone(),
+                ClusterTarget::Status(ClusterStatus::Available),
+                self.timeout,
+            )
+            .await?;
+        }
+        Ok(resp)
+    }
+}
@@ -0,0 +1,597 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use esc_api::infra::{
+    self, AclCidrBlock, AclId, CreateAclRequest, CreateNetworkRequest, CreatePeeringRequest,
+    NetworkId, NetworkStatus, PeeringId, PeeringStatus, UpdateAclRequest,
+};
+use esc_api::integrate::{
+    self, CreateIntegrationData, CreateIntegrationRequest, IntegrationId, UpdateIntegrationData,
+    UpdateIntegrationRequest,
+};
+use esc_api::mesdb::{
+    self, ClusterStatus, CreateClusterRequest, ExpandClusterDiskRequest, ResizeClusterRequest,
+    UpdateClusterRequest,
+};
+use esc_api::orchestrate::{self, CreateJobRequest, JobData, JobId, ScheduledBackupData};
+use esc_api::resources::ProjectId;
+use esc_api::{Client, ClusterId, OrgId, WaitError, WaitOptions};
+
+use super::live::Live;
+use super::plan::{self, Action, Kind, Operation};
+use super::Spec;
+use crate::wait::{self, ClusterTarget};
+
+/// What `esc apply` did, and what it left alone.
+#[derive(Debug, Default, Serialize)]
+pub struct Applied {
+    pub applied: Vec<Action>,
+    /// Deletes and replacements, when they aren't allowed.
+    pub skipped: Vec<Action>,
+}
+
+impl Applied {
+    /// How many resources still differ from the spec because replacing them
+    /// needed `--prune`.
+    pub fn skipped_replacements(&self) -> usize {
+        self.skipped
+            .iter()
+            .filter(|action| action.operation == Operation::Replace)
+            .count()
+    }
+}
+
+/// Makes the resources of a project match a spec.
+pub struct Apply {
+    pub org_id: OrgId,
+    pub project_id: ProjectId,
+    /// Deletes resources which aren't in the spec, and replaces those which
+    /// can't be changed in place. Without it, only creates and updates run.
+    pub prune: bool,
+    /// How long to wait for each resource to be ready.
+    pub timeout: Duration,
+}
+
+impl Apply {
+    /// Plans and runs the changes, printing progress to stderr. Every
+    /// request goes through `client`, which shouldn't print responses.
+    /// Skipped replacements aren't an error here, so that the caller can show
+    /// what was applied before failing.
+    pub async fn run(
+        &self,
+        client: &Client,
+        spec: &Spec,
+    ) -> Result<Applied, Box<dyn std::error::Error>> {
+        let live = Live::fetch(client, &self.org_id, &self.project_id).await?;
+        let plan = plan::plan(spec, &live)?;
+        if plan.is_empty() {
+            eprintln!("Nothing to do, the project matches the spec");
+        }
+
+        let mut applied = Applied::default();
+        let (allowed, skipped): (Vec<Action>, Vec<Action>) =
+            plan.actions.iter().cloned().partition(|action| {
+                self.prune || !matches!(action.operation, Operation::Replace | Operation::Delete)
+            });
+        for action in &skipped {
+            eprintln!(
+                "Skipping: {} {} needs --prune to be {}d",
+                action.kind,
+                action.name,
+                match action.operation {
+                    Operation::Replace => "replace",
+                    _ => "delete",
+                }
+            );
+        }
+        applied.skipped = skipped;
+
+        let mut ids = Ids::new(&live);
+
+        // What gets replaced is removed first, dependents first, so that its
+        // name is free again.
+        let mut replacements = operations(&allowed, Operation::Replace);
+        replacements.sort_by_key(|action| std::cmp::Reverse(action.kind));
+        for action in replacements {
+            self.remove(client, action).await?;
+            ids.forget(action.kind, &action.name);
+        }
+
+        for action in allowed
+            .iter()
+            .filter(|action| action.operation != Operation::Delete)
+        {
+            let mut action = action.clone();
+            match action.operation {
+                Operation::Create | Operation::Replace => {
+                    let id = self.create(client, spec, &ids, &action).await?;
+                    ids.insert(action.kind, &action.name, &id);
+                    action.id = Some(id);
+                }
+                _ => self.update(client, spec, &live, &ids, &action).await?,
+            }
+            applied.applied.push(action);
+        }
+
+        // Deletes go last, so that nothing is lost if a create or update
+        // fails, and dependents go first.
+        let mut deletes = operations(&allowed, Operation::Delete);
+        deletes.sort_by_key(|action| std::cmp::Reverse(action.kind));
+        for action in deletes {
+            self.remove(client, action).await?;
+            applied.applied.push(action.clone());
+        }
+        Ok(applied)
+    }
+
+    fn ids(&self) -> (OrgId, ProjectId) {
+        (self.org_id.clone(), self.project_id.clone())
+    }
+
+    fn wait_options(&self) -> WaitOptions {
+        WaitOptions::default().timeout(self.timeout)
+    }
+
+    /// Deletes the resource of the action, and waits until it's gone if
+    /// something it held on to may be deleted next.
+    async fn remove(
+        &self,
+        client: &Client,
+        action: &Action,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let id = action.id.clone().unwrap_or_default();
+        let (org_id, project_id) = self.ids();
+        eprintln!("Deleting {} {} ({})", action.kind, action.name, id);
+        match action.kind {
+            Kind::Network => {
+                infra::delete_network(client, org_id, project_id, NetworkId(id)).await?
+            }
+            Kind::Acl => infra::delete_acl(client, org_id, project_id, AclId(id)).await?,
+            Kind::Peering => {
+                let id = PeeringId(id);
+                infra::delete_peering(client, org_id.clone(), project_id.clone(), id.clone())
+                    .await?;
+                let result = infra::wait_for_peering(
+                    client,
+                    org_id,
+                    project_id,
+                    id,
+                    &self.wait_options(),
+                    |peering| peering.status == PeeringStatus::Deleted,
+                )
+                .await;
+                gone(result)?;
+            }
+            Kind::Cluster => {
+                let id = ClusterId(id);
+                mesdb::delete_cluster(client, org_id.clone(), project_id.clone(), id.clone())
+                    .await?;
+                wait::wait_for_cluster(
+                    client,
+                    org_id,
+                    project_id,
+                    id,
+                    ClusterTarget::Deleted,
+                    self.timeout,
+                )
+                .await?;
+            }
+            Kind::Job => orchestrate::delete_job(client, org_id, project_id, JobId(id)).await?,
+            Kind::Integration => {
+                integrate::delete_integration(client, org_id, project_id, IntegrationId(id)).await?
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the resource of the action, returning its id once it can be
+    /// used by the resources which refer to it.
+    async fn create(
+        &self,
+        client: &Client,
+        spec: &Spec,
+        ids: &Ids,
+        action: &Action,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let (org_id, project_id) = self.ids();
+        eprintln!("Creating {} {}", action.kind, action.name);
+        let name = action.name.clone();
+        let id = match action.kind {
+            Kind::Network => {
+                let network = find(&spec.networks, &name, |n| &n.name);
+                let id = infra::create_network(
+                    client,
+                    org_id.clone(),
+                    project_id.clone(),
+                    CreateNetworkRequest {
+                        cidr_block: network.cidr_block.clone(),
+                        description: name,
+                        provider: network.provider.clone(),
+                        public_access: network.public_access,
+                        region: network.region.clone(),
+                    },
+                )
+                .await?
+                .id;
+                // Clusters can only be created in available networks.
+                infra::wait_for_network(
+                    client,
+                    org_id,
+                    project_id,
+                    id.clone(),
+                    &self.wait_options(),
+                    |network| network.status == NetworkStatus::Available,
+                )
+                .await?;
+                id.0
+            }
+            Kind::Acl => {
+                let acl = find(&spec.acls, &name, |a| &a.name);
+                infra::create_acl(
+                    client,
+                    org_id,
+                    project_id,
+                    CreateAclRequest {
+                        cidr_blocks: cidr_blocks(acl),
+                        description: name,
+                    },
+                )
+                .await?
+                .id
+                .0
+            }
+            Kind::Peering => {
+                let peering = find(&spec.peerings, &name, |p| &p.name);
+                infra::create_peering(
+                    client,
+                    org_id,
+                    project_id,
+                    CreatePeeringRequest {
+                        network_id: NetworkId(ids.get(Kind::Network, &peering.network)?),
+                        description: name,
+                        peer_account_id: peering.peer_account_id.clone(),
+                        peer_network_id: peering.peer_network_id.clone(),
+                        peer_network_region: peering.peer_network_region.clone(),
+                        routes: peering.routes.clone(),
+                    },
+                )
+                .await?
+                .id
+                .0
+            }
+            Kind::Cluster => {
+                let cluster = find(&spec.clusters, &name, |c| &c.name);
+                let acl_id = match &cluster.acl {
+                    Some(acl) => Some(ids.get(Kind::Acl, acl)?),
+                    None => None,
+                };
+                let id = mesdb::create_cluster(
+                    client,
+                    org_id.clone(),
+                    project_id.clone(),
+                    CreateClusterRequest {
+                        acl_id,
+                        description: name,
+                        disk_size_gb: cluster.disk_size_gb,
+                        disk_type: cluster.disk_type.clone(),
+                        disk_iops: cluster.disk_iops,
+                        disk_throughput: cluster.disk_throughput,
+                        instance_type: cluster.instance_type.clone(),
+                        network_id: NetworkId(ids.get(Kind::Network, &cluster.network)?),
+                        projection_level: cluster.projection_level.clone(),
+                        server_version: cluster.server_version.clone(),
+                        source_backup_id: None,
+                        source_node_index: None,
+                        topology: cluster.topology.clone(),
+                        protected: Some(cluster.protected),
+                        public_access: cluster.public_access,
+                        source_backup_project_id: None,
+                    },
+                )
+                .await?
+                .id;
+                let available = ClusterTarget::Status(ClusterStatus::Available);
+                self.wait_for_cluster(client, &id, available).await?;
+                id.0
+            }
+            Kind::Job => {
+                let job = find(&spec.jobs, &name, |j| &j.name);
+                orchestrate::create_job(
+                    client,
+                    org_id,
+                    project_id,
+                    CreateJobRequest {
+                        data: JobData::ScheduledBackup(ScheduledBackupData {
+                            cluster_id: ClusterId(ids.get(Kind::Cluster, &job.cluster)?),
+                            description: job
+                                .backup_description
+                                .clone()
+                                .unwrap_or_else(|| name.clone()),
+                            max_backup_count: job.max_backup_count,
+                        }),
+                        description: name,
+                        schedule: job.schedule.clone(),
+                    },
+                )
+                .await?
+                .id
+                .0
+            }
+            Kind::Integration => {
+                let integration = find(&spec.integrations, &name, |i| &i.name);
+                let mut data = serde_json::Map::new();
+                data.insert("sink".to_string(), integration.sink.clone().into());
+                data.insert("source".to_string(), integration.source.clone().into());
+                if !integration.clusters.is_empty() {
+                    data.insert(
+                        "clusterIds".to_string(),
+                        ids.clusters(&integration.clusters)?.into(),
+                    );
+                }
+                for (key, value) in &integration.settings {
+                    data.insert(plan::camel_case(key), plan::resolve(value)?.into());
+                }
+                let data: CreateIntegrationData = serde_json::from_value(data.into())
+                    .map_err(|err| format!("Invalid settings for integration {}: {}", name, err))?;
+                integrate::create_integration(
+                    client,
+                    org_id,
+                    project_id,
+                    CreateIntegrationRequest {
+                        data,
+                        description: name,
+                    },
+                )
+                .await?
+                .id
+                .0
+            }
+        };
+        Ok(id)
+    }
+
+    /// Makes the changes of the action which can be made in place.
+    async fn update(
+        &self,
+        client: &Client,
+        spec: &Spec,
+        live: &Live,
+        ids: &Ids,
+        action: &Action,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (org_id, project_id) = self.ids();
+        let id = action.id.clone().unwrap_or_default();
+        eprintln!("Updating {} {} ({})", action.kind, action.name, id);
+        match action.kind {
+            Kind::Acl => {
+                let acl = find(&spec.acls, &action.name, |a| &a.name);
+                infra::update_acl(
+                    client,
+                    org_id,
+                    project_id,
+                    AclId(id),
+                    UpdateAclRequest {
+                        cidr_blocks: Some(cidr_blocks(acl)),
+                        description: None,
+                    },
+                )
+                .await?;
+            }
+            Kind::Cluster => {
+                let cluster = find(&spec.clusters, &action.name, |c| &c.name);
+                let id = ClusterId(id);
+                // Each of these needs the cluster to be done with the last.
+                let available = ClusterTarget::Status(ClusterStatus::Available);
+                self.wait_for_cluster(client, &id, available.clone())
+                    .await?;
+                if action.changes("instance-type") {
+                    mesdb::resize_cluster(
+                        client,
+                        org_id.clone(),
+                        project_id.clone(),
+                        id.clone(),
+                        ResizeClusterRequest {
+                            target_size: cluster.instance_type.clone(),
+                        },
+                    )
+                    .await?;
+                    let resized = ClusterTarget::InstanceType(cluster.instance_type.clone());
+                    self.wait_for_cluster(client, &id, resized).await?;
+                }
+                if ["disk-size-gb", "disk-type", "disk-iops", "disk-throughput"]
+                    .iter()
+                    .any(|field| action.changes(field))
+                {
+                    mesdb::expand_cluster_disk(
+                        client,
+                        org_id.clone(),
+                        project_id.clone(),
+                        id.clone(),
+                        ExpandClusterDiskRequest {
+                            disk_size_gb: cluster.disk_size_gb,
+                            disk_iops: cluster.disk_iops,
+                            disk_throughput: cluster.disk_throughput,
+                            disk_type: Some(cluster.disk_type.clone()),
+                        },
+                    )
+                    .await?;
+                    let expanded = ClusterTarget::DiskSize(cluster.disk_size_gb);
+                    self.wait_for_cluster(client, &id, expanded).await?;
+                }
+                if action.changes("acl") || action.changes("protected") {
+                    let acl_id = match &cluster.acl {
+                        Some(acl) => Some(ids.get(Kind::Acl, acl)?),
+                        None => None,
+                    };
+                    mesdb::update_cluster(
+                        client,
+                        org_id,
+                        project_id,
+                        id.clone(),
+                        UpdateClusterRequest {
+                            acl_id,
+                            description: None,
+                            protected: Some(cluster.protected),
+                        },
+                    )
+                    .await?;
+                    self.wait_for_cluster(client, &id, available).await?;
+                }
+            }
+            Kind::Integration => {
+                let integration = find(&spec.integrations, &action.name, |i| &i.name);
+                let mut data = serde_json::Map::new();
+                for change in &action.changes {
+                    if let Some(key) = change.field.strip_prefix("settings.") {
+                        let value = plan::resolve(&integration.settings[key])?;
+                        data.insert(plan::camel_case(key), value.into());
+                    }
+                }
+                if action.changes("clusters") {
+                    data.insert(
+                        "clusterIds".to_string(),
+                        ids.clusters(&integration.clusters)?.into(),
+                    );
+                }
+                let sink = live
+                    .integrations
+                    .get(&action.name)
+                    .map(|current| serde_json::to_value(&current.data))
+                    .transpose()?
+                    .and_then(|data| data.get("sink").and_then(|s| s.as_str().map(String::from)))
+                    .unwrap_or_default();
+                integrate::update_integration(
+                    client,
+                    org_id,
+                    project_id,
+                    IntegrationId(id),
+                    UpdateIntegrationRequest {
+                        data: Some(update_integration_data(&sink, data.into())?),
+                        description: None,
+                    },
+                )
+                .await?;
+            }
+            // Networks, peerings and jobs only change by being replaced.
+            Kind::Network | Kind::Peering | Kind::Job => {}
+        }
+        Ok(())
+    }
+
+    async fn wait_for_cluster(
+        &self,
+        client: &Client,
+        id: &ClusterId,
+        target: ClusterTarget,
+    ) -> Result<(), WaitError> {
+        let (org_id, project_id) = self.ids();
+        wait::wait_for_cluster(client, org_id, project_id, id.clone(), target, self.timeout).await
+    }
+}
+
+/// Ids of the resources, by kind and name.
+struct Ids(BTreeMap<(Kind, String), String>);
+
+impl Ids {
+    fn new(live: &Live) -> Ids {
+        let mut ids = BTreeMap::new();
+        let mut add = |kind: Kind, name: &String, id: &String| {
+            ids.insert((kind, name.clone()), id.clone());
+        };
+        live.networks
+            .iter()
+            .for_each(|(n, r)| add(Kind::Network, n, &r.id.0));
+        live.acls
+            .iter()
+            .for_each(|(n, r)| add(Kind::Acl, n, &r.id.0));
+        live.peerings
+            .iter()
+            .for_each(|(n, r)| add(Kind::Peering, n, &r.id.0));
+        live.clusters
+            .iter()
+            .for_each(|(n, r)| add(Kind::Cluster, n, &r.id.0));
+        live.jobs
+            .iter()
+            .for_each(|(n, r)| add(Kind::Job, n, &r.id.0));
+        live.integrations
+            .iter()
+            .for_each(|(n, r)| add(Kind::Integration, n, &r.id.0));
+        Ids(ids)
+    }
+
+    fn get(&self, kind: Kind, name: &str) -> Result<String, String> {
+        self.0
+            .get(&(kind, name.to_string()))
+            .cloned()
+            .ok_or_else(|| format!("{} {} doesn't exist", kind, name))
+    }
+
+    fn clusters(&self, names: &[String]) -> Result<Vec<String>, String> {
+        names
+            .iter()
+            .map(|name| self.get(Kind::Cluster, name))
+            .collect()
+    }
+
+    fn insert(&mut self, kind: Kind, name: &str, id: &str) {
+        self.0.insert((kind, name.to_string()), id.to_string());
+    }
+
+    fn forget(&mut self, kind: Kind, name: &str) {
+        self.0.remove(&(kind, name.to_string()));
+    }
+}
+
+fn find<'a, T>(resources: &'a [T], name: &str, name_of: impl Fn(&T) -> &String) -> &'a T {
+    resources
+        .iter()
+        .find(|resource| name_of(resource) == name)
+        .expect("planned resources are in the spec")
+}
+
+fn operations(actions: &[Action], operation: Operation) -> Vec<&Action> {
+    actions
+        .iter()
+        .filter(|action| action.operation == operation)
+        .collect()
+}
+
+fn cidr_blocks(acl: &super::AclSpec) -> Vec<AclCidrBlock> {
+    acl.cidr_blocks
+        .iter()
+        .map(|block| AclCidrBlock {
+            address: block.address.clone(),
+            comment: block.comment.clone(),
+        })
+        .collect()
+}
+
+/// Builds the update of an integration. The variants of the update can't
+/// be told apart by their fields, so the sink decides which one it is.
+fn update_integration_data(
+    sink: &str,
+    data: serde_json::Value,
+) -> Result<UpdateIntegrationData, Box<dyn std::error::Error>> {
+    use UpdateIntegrationData::*;
+    Ok(match sink {
+        "awsCloudWatchLogs" => {
+            UpdateAwsCloudWatchLogsIntegrationData(serde_json::from_value(data)?)
+        }
+        "awsCloudWatchMetrics" => {
+            UpdateAwsCloudWatchMetricsIntegrationData(serde_json::from_value(data)?)
+        }
+        "gcpLogging" => UpdateGcpLoggingIntegrationData(serde_json::from_value(data)?),
+        "opsGenie" => UpdateOpsGenieIntegrationData(serde_json::from_value(data)?),
+        "slack" => UpdateSlackIntegrationData(serde_json::from_value(data)?),
+        "pagerDuty" => UpdatePagerDutyIntegrationData(serde_json::from_value(data)?),
+        other => return Err(format!("Unknown integration sink {}", other).into()),
+    })
+}
+
+/// Deleted resources are only found for a while.
+fn gone<T>(result: Result<T, WaitError>) -> Result<(), WaitError> {
+    match result {
+        Err(err) if !err.is_not_found() => Err(err),
+        _ => Ok(()),
+    }
+}
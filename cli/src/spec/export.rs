@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use esc_api::orchestrate::JobData;
+
+use super::live::Live;
+use super::{
+    AclSpec, CidrBlockSpec, ClusterSpec, IntegrationSpec, JobSpec, NetworkSpec, PeeringSpec, Spec,
+};
+
+/// Describes the live resources of a project as a spec. Secrets which the
+/// API only shows masked, in `*Display` fields, are left out.
+pub fn export(live: &Live) -> Result<Spec, String> {
+    let mut spec = Spec::default();
+
+    for (name, network) in &live.networks {
+        spec.networks.push(NetworkSpec {
+            name: name.clone(),
+            provider: network.provider.clone(),
+            region: network.region.clone(),
+            cidr_block: network.cidr_block.clone(),
+            public_access: network.public_access,
+        });
+    }
+
+    for (name, acl) in &live.acls {
+        spec.acls.push(AclSpec {
+            name: name.clone(),
+            cidr_blocks: acl
+                .cidr_blocks
+                .iter()
+                .map(|block| CidrBlockSpec {
+                    address: block.address.clone(),
+                    comment: block.comment.clone(),
+                })
+                .collect(),
+        });
+    }
+
+    for (name, peering) in &live.peerings {
+        spec.peerings.push(PeeringSpec {
+            name: name.clone(),
+            network: reference(
+                live.network_name(&peering.network_id.0),
+                "peering",
+                name,
+                "network",
+            )?,
+            peer_account_id: peering.peer_account_id.clone(),
+            peer_network_id: peering.peer_network_id.clone(),
+            peer_network_region: peering.peer_network_region.clone(),
+            routes: peering.routes.clone(),
+        });
+    }
+
+    for (name, cluster) in &live.clusters {
+        let acl = match &cluster.acl_id {
+            Some(id) => Some(reference(live.acl_name(id), "cluster", name, "acl")?),
+            None => None,
+        };
+        spec.clusters.push(ClusterSpec {
+            name: name.clone(),
+            network: reference(
+                live.network_name(&cluster.network_id.0),
+                "cluster",
+                name,
+                "network",
+            )?,
+            acl,
+            topology: cluster.topology.clone(),
+            instance_type: cluster.instance_type.clone(),
+            disk_size_gb: cluster.disk_size_gb,
+            disk_type: cluster.disk_type.clone(),
+            disk_iops: cluster.disk_iops,
+            disk_throughput: cluster.disk_throughput,
+            server_version: cluster.server_version.clone(),
+            projection_level: cluster.projection_level.clone(),
+            protected: cluster.protected,
+            public_access: cluster.public_access,
+        });
+    }
+
+    for (name, job) in &live.jobs {
+        let JobData::ScheduledBackup(data) = &job.data;
+        spec.jobs.push(JobSpec {
+            name: name.clone(),
+            cluster: reference(live.job_cluster_name(job), "job", name, "cluster")?,
+            schedule: job.schedule.clone(),
+            max_backup_count: data.max_backup_count,
+            backup_description: Some(data.description.clone()).filter(|d| d != name),
+        });
+    }
+
+    for (name, integration) in &live.integrations {
+        let data = serde_json::to_value(&integration.data).map_err(|err| err.to_string())?;
+        let fields = data.as_object().cloned().unwrap_or_default();
+        let text = |key: &str| {
+            fields
+                .get(key)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let mut clusters = Vec::new();
+        let mut settings = BTreeMap::new();
+        for (key, value) in &fields {
+            match key.as_str() {
+                "sink" | "source" => {}
+                "clusterIds" => {
+                    for id in value.as_array().into_iter().flatten() {
+                        let id = id.as_str().unwrap_or_default();
+                        clusters.push(reference(
+                            live.cluster_name(id),
+                            "integration",
+                            name,
+                            "cluster",
+                        )?);
+                    }
+                }
+                key if key.ends_with("Display") => {}
+                key => {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        value => value.to_string(),
+                    };
+                    settings.insert(kebab_case(key), value);
+                }
+            }
+        }
+        spec.integrations.push(IntegrationSpec {
+            name: name.clone(),
+            sink: text("sink"),
+            source: text("source"),
+            clusters,
+            settings,
+        });
+    }
+
+    Ok(spec)
+}
+
+fn reference(name: Option<&str>, kind: &str, of: &str, target: &str) -> Result<String, String> {
+    name.map(String::from).ok_or_else(|| {
+        format!(
+            "{} {} refers to a {} which isn't in the project any more",
+            kind, of, target
+        )
+    })
+}
+
+/// Turns the name of a setting in the API into the one of the spec.
+fn kebab_case(key: &str) -> String {
+    let mut out = String::new();
+    for c in key.chars() {
+        if c.is_uppercase() {
+            out.push('-');
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
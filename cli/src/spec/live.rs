@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use esc_api::infra::{self, Acl, AclStatus, Network, NetworkStatus, Peering, PeeringStatus};
+use esc_api::integrate::{self, Integration, IntegrationStatus};
+use esc_api::mesdb::{self, Cluster, ClusterStatus};
+use esc_api::orchestrate::{self, Job, JobData};
+use esc_api::resources::ProjectId;
+use esc_api::{Client, OrgId};
+
+/// The resources of a project, by the name they have in a spec, which is
+/// their description. Resources on their way out are left out.
+#[derive(Debug, Default, Clone)]
+pub struct Live {
+    pub networks: BTreeMap<String, Network>,
+    pub acls: BTreeMap<String, Acl>,
+    pub peerings: BTreeMap<String, Peering>,
+    pub clusters: BTreeMap<String, Cluster>,
+    pub jobs: BTreeMap<String, Job>,
+    pub integrations: BTreeMap<String, Integration>,
+}
+
+impl Live {
+    pub async fn fetch(
+        client: &Client,
+        org_id: &OrgId,
+        project_id: &ProjectId,
+    ) -> Result<Live, Box<dyn std::error::Error>> {
+        let (org_id, project_id) = (org_id.clone(), project_id.clone());
+        let networks = infra::list_networks(client, org_id.clone(), project_id.clone())
+            .await?
+            .networks
+            .into_iter()
+            .filter(|n| !matches!(n.status, NetworkStatus::Deleting | NetworkStatus::Deleted));
+        let acls = infra::list_acls(client, org_id.clone(), project_id.clone())
+            .await?
+            .acls
+            .into_iter()
+            .filter(|a| a.status != AclStatus::Deleted);
+        let peerings = infra::list_peerings(client, org_id.clone(), project_id.clone())
+            .await?
+            .peerings
+            .into_iter()
+            .filter(|p| !matches!(p.status, PeeringStatus::Deleting | PeeringStatus::Deleted));
+        let clusters = mesdb::list_clusters(client, org_id.clone(), project_id.clone())
+            .await?
+            .clusters
+            .into_iter()
+            .filter(|c| !is_leaving(&c.status));
+        let jobs = orchestrate::list_jobs(client, org_id.clone(), project_id.clone())
+            .await?
+            .jobs;
+        let integrations = integrate::list_integrations(client, org_id, project_id)
+            .await?
+            .integrations
+            .into_iter()
+            .filter(|i| i.status != IntegrationStatus::Deleted);
+        Ok(Live {
+            networks: by_name("network", networks, |n| &n.description)?,
+            acls: by_name("acl", acls, |a| &a.description)?,
+            peerings: by_name("peering", peerings, |p| &p.description)?,
+            clusters: by_name("cluster", clusters, |c| &c.description)?,
+            jobs: by_name("job", jobs, |j| &j.description)?,
+            integrations: by_name("integration", integrations, |i| &i.description)?,
+        })
+    }
+
+    pub fn network_name(&self, id: &str) -> Option<&str> {
+        name_of(&self.networks, |n| n.id.0 == id)
+    }
+
+    pub fn acl_name(&self, id: &str) -> Option<&str> {
+        name_of(&self.acls, |a| a.id.0 == id)
+    }
+
+    pub fn cluster_name(&self, id: &str) -> Option<&str> {
+        name_of(&self.clusters, |c| c.id.0 == id)
+    }
+
+    /// Name of the cluster a job backs up.
+    pub fn job_cluster_name(&self, job: &Job) -> Option<&str> {
+        match &job.data {
+            JobData::ScheduledBackup(data) => self.cluster_name(&data.cluster_id.0),
+        }
+    }
+}
+
+pub fn is_leaving(status: &ClusterStatus) -> bool {
+    matches!(
+        status,
+        ClusterStatus::DeletingInstances
+            | ClusterStatus::InstancesDeleted
+            | ClusterStatus::DeletingDisks
+            | ClusterStatus::Deleted
+    )
+}
+
+fn by_name<T>(
+    kind: &str,
+    resources: impl IntoIterator<Item = T>,
+    name: impl Fn(&T) -> &String,
+) -> Result<BTreeMap<String, T>, String> {
+    let mut map = BTreeMap::new();
+    for resource in resources {
+        let key = name(&resource).clone();
+        if map.insert(key.clone(), resource).is_some() {
+            return Err(format!(
+                "there are several {}s described as {:?} in the project, so they can't be told apart",
+                kind, key
+            ));
+        }
+    }
+    Ok(map)
+}
+
+fn name_of<T>(map: &BTreeMap<String, T>, is: impl Fn(&T) -> bool) -> Option<&str> {
+    map.iter()
+        .find(|(_, resource)| is(resource))
+        .map(|(name, _)| name.as_str())
+}
@@ -0,0 +1,222 @@
+//! Declarative description of the infrastructure of a project. Resources are
+//! named, and a live resource is the one whose description is that name;
+//! resources refer to each other by those names rather than by ids.
+
+pub mod apply;
+pub mod export;
+pub mod live;
+pub mod plan;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use esc_api::mesdb::{ProjectionLevel, Topology};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Spec {
+    #[serde(default, rename = "network", skip_serializing_if = "Vec::is_empty")]
+    pub networks: Vec<NetworkSpec>,
+    #[serde(default, rename = "acl", skip_serializing_if = "Vec::is_empty")]
+    pub acls: Vec<AclSpec>,
+    #[serde(default, rename = "peering", skip_serializing_if = "Vec::is_empty")]
+    pub peerings: Vec<PeeringSpec>,
+    #[serde(default, rename = "cluster", skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<ClusterSpec>,
+    #[serde(default, rename = "job", skip_serializing_if = "Vec::is_empty")]
+    pub jobs: Vec<JobSpec>,
+    #[serde(default, rename = "integration", skip_serializing_if = "Vec::is_empty")]
+    pub integrations: Vec<IntegrationSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NetworkSpec {
+    pub name: String,
+    pub provider: String,
+    pub region: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cidr_block: Option<String>,
+    #[serde(default)]
+    pub public_access: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AclSpec {
+    pub name: String,
+    pub cidr_blocks: Vec<CidrBlockSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CidrBlockSpec {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PeeringSpec {
+    pub name: String,
+    /// Name of the network.
+    pub network: String,
+    pub peer_account_id: String,
+    pub peer_network_id: String,
+    pub peer_network_region: String,
+    pub routes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ClusterSpec {
+    pub name: String,
+    /// Name of the network.
+    pub network: String,
+    /// Name of the ACL, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acl: Option<String>,
+    pub topology: Topology,
+    pub instance_type: String,
+    pub disk_size_gb: i32,
+    pub disk_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_iops: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_throughput: Option<i32>,
+    /// Only used to create the cluster, upgrades are done separately.
+    pub server_version: String,
+    pub projection_level: ProjectionLevel,
+    #[serde(default)]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_access: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JobSpec {
+    pub name: String,
+    /// Name of the cluster to back up.
+    pub cluster: String,
+    pub schedule: String,
+    pub max_backup_count: i32,
+    /// Description of the backups. Defaults to the name of the job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IntegrationSpec {
+    pub name: String,
+    pub sink: String,
+    pub source: String,
+    /// Names of the clusters, for the sinks which take cluster ids.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clusters: Vec<String>,
+    /// The settings of the sink, such as `channel-id` for slack. A value
+    /// like `${NAME}` is read from the environment variable NAME, which keeps
+    /// secrets out of the file.
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+}
+
+impl Spec {
+    /// Reads a spec from a TOML file, or from a YAML one if its name ends
+    /// with .yaml or .yml.
+    pub fn load(path: &Path) -> Result<Spec, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+        let yaml = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("yaml") | Some("yml")
+        );
+        let spec: Spec = if yaml {
+            serde_yaml::from_str(&text)
+                .map_err(|err| format!("Invalid spec {}: {}", path.display(), err))?
+        } else {
+            toml::from_str(&text)
+                .map_err(|err| format!("Invalid spec {}: {}", path.display(), err))?
+        };
+        spec.validate()
+            .map_err(|err| format!("Invalid spec {}: {}", path.display(), err))?;
+        Ok(spec)
+    }
+
+    /// Checks that names are unique per kind of resource, and that every
+    /// reference is to a resource of the spec.
+    pub fn validate(&self) -> Result<(), String> {
+        let networks = unique("network", self.networks.iter().map(|n| &n.name))?;
+        let acls = unique("acl", self.acls.iter().map(|a| &a.name))?;
+        unique("peering", self.peerings.iter().map(|p| &p.name))?;
+        let clusters = unique("cluster", self.clusters.iter().map(|c| &c.name))?;
+        unique("job", self.jobs.iter().map(|j| &j.name))?;
+        unique("integration", self.integrations.iter().map(|i| &i.name))?;
+
+        let check =
+            |kind: &str, name: &str, names: &BTreeSet<&String>, what: &str, target: &str| {
+                if names.iter().any(|n| n.as_str() == target) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} {} refers to {} {}, which isn't in the spec",
+                        kind, name, what, target
+                    ))
+                }
+            };
+        for peering in &self.peerings {
+            check(
+                "peering",
+                &peering.name,
+                &networks,
+                "network",
+                &peering.network,
+            )?;
+        }
+        for cluster in &self.clusters {
+            check(
+                "cluster",
+                &cluster.name,
+                &networks,
+                "network",
+                &cluster.network,
+            )?;
+            if let Some(acl) = &cluster.acl {
+                check("cluster", &cluster.name, &acls, "acl", acl)?;
+            }
+        }
+        for job in &self.jobs {
+            check("job", &job.name, &clusters, "cluster", &job.cluster)?;
+        }
+        for integration in &self.integrations {
+            for cluster in &integration.clusters {
+                check(
+                    "integration",
+                    &integration.name,
+                    &clusters,
+                    "cluster",
+                    cluster,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unique<'a>(
+    kind: &str,
+    names: impl Iterator<Item = &'a String>,
+) -> Result<BTreeSet<&'a String>, String> {
+    let mut seen = BTreeSet::new();
+    for name in names {
+        if name.trim().is_empty() {
+            return Err(format!("a {} has no name", kind));
+        }
+        if !seen.insert(name) {
+            return Err(format!("there are two {}s named {}", kind, name));
+        }
+    }
+    Ok(seen)
+}
@@ -0,0 +1,602 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use esc_api::mesdb::Cluster;
+use esc_api::orchestrate::JobData;
+
+use super::live::Live;
+use super::{ClusterSpec, IntegrationSpec, Spec};
+
+/// Kinds of resources, in the order they depend on each other: a resource
+/// only refers to resources of the kinds before its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Network,
+    Acl,
+    Peering,
+    Cluster,
+    Job,
+    Integration,
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Kind::Network => "network",
+            Kind::Acl => "acl",
+            Kind::Peering => "peering",
+            Kind::Cluster => "cluster",
+            Kind::Job => "job",
+            Kind::Integration => "integration",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Create,
+    /// Changes the resource in place.
+    Update,
+    /// Deletes the resource and creates it again, for changes the API can't
+    /// make in place.
+    Replace,
+    /// Deletes a resource which isn't in the spec.
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// True if the API can't make the change in place.
+    pub forces_replacement: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Action {
+    pub kind: Kind,
+    pub name: String,
+    /// Id of the live resource, unless it's created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub operation: Operation,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+}
+
+impl Action {
+    pub fn changes(&self, field: &str) -> bool {
+        self.changes.iter().any(|change| change.field == field)
+    }
+}
+
+/// What it takes to make the live resources match the spec, in dependency
+/// order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn count(&self, operation: Operation) -> usize {
+        self.actions
+            .iter()
+            .filter(|action| action.operation == operation)
+            .count()
+    }
+
+    pub fn get(&self, kind: Kind, name: &str) -> Option<&Action> {
+        self.actions
+            .iter()
+            .find(|action| action.kind == kind && action.name == name)
+    }
+
+    /// True if the resource will have a new id.
+    fn is_new(&self, kind: Kind, name: &str) -> bool {
+        matches!(
+            self.get(kind, name).map(|action| action.operation),
+            Some(Operation::Create) | Some(Operation::Replace)
+        )
+    }
+}
+
+/// The error of `esc plan` when the project differs from the spec, which
+/// makes it exit with its own code.
+pub struct Drift {
+    pub changes: usize,
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The project differs from the spec: {} change(s) planned",
+            self.changes
+        )
+    }
+}
+
+impl std::fmt::Debug for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for Drift {}
+
+/// Records the differences of one resource, field by field.
+#[derive(Default)]
+struct Diff {
+    update: Vec<FieldChange>,
+    replace: Vec<FieldChange>,
+}
+
+impl Diff {
+    fn field<T: ToString + PartialEq>(&mut self, in_place: bool, field: &str, from: T, to: T) {
+        if from != to {
+            let change = FieldChange {
+                field: field.to_string(),
+                from: Some(from.to_string()),
+                to: Some(to.to_string()),
+                forces_replacement: !in_place,
+            };
+            match in_place {
+                true => self.update.push(change),
+                false => self.replace.push(change),
+            }
+        }
+    }
+
+    /// Records that a resource this one refers to gets a new id.
+    fn new_reference(&mut self, in_place: bool, field: &str, name: &str) {
+        let change = FieldChange {
+            field: field.to_string(),
+            from: None,
+            to: Some(format!("{} (new)", name)),
+            forces_replacement: !in_place,
+        };
+        match in_place {
+            true => self.update.push(change),
+            false => self.replace.push(change),
+        }
+    }
+
+    fn action(self, kind: Kind, name: &str, id: &str) -> Option<Action> {
+        let (operation, changes) = if !self.replace.is_empty() {
+            let mut changes = self.replace;
+            changes.extend(self.update);
+            (Operation::Replace, changes)
+        } else if !self.update.is_empty() {
+            (Operation::Update, self.update)
+        } else {
+            return None;
+        };
+        Some(Action {
+            kind,
+            name: name.to_string(),
+            id: Some(id.to_string()),
+            operation,
+            changes,
+        })
+    }
+}
+
+fn create(kind: Kind, name: &str) -> Action {
+    Action {
+        kind,
+        name: name.to_string(),
+        id: None,
+        operation: Operation::Create,
+        changes: Vec::new(),
+    }
+}
+
+fn show<T: serde::Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Compares the spec with the live resources. Resources which aren't in the
+/// spec are planned to be deleted.
+pub fn plan(spec: &Spec, live: &Live) -> Result<Plan, String> {
+    let mut plan = Plan::default();
+
+    for network in &spec.networks {
+        let action = match live.networks.get(&network.name) {
+            None => Some(create(Kind::Network, &network.name)),
+            Some(current) => {
+                let mut diff = Diff::default();
+                diff.field(false, "provider", &current.provider, &network.provider);
+                diff.field(false, "region", &current.region, &network.region);
+                if let Some(cidr_block) = &network.cidr_block {
+                    diff.field(
+                        false,
+                        "cidr-block",
+                        current.cidr_block.as_deref().unwrap_or(""),
+                        cidr_block,
+                    );
+                }
+                diff.field(
+                    false,
+                    "public-access",
+                    current.public_access,
+                    network.public_access,
+                );
+                diff.action(Kind::Network, &network.name, &current.id.0)
+            }
+        };
+        plan.actions.extend(action);
+    }
+
+    for acl in &spec.acls {
+        let action = match live.acls.get(&acl.name) {
+            None => Some(create(Kind::Acl, &acl.name)),
+            Some(current) => {
+                let mut diff = Diff::default();
+                let blocks = |blocks: Vec<(&str, Option<&str>)>| {
+                    blocks
+                        .iter()
+                        .map(|(address, comment)| match comment {
+                            Some(comment) => format!("{} ({})", address, comment),
+                            None => address.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                diff.field(
+                    true,
+                    "cidr-blocks",
+                    blocks(
+                        current
+                            .cidr_blocks
+                            .iter()
+                            .map(|b| (b.address.as_str(), b.comment.as_deref()))
+                            .collect(),
+                    ),
+                    blocks(
+                        acl.cidr_blocks
+                            .iter()
+                            .map(|b| (b.address.as_str(), b.comment.as_deref()))
+                            .collect(),
+                    ),
+                );
+                diff.action(Kind::Acl, &acl.name, &current.id.0)
+            }
+        };
+        plan.actions.extend(action);
+    }
+
+    for peering in &spec.peerings {
+        let action = match live.peerings.get(&peering.name) {
+            None => Some(create(Kind::Peering, &peering.name)),
+            Some(current) => {
+                let mut diff = Diff::default();
+                if plan.is_new(Kind::Network, &peering.network) {
+                    diff.new_reference(false, "network", &peering.network);
+                } else {
+                    diff.field(
+                        false,
+                        "network",
+                        live.network_name(&current.network_id.0).unwrap_or(""),
+                        &peering.network,
+                    );
+                }
+                diff.field(
+                    false,
+                    "peer-account-id",
+                    &current.peer_account_id,
+                    &peering.peer_account_id,
+                );
+                diff.field(
+                    false,
+                    "peer-network-id",
+                    &current.peer_network_id,
+                    &peering.peer_network_id,
+                );
+                diff.field(
+                    false,
+                    "peer-network-region",
+                    &current.peer_network_region,
+                    &peering.peer_network_region,
+                );
+                diff.field(
+                    false,
+                    "routes",
+                    current.routes.join(", "),
+                    peering.routes.join(", "),
+                );
+                diff.action(Kind::Peering, &peering.name, &current.id.0)
+            }
+        };
+        plan.actions.extend(action);
+    }
+
+    for cluster in &spec.clusters {
+        let action = match live.clusters.get(&cluster.name) {
+            None => Some(create(Kind::Cluster, &cluster.name)),
+            Some(current) => cluster_diff(&plan, live, cluster, current).action(
+                Kind::Cluster,
+                &cluster.name,
+                &current.id.0,
+            ),
+        };
+        plan.actions.extend(action);
+    }
+
+    for job in &spec.jobs {
+        let action = match live.jobs.get(&job.name) {
+            None => Some(create(Kind::Job, &job.name)),
+            Some(current) => {
+                let mut diff = Diff::default();
+                let JobData::ScheduledBackup(data) = &current.data;
+                if plan.is_new(Kind::Cluster, &job.cluster) {
+                    diff.new_reference(false, "cluster", &job.cluster);
+                } else {
+                    diff.field(
+                        false,
+                        "cluster",
+                        live.job_cluster_name(current).unwrap_or(""),
+                        &job.cluster,
+                    );
+                }
+                diff.field(false, "schedule", &current.schedule, &job.schedule);
+                diff.field(
+                    false,
+                    "max-backup-count",
+                    data.max_backup_count,
+                    job.max_backup_count,
+                );
+                diff.field(
+                    false,
+                    "backup-description",
+                    &data.description,
+                    job.backup_description.as_ref().unwrap_or(&job.name),
+                );
+                diff.action(Kind::Job, &job.name, &current.id.0)
+            }
+        };
+        plan.actions.extend(action);
+    }
+
+    for integration in &spec.integrations {
+        let action = match live.integrations.get(&integration.name) {
+            None => Some(create(Kind::Integration, &integration.name)),
+            Some(current) => {
+                let data = serde_json::to_value(&current.data).map_err(|err| err.to_string())?;
+                integration_diff(&plan, live, integration, &data)?.action(
+                    Kind::Integration,
+                    &integration.name,
+                    &current.id.0,
+                )
+            }
+        };
+        plan.actions.extend(action);
+    }
+
+    // Whatever isn't in the spec goes, dependents first.
+    let specified: BTreeMap<Kind, BTreeSet<&String>> = BTreeMap::from([
+        (
+            Kind::Network,
+            spec.networks.iter().map(|r| &r.name).collect(),
+        ),
+        (Kind::Acl, spec.acls.iter().map(|r| &r.name).collect()),
+        (
+            Kind::Peering,
+            spec.peerings.iter().map(|r| &r.name).collect(),
+        ),
+        (
+            Kind::Cluster,
+            spec.clusters.iter().map(|r| &r.name).collect(),
+        ),
+        (Kind::Job, spec.jobs.iter().map(|r| &r.name).collect()),
+        (
+            Kind::Integration,
+            spec.integrations.iter().map(|r| &r.name).collect(),
+        ),
+    ]);
+    let live_ids: Vec<(Kind, Vec<(&String, &String)>)> = vec![
+        (
+            Kind::Integration,
+            live.integrations
+                .iter()
+                .map(|(n, r)| (n, &r.id.0))
+                .collect(),
+        ),
+        (
+            Kind::Job,
+            live.jobs.iter().map(|(n, r)| (n, &r.id.0)).collect(),
+        ),
+        (
+            Kind::Cluster,
+            live.clusters.iter().map(|(n, r)| (n, &r.id.0)).collect(),
+        ),
+        (
+            Kind::Peering,
+            live.peerings.iter().map(|(n, r)| (n, &r.id.0)).collect(),
+        ),
+        (
+            Kind::Acl,
+            live.acls.iter().map(|(n, r)| (n, &r.id.0)).collect(),
+        ),
+        (
+            Kind::Network,
+            live.networks.iter().map(|(n, r)| (n, &r.id.0)).collect(),
+        ),
+    ];
+    for (kind, resources) in live_ids {
+        for (name, id) in resources {
+            if !specified[&kind].contains(name) {
+                plan.actions.push(Action {
+                    kind,
+                    name: name.clone(),
+                    id: Some(id.clone()),
+                    operation: Operation::Delete,
+                    changes: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(plan)
+}
+
+fn cluster_diff(plan: &Plan, live: &Live, spec: &ClusterSpec, current: &Cluster) -> Diff {
+    let mut diff = Diff::default();
+    if plan.is_new(Kind::Network, &spec.network) {
+        diff.new_reference(false, "network", &spec.network);
+    } else {
+        diff.field(
+            false,
+            "network",
+            live.network_name(&current.network_id.0).unwrap_or(""),
+            &spec.network,
+        );
+    }
+    diff.field(
+        false,
+        "topology",
+        show(&current.topology),
+        show(&spec.topology),
+    );
+    diff.field(
+        false,
+        "projection-level",
+        show(&current.projection_level),
+        show(&spec.projection_level),
+    );
+    if let Some(public_access) = spec.public_access {
+        diff.field(
+            false,
+            "public-access",
+            current.public_access.unwrap_or(false),
+            public_access,
+        );
+    }
+    // Disks grow in place, but can't shrink.
+    diff.field(
+        spec.disk_size_gb >= current.disk_size_gb,
+        "disk-size-gb",
+        current.disk_size_gb,
+        spec.disk_size_gb,
+    );
+    diff.field(true, "disk-type", &current.disk_type, &spec.disk_type);
+    if let Some(iops) = spec.disk_iops {
+        diff.field(true, "disk-iops", current.disk_iops.unwrap_or(0), iops);
+    }
+    if let Some(throughput) = spec.disk_throughput {
+        diff.field(
+            true,
+            "disk-throughput",
+            current.disk_throughput.unwrap_or(0),
+            throughput,
+        );
+    }
+    diff.field(
+        true,
+        "instance-type",
+        &current.instance_type,
+        &spec.instance_type,
+    );
+    match &spec.acl {
+        Some(acl) if plan.is_new(Kind::Acl, acl) => diff.new_reference(true, "acl", acl),
+        _ => {
+            let current_acl = current
+                .acl_id
+                .as_ref()
+                .map(|id| live.acl_name(id).unwrap_or(id.as_str()))
+                .unwrap_or("");
+            diff.field(true, "acl", current_acl, spec.acl.as_deref().unwrap_or(""));
+        }
+    }
+    diff.field(true, "protected", current.protected, spec.protected);
+    diff
+}
+
+fn integration_diff(
+    plan: &Plan,
+    live: &Live,
+    spec: &IntegrationSpec,
+    data: &serde_json::Value,
+) -> Result<Diff, String> {
+    let mut diff = Diff::default();
+    let text = |key: &str| match data.get(key) {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    };
+    diff.field(false, "sink", text("sink"), spec.sink.clone());
+    diff.field(false, "source", text("source"), spec.source.clone());
+    for (key, value) in &spec.settings {
+        let key_in_api = camel_case(key);
+        // Secrets only come back masked, so they can't be compared.
+        if data.get(format!("{}Display", key_in_api)).is_some() {
+            continue;
+        }
+        diff.field(
+            true,
+            &format!("settings.{}", key),
+            text(&key_in_api),
+            resolve(value)?,
+        );
+    }
+    if spec.clusters.iter().any(|c| plan.is_new(Kind::Cluster, c)) {
+        diff.new_reference(true, "clusters", &spec.clusters.join(", "));
+    } else {
+        let current: BTreeSet<&str> = data
+            .get("clusterIds")
+            .and_then(|ids| ids.as_array())
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str())
+                    .map(|id| live.cluster_name(id).unwrap_or(id))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let wanted: BTreeSet<&str> = spec.clusters.iter().map(|c| c.as_str()).collect();
+        let join = |names: BTreeSet<&str>| names.into_iter().collect::<Vec<_>>().join(", ");
+        diff.field(true, "clusters", join(current), join(wanted));
+    }
+    Ok(diff)
+}
+
+/// Turns the kebab-case name of a setting into the name the API uses.
+pub fn camel_case(key: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '-' || c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Reads `${NAME}` values from the environment.
+pub fn resolve(value: &str) -> Result<String, String> {
+    match value
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+    {
+        Some(name) => {
+            std::env::var(name).map_err(|_| format!("environment variable {} isn't set", name))
+        }
+        None => Ok(value.to_string()),
+    }
+}
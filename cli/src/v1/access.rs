@@ -213,3 +213,91 @@ impl ToV1 for esc_api::access::ListMembersResponse {
         List(l)
     }
 }
+
+impl ToV1 for esc_api::access::ListUserRefreshTokensResponse {
+    type V1Type = List<esc_api::access::UserRefreshToken>;
+    fn to_v1(self) -> Self::V1Type {
+        List(self.tokens)
+    }
+}
+
+/// Shows settings as `name: value` lines, or just the value when one was
+/// asked for. Strings aren't quoted so they can be used as is in scripts.
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct SettingValues(crate::access_settings::Shown);
+
+impl ToV1 for crate::access_settings::Shown {
+    type V1Type = SettingValues;
+    fn to_v1(self) -> Self::V1Type {
+        SettingValues(self)
+    }
+}
+
+fn setting_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl std::fmt::Debug for SettingValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.0 {
+            crate::access_settings::Shown::One(value) => write!(f, "{}", setting_value(value)),
+            crate::access_settings::Shown::All(settings) => {
+                let lines: Vec<String> = settings
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, setting_value(value)))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// Shows the outcome of a policy check, with the policies behind it.
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct Explanation(crate::policies::Evaluation);
+
+impl ToV1 for crate::policies::Evaluation {
+    type V1Type = Explanation;
+    fn to_v1(self) -> Self::V1Type {
+        Explanation(self)
+    }
+}
+
+impl std::fmt::Debug for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> core::fmt::Result {
+        let evaluation = &self.0;
+        let who = evaluation.subject.as_deref().unwrap_or("You");
+        let may = if evaluation.allowed { "may" } else { "may not" };
+        writeln!(
+            f,
+            "{} {} {} {:?}",
+            who, may, evaluation.action, evaluation.resource
+        )?;
+        if evaluation.matches.is_empty() {
+            return write!(f, "No policy covers it.");
+        }
+        match evaluation.subject {
+            Some(_) => writeln!(f, "Policies which apply:")?,
+            None => writeln!(f, "Policies covering it, for their subjects:")?,
+        }
+        for policy in &evaluation.matches {
+            writeln!(
+                f,
+                "  {:<5} {} {:?} via {}",
+                policy.effect.to_string(),
+                policy.id.0,
+                policy.name,
+                policy.via.join(", ")
+            )?;
+        }
+        if evaluation.deny_won {
+            write!(f, "A policy denying it wins over those allowing it.")?;
+        }
+        Ok(())
+    }
+}
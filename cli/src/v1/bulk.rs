@@ -0,0 +1,8 @@
+use super::common::ToV1;
+
+impl ToV1 for crate::bulk::Summary {
+    type V1Type = Self;
+    fn to_v1(self) -> Self::V1Type {
+        self
+    }
+}
@@ -135,3 +135,10 @@ impl ToV1 for esc_api::integrate::ListIntegrationsResponse {
         }
     }
 }
+
+impl ToV1 for esc_api::integrate::IntegrationsOptionsResponse {
+    type V1Type = super::common::List<esc_api::integrate::SourceSummary>;
+    fn to_v1(self) -> Self::V1Type {
+        super::common::List(self.sources)
+    }
+}
@@ -170,3 +170,17 @@ impl ToV1 for esc_api::mesdb::ListBackupsResponse {
         List(l)
     }
 }
+
+impl ToV1 for esc_api::mesdb::ListClusterCreateVersionsResponse {
+    type V1Type = List<esc_api::mesdb::ClusterCreateVersion>;
+    fn to_v1(self) -> Self::V1Type {
+        List(self.versions)
+    }
+}
+
+impl ToV1 for esc_api::mesdb::ListClusterUpgradeVersionsResponse {
+    type V1Type = List<esc_api::mesdb::ClusterUpgradeVersion>;
+    fn to_v1(self) -> Self::V1Type {
+        List(self.versions)
+    }
+}
@@ -1,11 +1,13 @@
 mod access;
 mod audit;
+mod bulk;
 mod common;
 mod infra;
 mod integrate;
 mod mesdb;
 mod orchestrate;
 mod resources;
+mod spec;
 
 pub use common::List;
 pub use common::ToV1;
@@ -0,0 +1,64 @@
+use super::common::ToV1;
+use crate::spec::plan::{Operation, Plan};
+
+impl ToV1 for crate::spec::apply::Applied {
+    type V1Type = Self;
+    fn to_v1(self) -> Self::V1Type {
+        self
+    }
+}
+
+/// Shows a plan as a diff.
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct PlanDiff(Plan);
+
+impl ToV1 for Plan {
+    type V1Type = PlanDiff;
+    fn to_v1(self) -> Self::V1Type {
+        PlanDiff(self)
+    }
+}
+
+impl std::fmt::Debug for PlanDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> core::fmt::Result {
+        let plan = &self.0;
+        if plan.is_empty() {
+            return write!(f, "No changes, the project matches the spec.");
+        }
+        for action in &plan.actions {
+            let (sign, verb) = match action.operation {
+                Operation::Create => ("+", "create"),
+                Operation::Update => ("~", "update"),
+                Operation::Replace => ("-/+", "replace"),
+                Operation::Delete => ("-", "delete"),
+            };
+            write!(f, "{} {} {} {:?}", sign, verb, action.kind, action.name)?;
+            if let Some(id) = &action.id {
+                write!(f, " ({})", id)?;
+            }
+            writeln!(f)?;
+            for change in &action.changes {
+                write!(
+                    f,
+                    "    {}: {:?} -> {:?}",
+                    change.field,
+                    change.from.as_deref().unwrap_or(""),
+                    change.to.as_deref().unwrap_or("")
+                )?;
+                if change.forces_replacement && action.operation == Operation::Replace {
+                    write!(f, " (forces replacement)")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        write!(
+            f,
+            "\nPlan: {} to create, {} to update, {} to replace, {} to delete.",
+            plan.count(Operation::Create),
+            plan.count(Operation::Update),
+            plan.count(Operation::Replace),
+            plan.count(Operation::Delete)
+        )
+    }
+}
@@ -0,0 +1,134 @@
+use esc_api::mesdb::{self, ClusterUpgradeVersion, UpgradeChangeType};
+use esc_api::resources::ProjectId;
+use esc_api::{Client, ClusterId, OrgId};
+
+/// A version picked by what it is rather than by its tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpgradeTo {
+    Recommended,
+    LatestLts,
+    LatestPatch,
+}
+
+impl std::str::FromStr for UpgradeTo {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "recommended" => Ok(Self::Recommended),
+            "latest-lts" => Ok(Self::LatestLts),
+            "latest-patch" => Ok(Self::LatestPatch),
+            _ => Err(format!(
+                "unknown version: {}, expected recommended, latest-lts or latest-patch",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for UpgradeTo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradeTo::Recommended => write!(f, "recommended"),
+            UpgradeTo::LatestLts => write!(f, "latest-lts"),
+            UpgradeTo::LatestPatch => write!(f, "latest-patch"),
+        }
+    }
+}
+
+/// What a cluster is upgraded to, as given on the command line.
+#[derive(Clone, Debug)]
+pub enum Target {
+    Tag(String),
+    To(UpgradeTo),
+}
+
+/// The kinds of upgrade which were explicitly allowed, on top of patches.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Allowed {
+    pub major: bool,
+    pub downgrade: bool,
+}
+
+/// An upgrade which passed the checks. `change` and `warnings` are only
+/// known when the API lists the tag among those the cluster can go to.
+#[derive(Clone, Debug)]
+pub struct Upgrade {
+    pub tag: String,
+    pub change: Option<UpgradeChangeType>,
+    pub warnings: Vec<String>,
+}
+
+fn parse_tag(tag: &str) -> Vec<u32> {
+    tag.split('.').filter_map(|p| p.parse().ok()).collect()
+}
+
+/// Picks the newest version matching `to`. Downgrades are never picked.
+pub fn pick(to: UpgradeTo, versions: &[ClusterUpgradeVersion]) -> Option<&ClusterUpgradeVersion> {
+    versions
+        .iter()
+        .filter(|v| v.change != UpgradeChangeType::Downgrade)
+        .filter(|v| match to {
+            UpgradeTo::Recommended => v.recommended,
+            UpgradeTo::LatestLts => v.lts,
+            UpgradeTo::LatestPatch => v.change == UpgradeChangeType::Patch,
+        })
+        .max_by_key(|v| parse_tag(&v.tag))
+}
+
+/// Refuses major upgrades and downgrades which weren't allowed.
+pub fn check(
+    cluster_id: &ClusterId,
+    version: &ClusterUpgradeVersion,
+    allowed: Allowed,
+) -> Result<(), String> {
+    let flag = match version.change {
+        UpgradeChangeType::Major if !allowed.major => "--allow-major",
+        UpgradeChangeType::Downgrade if !allowed.downgrade => "--allow-downgrade",
+        _ => return Ok(()),
+    };
+    Err(format!(
+        "Going to {} is a {} change for cluster {}, pass {} to go ahead",
+        version.tag, version.change, cluster_id.0, flag
+    ))
+}
+
+/// Works out the tag to upgrade the cluster to, and checks the change is
+/// allowed. Errors of the API are kept as they are, for their exit codes.
+pub async fn resolve(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    cluster_id: ClusterId,
+    target: &Target,
+    allowed: Allowed,
+) -> Result<Upgrade, Box<dyn std::error::Error>> {
+    let versions =
+        mesdb::list_versions_for_cluster_upgrade(client, org_id, project_id, cluster_id.clone())
+            .await?
+            .versions;
+    let version = match target {
+        Target::Tag(tag) => match versions.iter().find(|v| v.tag == *tag) {
+            Some(version) => version,
+            // The API has the last word on tags it doesn't list.
+            None => {
+                return Ok(Upgrade {
+                    tag: tag.clone(),
+                    change: None,
+                    warnings: Vec::new(),
+                })
+            }
+        },
+        Target::To(to) => pick(*to, &versions).ok_or_else(|| {
+            format!(
+                "There is no {} version to upgrade cluster {} to",
+                to, cluster_id.0
+            )
+        })?,
+    };
+    check(&cluster_id, version, allowed)?;
+    Ok(Upgrade {
+        tag: version.tag.clone(),
+        change: Some(version.change.clone()),
+        warnings: version.warnings.clone(),
+    })
+}
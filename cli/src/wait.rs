@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use esc_api::mesdb::{self, BackupStatus, Cluster, ClusterStatus};
+use esc_api::resources::ProjectId;
+use esc_api::{BackupId, Client, ClusterId, OrgId, WaitError, WaitOptions};
+
+/// How long `--wait` waits when no `--timeout` is given.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// What a cluster should end up as once the operation is done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClusterTarget {
+    Status(ClusterStatus),
+    /// Available with the instance type, once resized.
+    InstanceType(String),
+    /// Available with a disk at least that large in GB, once expanded.
+    DiskSize(i32),
+    /// Available running the server version tag, once upgraded.
+    ServerVersionTag(String),
+    Deleted,
+}
+
+impl ClusterTarget {
+    /// Whether the cluster got there. The API takes a moment to pick up an
+    /// operation, so a cluster which is available may not have started yet,
+    /// and what it's changed to tells when it's done.
+    pub fn is_reached(&self, cluster: &Cluster) -> bool {
+        let available = cluster.status == ClusterStatus::Available;
+        match self {
+            ClusterTarget::Status(status) => cluster.status == *status,
+            ClusterTarget::InstanceType(instance_type) => {
+                available && cluster.instance_type == *instance_type
+            }
+            ClusterTarget::DiskSize(size_gb) => available && cluster.disk_size_gb >= *size_gb,
+            ClusterTarget::ServerVersionTag(tag) => available && cluster.server_version_tag == *tag,
+            ClusterTarget::Deleted => cluster.status == ClusterStatus::Deleted,
+        }
+    }
+}
+
+/// Waits for a cluster to reach the target, printing every status it goes
+/// through to stderr. The client shouldn't print responses, as the polls
+/// aren't the output of the command.
+pub async fn wait_for_cluster(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    cluster_id: ClusterId,
+    target: ClusterTarget,
+    timeout: Duration,
+) -> Result<(), WaitError> {
+    let resource = format!("Cluster {}", cluster_id);
+    let mut last_status: Option<ClusterStatus> = None;
+    let result = mesdb::wait_for_cluster(
+        client,
+        org_id,
+        project_id,
+        cluster_id,
+        &WaitOptions::default().timeout(timeout),
+        |cluster| {
+            if last_status.as_ref() != Some(&cluster.status) {
+                eprintln!("{} is {}", resource, cluster.status);
+                last_status = Some(cluster.status.clone());
+            }
+            target.is_reached(cluster)
+        },
+    )
+    .await;
+    match result {
+        Ok(_) => Ok(()),
+        // Deleted clusters are only listed for a while.
+        Err(err) if err.is_not_found() && target == ClusterTarget::Deleted => {
+            eprintln!("{} is deleted", resource);
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Waits for a backup to be available, printing every status it goes
+/// through to stderr.
+pub async fn wait_for_backup(
+    client: &Client,
+    org_id: OrgId,
+    project_id: ProjectId,
+    backup_id: BackupId,
+    timeout: Duration,
+) -> Result<(), WaitError> {
+    let resource = format!("Backup {}", backup_id);
+    let mut last_status: Option<BackupStatus> = None;
+    mesdb::wait_for_backup(
+        client,
+        org_id,
+        project_id,
+        backup_id,
+        &WaitOptions::default().timeout(timeout),
+        |backup| {
+            if last_status.as_ref() != Some(&backup.status) {
+                eprintln!("{} is {}", resource, backup.status);
+                last_status = Some(backup.status.clone());
+            }
+            backup.status == BackupStatus::Available
+        },
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use esc_api::mesdb::{self, Backup, Cluster};
+use esc_api::resources::ProjectId;
+use esc_api::{Client, ErrorKind, OrgId};
+use serde::Serialize;
+
+/// A change noticed between two polls, written as one JSON line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WatchEvent {
+    pub event: &'static str,
+    pub time: String,
+    pub id: String,
+    pub description: String,
+    /// True for the events describing what was there when watching began.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub initial: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+impl WatchEvent {
+    fn new(event: &'static str, id: &str, description: &str) -> Self {
+        Self {
+            event,
+            time: chrono::Utc::now().to_rfc3339(),
+            id: id.to_string(),
+            description: description.to_string(),
+            initial: false,
+            from: None,
+            to: None,
+        }
+    }
+
+    fn change<T: ToString + PartialEq>(
+        event: &'static str,
+        id: &str,
+        description: &str,
+        from: &T,
+        to: &T,
+    ) -> Option<Self> {
+        if from == to {
+            return None;
+        }
+        Some(Self {
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            ..Self::new(event, id, description)
+        })
+    }
+}
+
+/// The events which tell how the clusters changed between two polls.
+pub fn cluster_events(
+    before: &BTreeMap<String, Cluster>,
+    after: &BTreeMap<String, Cluster>,
+) -> Vec<WatchEvent> {
+    let mut events = Vec::new();
+    for (id, cluster) in after {
+        let desc = cluster.description.as_str();
+        match before.get(id) {
+            None => events.push(WatchEvent {
+                to: Some(cluster.status.to_string()),
+                ..WatchEvent::new("cluster_added", id, desc)
+            }),
+            Some(old) => events.extend(
+                vec![
+                    WatchEvent::change("status_changed", id, desc, &old.status, &cluster.status),
+                    WatchEvent::change("health_changed", id, desc, &old.health, &cluster.health),
+                    WatchEvent::change(
+                        "patch_available_changed",
+                        id,
+                        desc,
+                        &old.patch_available,
+                        &cluster.patch_available,
+                    ),
+                ]
+                .into_iter()
+                .flatten(),
+            ),
+        }
+    }
+    for (id, cluster) in before {
+        if !after.contains_key(id) {
+            events.push(WatchEvent::new("cluster_removed", id, &cluster.description));
+        }
+    }
+    events
+}
+
+/// The events which tell how the backups changed between two polls.
+pub fn backup_events(
+    before: &BTreeMap<String, Backup>,
+    after: &BTreeMap<String, Backup>,
+) -> Vec<WatchEvent> {
+    let mut events = Vec::new();
+    for (id, backup) in after {
+        let desc = backup.description.as_str();
+        match before.get(id) {
+            None => events.push(WatchEvent {
+                to: Some(backup.status.to_string()),
+                ..WatchEvent::new("backup_added", id, desc)
+            }),
+            Some(old) => events.extend(WatchEvent::change(
+                "backup_status_changed",
+                id,
+                desc,
+                &old.status,
+                &backup.status,
+            )),
+        }
+    }
+    for (id, backup) in before {
+        if !after.contains_key(id) {
+            events.push(WatchEvent::new("backup_removed", id, &backup.description));
+        }
+    }
+    events
+}
+
+pub struct Watch {
+    pub org_id: OrgId,
+    pub project_id: ProjectId,
+    pub interval: Duration,
+    pub backups: bool,
+    pub max_polls: Option<u64>,
+}
+
+impl Watch {
+    /// Polls the project until interrupted, or for the given number of polls,
+    /// printing a JSON line to stdout for every change. The client shouldn't
+    /// print responses, as only the events are output.
+    pub async fn run(self, client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+        let mut clusters = BTreeMap::new();
+        let mut backups = BTreeMap::new();
+        let mut polls = 0u64;
+        // Until a poll succeeds, whatever is found was there from the start.
+        let mut has_baseline = false;
+        loop {
+            match self.poll(client).await {
+                Ok((new_clusters, new_backups)) => {
+                    let mut events = cluster_events(&clusters, &new_clusters);
+                    if let Some(new_backups) = new_backups {
+                        events.extend(backup_events(&backups, &new_backups));
+                        backups = new_backups;
+                    }
+                    if !has_baseline {
+                        for event in events.iter_mut() {
+                            event.initial = true;
+                        }
+                        has_baseline = true;
+                    }
+                    clusters = new_clusters;
+                    let mut out = std::io::stdout().lock();
+                    for event in events {
+                        serde_json::to_writer(&mut out, &event)?;
+                        writeln!(out)?;
+                    }
+                    out.flush()?;
+                }
+                // A watch is meant to outlive hiccups of the API.
+                Err(err) if is_transient(&err) => eprintln!("Polling failed: {}", err),
+                Err(err) => return Err(err.into()),
+            }
+            polls += 1;
+            if self.max_polls.map_or(false, |max| polls >= max) {
+                return Ok(());
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    async fn poll(
+        &self,
+        client: &Client,
+    ) -> esc_api::Result<(BTreeMap<String, Cluster>, Option<BTreeMap<String, Backup>>)> {
+        let clusters =
+            mesdb::list_clusters(client, self.org_id.clone(), self.project_id.clone()).await?;
+        let clusters = clusters
+            .clusters
+            .into_iter()
+            .map(|cluster| (cluster.id.to_string(), cluster))
+            .collect();
+        if !self.backups {
+            return Ok((clusters, None));
+        }
+        let backups =
+            mesdb::list_backups(client, self.org_id.clone(), self.project_id.clone()).await?;
+        let backups = backups
+            .backups
+            .into_iter()
+            .map(|backup| (backup.id.to_string(), backup))
+            .collect();
+        Ok((clusters, Some(backups)))
+    }
+}
+
+fn is_transient(err: &esc_api::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Transport | ErrorKind::Timeout | ErrorKind::ServerError | ErrorKind::RateLimited
+    )
+}
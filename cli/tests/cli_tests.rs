@@ -0,0 +1,1603 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::time::Duration;
+
+use esc_mock::MockServer;
+
+/// Runs the esc binary against a mock server, with a home directory of its
+/// own so the user's settings and tokens aren't touched.
+struct Esc {
+    // Keeps serving until the test is done.
+    _server: MockServer,
+    home: PathBuf,
+    refresh_token: String,
+}
+
+impl Esc {
+    fn new(name: &str) -> Self {
+        Self::with_profile(name, None, "")
+    }
+
+    /// Sets up a profile which sends requests to `url` if given, else to the
+    /// mock. `{mock}` in the extra profile settings is replaced with the
+    /// mock's URL.
+    fn with_profile(name: &str, url: Option<&str>, extra: &str) -> Self {
+        let server = MockServer::start().unwrap();
+        let home = std::env::temp_dir().join(format!("esc-test-{}-{}", name, std::process::id()));
+        let esc_dir = home.join(".esc");
+        std::fs::create_dir_all(&esc_dir).unwrap();
+        let token_config = server.token_config();
+        let url = url.unwrap_or_else(|| server.url());
+        let settings = format!(
+            "default-profile = \"mock\"\n\n\
+             [[profile]]\n\
+             name = \"mock\"\n\
+             api-base-url = \"{}\"\n\
+             {}\n\n\
+             [profile.token-config]\n\
+             audience = \"{}\"\n\
+             identity-url = \"{}\"\n\
+             public-key = \"\"\"\n{}\"\"\"\n",
+            url,
+            extra.replace("{mock}", server.url()),
+            token_config.audience,
+            url,
+            token_config.public_key,
+        );
+        std::fs::write(esc_dir.join("settings.toml"), settings).unwrap();
+        let refresh_token = server.refresh_token("test@example.com");
+        Self {
+            _server: server,
+            home,
+            refresh_token,
+        }
+    }
+
+    async fn run(&self, args: &[&str]) -> Output {
+        self.run_with(Some(&self.refresh_token), args).await
+    }
+
+    /// Runs a command with the given refresh token, or else with the one
+    /// saved in the home directory, if any.
+    async fn run_with(&self, refresh_token: Option<&str>, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_esc"));
+        command.env("HOME", &self.home).arg("--noninteractive");
+        if let Some(refresh_token) = refresh_token {
+            command.arg("--refresh-token").arg(refresh_token);
+        }
+        command.args(args);
+        // The mock is served by this test's runtime, so don't block it.
+        tokio::task::spawn_blocking(move || command.output().unwrap())
+            .await
+            .unwrap()
+    }
+}
+
+impl Esc {
+    /// Runs a command which must succeed, and returns what it printed in the
+    /// API's format.
+    async fn json(&self, args: &[&str]) -> serde_json::Value {
+        let output = self.run(&[&["--fmt", "api"], args].concat()).await;
+        assert!(output.status.success(), "{:?}", output);
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    /// Creates an organization and a project with an available network in
+    /// it, and returns their ids.
+    async fn network(&self) -> (String, String, String) {
+        let org_id = self
+            .json(&["resources", "organizations", "create", "--name", "org"])
+            .await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let (project_id, network_id) = self.project_network(&org_id).await;
+        (org_id, project_id, network_id)
+    }
+
+    /// Creates a project with an available network in the organization, and
+    /// returns their ids.
+    async fn project_network(&self, org_id: &str) -> (String, String) {
+        let project_id = self
+            .json(&[
+                "resources",
+                "projects",
+                "create",
+                "--org-id",
+                org_id,
+                "--name",
+                "project",
+            ])
+            .await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let ids = ["--org-id", org_id, "--project-id", &project_id];
+        let network_id = self
+            .json(
+                &[
+                    &["infra", "networks", "create"],
+                    &ids[..],
+                    &[
+                        "--provider",
+                        "aws",
+                        "--cidr-block",
+                        "172.21.0.0/16",
+                        "--description",
+                        "network",
+                        "--region",
+                        "us-west-2",
+                    ],
+                ]
+                .concat(),
+            )
+            .await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        // The mock provisions the network as it's being looked at.
+        for _ in 0..2 {
+            self.json(&[&["infra", "networks", "get", "--id", &network_id], &ids[..]].concat())
+                .await;
+        }
+        (project_id, network_id)
+    }
+
+    /// Creates a single node cluster in the network.
+    async fn cluster(
+        &self,
+        org_id: &str,
+        project_id: &str,
+        network_id: &str,
+        extra: &[&str],
+    ) -> serde_json::Value {
+        let args = [
+            "mesdb",
+            "clusters",
+            "create",
+            "--org-id",
+            org_id,
+            "--project-id",
+            project_id,
+            "--network-id",
+            network_id,
+            "--description",
+            "cluster",
+            "--topology",
+            "single-node",
+            "--instance-type",
+            "F1",
+            "--disk-size-in-gb",
+            "16",
+            "--disk-type",
+            "gp3",
+            "--server-version",
+            "22.10",
+            "--projection-level",
+            "off",
+        ];
+        self.json(&[&args[..], extra].concat()).await
+    }
+
+    /// Creates an ACL in the project, and returns its id.
+    async fn acl(&self, org_id: &str, project_id: &str) -> String {
+        self.json(&[
+            "infra",
+            "acls",
+            "create",
+            "--org-id",
+            org_id,
+            "--project-id",
+            project_id,
+            "--cidr-blocks",
+            "10.0.0.0/8",
+            "--description",
+            "office",
+        ])
+        .await["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Backs up the cluster, and returns the id of the available backup.
+    async fn backup(&self, org_id: &str, project_id: &str, cluster_id: &str) -> String {
+        let ids = ["--org-id", org_id, "--project-id", project_id];
+        let backup_id = self
+            .json(
+                &[
+                    &["mesdb", "backups", "create"],
+                    &ids[..],
+                    &[
+                        "--source-cluster-id",
+                        cluster_id,
+                        "--description",
+                        "nightly",
+                    ],
+                ]
+                .concat(),
+            )
+            .await["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        // The mock finishes the backup as it's being looked at.
+        for _ in 0..2 {
+            self.json(&[&["mesdb", "backups", "get", "--id", &backup_id], &ids[..]].concat())
+                .await;
+        }
+        backup_id
+    }
+}
+
+impl Drop for Esc {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+#[tokio::test]
+async fn test_organizations_are_created_and_listed() {
+    let esc = Esc::new("organizations");
+
+    let created = esc
+        .run(&["resources", "organizations", "create", "--name", "acme"])
+        .await;
+    assert!(created.status.success(), "{:?}", created);
+    let listed = esc
+        .run(&["--fmt", "api", "resources", "organizations", "list"])
+        .await;
+
+    assert!(listed.status.success(), "{:?}", listed);
+    let listed: serde_json::Value = serde_json::from_slice(&listed.stdout).unwrap();
+    assert_eq!("acme", listed["organizations"][0]["name"]);
+}
+
+#[tokio::test]
+async fn test_missing_resources_exit_with_not_found() {
+    let esc = Esc::new("not-found");
+
+    let output = esc
+        .run(&["resources", "organizations", "get", "--id", "nope"])
+        .await;
+
+    assert_eq!(Some(13), output.status.code(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_identity_timeouts_exit_as_timeouts() {
+    // Accepts connections and never answers.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let _connections: Vec<_> = listener.incoming().collect();
+    });
+    let esc = Esc::with_profile("identity-timeout", Some(&url), "");
+
+    let output = esc
+        .run(&[
+            "--request-timeout",
+            "1",
+            "resources",
+            "organizations",
+            "list",
+        ])
+        .await;
+
+    assert_eq!(Some(22), output.status.code(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_connect_timeouts_exit_as_timeouts() {
+    // Nothing accepts on this socket, so once its backlog is full further
+    // connections are never answered.
+    let socket = tokio::net::TcpSocket::new_v4().unwrap();
+    socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let listener = socket.listen(0).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut backlog = Vec::new();
+    for _ in 0..4 {
+        let connect = tokio::net::TcpStream::connect(addr);
+        if let Ok(Ok(stream)) = tokio::time::timeout(Duration::from_millis(200), connect).await {
+            backlog.push(stream);
+        }
+    }
+    let esc = Esc::with_profile("connect-timeout", Some(&format!("http://{}", addr)), "");
+
+    let output = esc
+        .run(&[
+            "--connect-timeout",
+            "1",
+            "resources",
+            "organizations",
+            "list",
+        ])
+        .await;
+
+    assert_eq!(Some(22), output.status.code(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_requests_go_through_the_proxy() {
+    // Nothing resolves esc.invalid, only the proxy knows where to go.
+    let esc = Esc::with_profile("proxy", Some("http://esc.invalid"), "proxy = \"{mock}\"");
+
+    let proxied = esc.run(&["resources", "organizations", "list"]).await;
+    let bypassed = esc
+        .run(&[
+            "--no-proxy",
+            "esc.invalid",
+            "resources",
+            "organizations",
+            "list",
+        ])
+        .await;
+
+    assert!(proxied.status.success(), "{:?}", proxied);
+    assert!(!bypassed.status.success(), "{:?}", bypassed);
+    let stderr = String::from_utf8_lossy(&bypassed.stderr);
+    assert!(
+        stderr.contains("http://esc.invalid/oauth/token"),
+        "{}",
+        stderr
+    );
+}
+
+#[tokio::test]
+async fn test_request_log_stays_out_of_the_output() {
+    let esc = Esc::new("request-log");
+
+    let output = esc
+        .run(&[
+            "--fmt",
+            "api",
+            "--log-format",
+            "json",
+            "resources",
+            "organizations",
+            "list",
+        ])
+        .await;
+
+    assert!(output.status.success(), "{:?}", output);
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(listed["organizations"].is_array());
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(2, events.len(), "{:?}", events);
+    assert_eq!("request", events[0]["event"]);
+    assert_eq!("Bearer [redacted]", events[0]["headers"]["authorization"]);
+    assert_eq!("response", events[1]["event"]);
+    assert_eq!(200, events[1]["status"]);
+    assert_eq!(events[0]["request_id"], events[1]["request_id"]);
+}
+
+#[tokio::test]
+async fn test_wait_follows_the_cluster_until_it_is_done() {
+    let esc = Esc::new("wait");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+
+    let created = esc
+        .cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let cluster_id = created["id"].as_str().unwrap();
+    let cluster = esc
+        .json(&[&["mesdb", "clusters", "get", "--id", cluster_id], &ids[..]].concat())
+        .await;
+    assert_eq!("available", cluster["cluster"]["status"]);
+
+    let deleted = esc
+        .run(
+            &[
+                &[
+                    "mesdb", "clusters", "delete", "--wait", "--yes", "--id", cluster_id,
+                ],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+
+    assert!(deleted.status.success(), "{:?}", deleted);
+    let progress = String::from_utf8_lossy(&deleted.stderr);
+    assert!(progress.contains("is deleting instances"), "{}", progress);
+    assert!(progress.contains("is deleted"), "{}", progress);
+}
+
+#[tokio::test]
+async fn test_watch_prints_changes_as_json_lines() {
+    let esc = Esc::new("watch");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let created = esc.cluster(&org_id, &project_id, &network_id, &[]).await;
+
+    let output = esc
+        .run(&[
+            "mesdb",
+            "clusters",
+            "watch",
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &project_id,
+            "--interval",
+            "0",
+            "--max-polls",
+            "3",
+        ])
+        .await;
+
+    assert!(output.status.success(), "{:?}", output);
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let summary: Vec<(&str, &str)> = events
+        .iter()
+        .map(|e| (e["event"].as_str().unwrap(), e["to"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("cluster_added", "provisioning"),
+            ("status_changed", "available"),
+            ("health_changed", "ok"),
+        ],
+        summary
+    );
+    assert_eq!(true, events[0]["initial"]);
+    assert_eq!(created["id"], events[1]["id"]);
+}
+
+#[tokio::test]
+async fn test_backups_are_restored_into_another_project() {
+    let esc = Esc::new("restore");
+    let (org_id, source_project_id, network_id) = esc.network().await;
+    let source = esc
+        .cluster(&org_id, &source_project_id, &network_id, &["--wait"])
+        .await;
+    let backup_id = esc
+        .backup(&org_id, &source_project_id, source["id"].as_str().unwrap())
+        .await;
+    let (project_id, network_id) = esc.project_network(&org_id).await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+
+    let restored = esc
+        .json(
+            &[
+                &["mesdb", "backups", "restore", "--wait"],
+                &ids[..],
+                &[
+                    "--backup-project-id",
+                    &source_project_id,
+                    "--backup-id",
+                    &backup_id,
+                    "--network-id",
+                    &network_id,
+                ],
+            ]
+            .concat(),
+        )
+        .await;
+
+    let cluster = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "get", "--id"][..],
+                &[restored["id"].as_str().unwrap()],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!("available", cluster["cluster"]["status"]);
+    assert_eq!("F1", cluster["cluster"]["instanceType"]);
+    assert_eq!(network_id, cluster["cluster"]["networkId"]);
+    assert_eq!(
+        format!("cluster (restored from {})", backup_id),
+        cluster["cluster"]["description"]
+    );
+}
+
+#[tokio::test]
+async fn test_restores_into_another_project_need_an_acl_of_it() {
+    let esc = Esc::new("restore-acl");
+    let (org_id, source_project_id, network_id) = esc.network().await;
+    let source_acl_id = esc.acl(&org_id, &source_project_id).await;
+    let source = esc
+        .cluster(
+            &org_id,
+            &source_project_id,
+            &network_id,
+            &["--wait", "--acl-id", &source_acl_id],
+        )
+        .await;
+    let backup_id = esc
+        .backup(&org_id, &source_project_id, source["id"].as_str().unwrap())
+        .await;
+    let (project_id, network_id) = esc.project_network(&org_id).await;
+    let restore = [
+        "--fmt",
+        "api",
+        "mesdb",
+        "backups",
+        "restore",
+        "--org-id",
+        &org_id,
+        "--project-id",
+        &project_id,
+        "--backup-project-id",
+        &source_project_id,
+        "--backup-id",
+        &backup_id,
+        "--network-id",
+        &network_id,
+    ];
+
+    let without_acl = esc.run(&restore).await;
+    let with_source_acl = esc
+        .run(&[&restore[..], &["--acl-id", &source_acl_id]].concat())
+        .await;
+    let acl_id = esc.acl(&org_id, &project_id).await;
+    let with_acl = esc
+        .run(&[&restore[..], &["--acl-id", &acl_id]].concat())
+        .await;
+
+    assert!(!without_acl.status.success(), "{:?}", without_acl);
+    assert!(
+        String::from_utf8_lossy(&without_acl.stderr).contains("--acl-id must be given"),
+        "{:?}",
+        without_acl
+    );
+    assert!(!with_source_acl.status.success(), "{:?}", with_source_acl);
+    assert!(
+        String::from_utf8_lossy(&with_source_acl.stderr).contains("acl not found"),
+        "{:?}",
+        with_source_acl
+    );
+    assert!(with_acl.status.success(), "{:?}", with_acl);
+    let restored: serde_json::Value = serde_json::from_slice(&with_acl.stdout).unwrap();
+    let cluster = esc
+        .json(&[
+            "mesdb",
+            "clusters",
+            "get",
+            "--id",
+            restored["id"].as_str().unwrap(),
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &project_id,
+        ])
+        .await;
+    assert_eq!(acl_id, cluster["cluster"]["aclId"]);
+}
+
+#[tokio::test]
+async fn test_clusters_are_cloned_through_a_backup() {
+    let esc = Esc::new("clone");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let source = esc
+        .cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let (target_project_id, target_network_id) = esc.project_network(&org_id).await;
+
+    let output = esc
+        .run(&[
+            "--fmt",
+            "api",
+            "mesdb",
+            "clusters",
+            "clone",
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &project_id,
+            "--id",
+            source["id"].as_str().unwrap(),
+            "--target-project",
+            &target_project_id,
+            "--target-network",
+            &target_network_id,
+            "--ephemeral-backup",
+        ])
+        .await;
+
+    assert!(output.status.success(), "{:?}", output);
+    let created: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let target_ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        target_project_id.as_str(),
+    ];
+    let clone = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "get", "--id"][..],
+                &[created["id"].as_str().unwrap()],
+                &target_ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!("available", clone["cluster"]["status"]);
+    assert_eq!("single-node", clone["cluster"]["topology"]);
+    let backups = esc
+        .json(&[
+            "mesdb",
+            "backups",
+            "list",
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &project_id,
+        ])
+        .await;
+    assert_eq!("deleting", backups["backups"][0]["status"], "{}", backups);
+}
+
+#[tokio::test]
+async fn test_failed_clones_keep_their_ephemeral_backup() {
+    let esc = Esc::new("clone-acl");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let source_acl_id = esc.acl(&org_id, &project_id).await;
+    let source = esc
+        .cluster(
+            &org_id,
+            &project_id,
+            &network_id,
+            &["--wait", "--acl-id", &source_acl_id],
+        )
+        .await;
+    let (target_project_id, target_network_id) = esc.project_network(&org_id).await;
+    let clone = [
+        "--fmt",
+        "api",
+        "mesdb",
+        "clusters",
+        "clone",
+        "--org-id",
+        &org_id,
+        "--project-id",
+        &project_id,
+        "--id",
+        source["id"].as_str().unwrap(),
+        "--target-project",
+        &target_project_id,
+        "--target-network",
+        &target_network_id,
+        "--ephemeral-backup",
+    ];
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+
+    let failed = esc.run(&clone).await;
+    let kept = esc
+        .json(&[&["mesdb", "backups", "list"], &ids[..]].concat())
+        .await;
+    let acl_id = esc.acl(&org_id, &target_project_id).await;
+    let cloned = esc
+        .run(&[&clone[..], &["--acl-id", &acl_id]].concat())
+        .await;
+
+    assert!(!failed.status.success(), "{:?}", failed);
+    let backup_id = kept["backups"][0]["id"].as_str().unwrap();
+    assert_eq!("available", kept["backups"][0]["status"], "{}", kept);
+    assert!(
+        String::from_utf8_lossy(&failed.stderr).contains(&format!(
+            "Keeping backup {} as the copy isn't available",
+            backup_id
+        )),
+        "{:?}",
+        failed
+    );
+    assert!(cloned.status.success(), "{:?}", cloned);
+    let created: serde_json::Value = serde_json::from_slice(&cloned.stdout).unwrap();
+    let copy = esc
+        .json(&[
+            "mesdb",
+            "clusters",
+            "get",
+            "--id",
+            created["id"].as_str().unwrap(),
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &target_project_id,
+        ])
+        .await;
+    assert_eq!(acl_id, copy["cluster"]["aclId"]);
+}
+
+#[tokio::test]
+async fn test_apply_reconciles_the_project_with_the_spec() {
+    let esc = Esc::new("apply");
+    let (org_id, project_id, _) = esc.network().await;
+    let spec = |instance_type: &str, job: &str| {
+        format!(
+            r#"
+[[network]]
+name = "network"
+provider = "aws"
+region = "us-west-2"
+cidr-block = "172.21.0.0/16"
+
+[[cluster]]
+name = "db"
+network = "network"
+topology = "single-node"
+instance-type = "{}"
+disk-size-gb = 16
+disk-type = "gp3"
+server-version = "22.10"
+projection-level = "off"
+{}"#,
+            instance_type, job
+        )
+    };
+    let job = r#"
+[[job]]
+name = "nightly"
+cluster = "db"
+schedule = "0 2 * * *"
+max-backup-count = 7
+"#;
+    let file = esc.home.join("project.toml");
+    let apply = |extra: &'static [&'static str]| {
+        let file = file.to_str().unwrap().to_string();
+        let (org_id, project_id) = (org_id.clone(), project_id.clone());
+        let esc = &esc;
+        async move {
+            let args = [
+                "apply",
+                "--org-id",
+                &org_id,
+                "--project-id",
+                &project_id,
+                "-f",
+                &file,
+            ];
+            esc.json(&[&args[..], extra].concat()).await
+        }
+    };
+    let operations = |applied: &serde_json::Value, key: &str| {
+        applied[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| format!("{} {} {}", a["operation"], a["kind"], a["name"]))
+            .collect::<Vec<_>>()
+            .join(", ")
+            .replace('"', "")
+    };
+
+    std::fs::write(&file, spec("F1", job)).unwrap();
+    let applied = apply(&[]).await;
+    assert_eq!(
+        "create cluster db, create job nightly",
+        operations(&applied, "applied")
+    );
+    let applied = apply(&[]).await;
+    assert_eq!("", operations(&applied, "applied"));
+
+    std::fs::write(&file, spec("C4", "")).unwrap();
+    let applied = apply(&[]).await;
+    assert_eq!("update cluster db", operations(&applied, "applied"));
+    assert_eq!("delete job nightly", operations(&applied, "skipped"));
+    std::fs::write(&file, spec("M8", "")).unwrap();
+    let applied = apply(&["--prune"]).await;
+    assert_eq!(
+        "update cluster db, delete job nightly",
+        operations(&applied, "applied")
+    );
+
+    let clusters = esc
+        .json(&[
+            "mesdb",
+            "clusters",
+            "list",
+            "--org-id",
+            &org_id,
+            "--project-id",
+            &project_id,
+        ])
+        .await;
+    assert_eq!("M8", clusters["clusters"][0]["instanceType"]);
+}
+
+#[tokio::test]
+async fn test_plan_exits_with_2_when_the_project_drifted() {
+    let esc = Esc::new("plan");
+    let (org_id, project_id, network_id) = esc.network().await;
+    esc.cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let file = esc.home.join("project.yaml");
+    let spec = |topology: &str| {
+        format!(
+            "network:\n\
+             - name: network\n  provider: aws\n  region: us-west-2\n  cidr-block: 172.21.0.0/16\n\
+             cluster:\n\
+             - name: cluster\n  network: network\n  topology: {}\n  instance-type: F1\n  \
+             disk-size-gb: 16\n  disk-type: gp3\n  server-version: '22.10'\n  projection-level: off\n",
+            topology
+        )
+    };
+    let args = [
+        "plan",
+        "--org-id",
+        &org_id,
+        "--project-id",
+        &project_id,
+        "-f",
+        file.to_str().unwrap(),
+    ];
+
+    std::fs::write(&file, spec("single-node")).unwrap();
+    let output = esc.run(&args).await;
+    assert!(output.status.success(), "{:?}", output);
+
+    std::fs::write(&file, spec("three-node-multi-zone")).unwrap();
+    let output = esc.run(&[&["--fmt", "api"][..], &args[..]].concat()).await;
+    assert_eq!(Some(2), output.status.code(), "{:?}", output);
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let action = &plan["actions"][0];
+    assert_eq!("replace", action["operation"]);
+    assert_eq!("topology", action["changes"][0]["field"]);
+    assert_eq!(true, action["changes"][0]["forcesReplacement"]);
+
+    let output = esc.run(&args).await;
+    assert_eq!(Some(2), output.status.code(), "{:?}", output);
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.contains("-/+ replace cluster \"cluster\""), "{}", diff);
+    assert!(
+        diff.contains("0 to create, 0 to update, 1 to replace"),
+        "{}",
+        diff
+    );
+
+    let mut apply = args;
+    apply[0] = "apply";
+    let output = esc.run(&[&["--fmt", "api"][..], &apply[..]].concat()).await;
+    assert_eq!(Some(1), output.status.code(), "{:?}", output);
+    let applied: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("replace", applied["skipped"][0]["operation"]);
+}
+
+#[tokio::test]
+async fn test_exported_specs_match_the_project() {
+    let esc = Esc::new("export");
+    let (org_id, project_id, network_id) = esc.network().await;
+    esc.cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let ids = ["--org-id", &org_id, "--project-id", &project_id];
+    let file = esc.home.join("project.toml");
+    std::fs::write(
+        &file,
+        r#"
+[[network]]
+name = "network"
+provider = "aws"
+region = "us-west-2"
+cidr-block = "172.21.0.0/16"
+
+[[cluster]]
+name = "cluster"
+network = "network"
+topology = "single-node"
+instance-type = "F1"
+disk-size-gb = 16
+disk-type = "gp3"
+server-version = "22.10"
+projection-level = "off"
+
+[[job]]
+name = "nightly"
+cluster = "cluster"
+schedule = "0 2 * * *"
+max-backup-count = 7
+backup-description = "nightly backup"
+
+[[integration]]
+name = "alerts"
+sink = "slack"
+source = "notifications"
+settings = { channel-id = "C0123", token = "xoxb-secret" }
+"#,
+    )
+    .unwrap();
+    esc.json(&[&["apply", "-f", file.to_str().unwrap()][..], &ids[..]].concat())
+        .await;
+
+    let output = esc.run(&[&["export"][..], &ids[..]].concat()).await;
+    assert!(output.status.success(), "{:?}", output);
+    let exported = String::from_utf8(output.stdout).unwrap();
+    assert!(exported.contains("network = \"network\""), "{}", exported);
+    assert!(exported.contains("channel-id = \"C0123\""), "{}", exported);
+    assert!(!exported.contains("token"), "{}", exported);
+    assert!(!exported.contains(&network_id), "{}", exported);
+
+    std::fs::write(&file, exported).unwrap();
+    let output = esc
+        .run(&[&["plan", "-f", file.to_str().unwrap()][..], &ids[..]].concat())
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_bulk_commands_act_on_the_selected_clusters() {
+    let esc = Esc::new("bulk");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+    for description in &["dev-a", "dev-b", "prod"] {
+        esc.json(
+            &[
+                &["mesdb", "clusters", "create", "--network-id", &network_id][..],
+                &ids[..],
+                &[
+                    "--description",
+                    description,
+                    "--topology",
+                    "single-node",
+                    "--instance-type",
+                    "F1",
+                    "--disk-size-in-gb",
+                    "16",
+                    "--disk-type",
+                    "gp3",
+                    "--server-version",
+                    "22.10",
+                    "--projection-level",
+                    "off",
+                    "--wait",
+                ],
+            ]
+            .concat(),
+        )
+        .await;
+    }
+
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "stop", "--description-glob", "dev-*"][..],
+                &ids[..],
+                &["--dry-run"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+    let preview = String::from_utf8(output.stderr).unwrap();
+    assert!(preview.contains("Selected 2 cluster(s)"), "{}", preview);
+
+    let stop = [
+        &["mesdb", "clusters", "stop", "--description-glob", "dev-*"][..],
+        &ids[..],
+        &["--concurrency", "2", "--wait"],
+    ]
+    .concat();
+    let output = esc.run(&stop).await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("stop 2 cluster(s) without --yes"),
+        "{}",
+        stderr
+    );
+
+    let summary = esc.json(&[&stop[..], &["--yes"]].concat()).await;
+    assert_eq!(2, summary["succeeded"], "{}", summary);
+    assert_eq!(0, summary["failed"], "{}", summary);
+    assert_eq!("dev-a", summary["results"][0]["description"]);
+
+    let clusters = esc
+        .json(&[&["mesdb", "clusters", "list"][..], &ids[..]].concat())
+        .await;
+    let mut statuses: Vec<String> = clusters["clusters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| format!("{} {}", c["description"], c["status"]).replace('"', ""))
+        .collect();
+    statuses.sort();
+    assert_eq!(
+        vec![
+            "dev-a disks available",
+            "dev-b disks available",
+            "prod available"
+        ],
+        statuses
+    );
+
+    let output = esc
+        .run(&[&["mesdb", "clusters", "stop"][..], &ids[..]].concat())
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn test_waits_last_until_the_cluster_is_resized() {
+    let esc = Esc::new("resize-wait");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+    let created = esc
+        .cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let cluster_id = created["id"].as_str().unwrap();
+
+    // The mock still shows the cluster available as F1 on the first poll.
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "resize", "--id", cluster_id][..],
+                &ids[..],
+                &["--target-size", "C4", "--wait"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is resizing"), "{}", stderr);
+
+    let cluster = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "get", "--id", cluster_id][..],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!("C4", cluster["cluster"]["instanceType"]);
+    assert_eq!("available", cluster["cluster"]["status"]);
+}
+
+#[tokio::test]
+async fn test_deletes_need_confirmation_and_spare_protected_clusters() {
+    let esc = Esc::new("confirm");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+    let created = esc
+        .cluster(&org_id, &project_id, &network_id, &["--wait"])
+        .await;
+    let cluster_id = created["id"].as_str().unwrap();
+    let delete = [
+        &["mesdb", "clusters", "delete", "--id", cluster_id][..],
+        &ids[..],
+    ]
+    .concat();
+
+    let output = esc.run(&delete).await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("without --yes"), "{}", stderr);
+    assert!(stderr.contains("description: cluster"), "{}", stderr);
+
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "update", "--id", cluster_id][..],
+                &ids[..],
+                &["--protected", "true"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+    let output = esc.run(&[&delete[..], &["--yes"]].concat()).await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--protected false"), "{}", stderr);
+
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "update", "--id", cluster_id][..],
+                &ids[..],
+                &["--protected", "false"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+    let output = esc.run(&[&delete[..], &["--yes"]].concat()).await;
+    assert!(output.status.success(), "{:?}", output);
+
+    let output = esc
+        .run(
+            &[
+                &["infra", "networks", "delete", "--id", &network_id][..],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("still used by"), "{}", stderr);
+}
+
+#[tokio::test]
+async fn test_upgrades_resolve_versions_and_guard_major_changes() {
+    let esc = Esc::new("versions");
+    let (org_id, project_id, network_id) = esc.network().await;
+    let ids = [
+        "--org-id",
+        org_id.as_str(),
+        "--project-id",
+        project_id.as_str(),
+    ];
+    let versions = esc
+        .json(&["mesdb", "versions", "list", "--org-id", &org_id])
+        .await;
+    let recommended: Vec<&serde_json::Value> = versions["versions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|v| v["recommended"] == true)
+        .collect();
+    assert_eq!(
+        vec!["22.10.3"],
+        recommended
+            .iter()
+            .map(|v| v["tag"].as_str().unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    let created = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "create", "--network-id", &network_id][..],
+                &ids[..],
+                &[
+                    "--description",
+                    "cluster",
+                    "--topology",
+                    "single-node",
+                    "--instance-type",
+                    "F1",
+                    "--disk-size-in-gb",
+                    "16",
+                    "--disk-type",
+                    "gp3",
+                    "--server-version",
+                    "21.10.8",
+                    "--projection-level",
+                    "off",
+                    "--wait",
+                ],
+            ]
+            .concat(),
+        )
+        .await;
+    let cluster_id = created["id"].as_str().unwrap();
+    let upgrades = esc
+        .json(
+            &[
+                &["mesdb", "versions", "list", "--for-cluster", cluster_id][..],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    let latest = upgrades["versions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["tag"] == "22.10.3")
+        .unwrap();
+    assert_eq!("major", latest["change"]);
+    assert!(!latest["warnings"].as_array().unwrap().is_empty());
+
+    let upgrade = [
+        &["mesdb", "clusters", "upgrade", "--id", cluster_id][..],
+        &ids[..],
+    ]
+    .concat();
+    let output = esc
+        .run(&[&upgrade[..], &["--to", "latest-patch", "--wait"]].concat())
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+    let cluster = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "get", "--id", cluster_id][..],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!("21.10.9", cluster["cluster"]["serverVersionTag"]);
+
+    let output = esc
+        .run(&[&upgrade[..], &["--to", "recommended"]].concat())
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--allow-major"), "{}", stderr);
+
+    let output = esc
+        .run(&[&upgrade[..], &["--to", "recommended", "--allow-major"]].concat())
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("can't be rolled back"), "{}", stderr);
+    assert!(stderr.contains("without --yes"), "{}", stderr);
+
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "upgrade", "--all"][..],
+                &ids[..],
+                &["--to", "recommended", "--allow-major"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("can't be rolled back"), "{}", stderr);
+    assert!(
+        stderr.contains("upgrade 1 cluster(s) without --yes"),
+        "{}",
+        stderr
+    );
+    let cluster = esc
+        .json(
+            &[
+                &["mesdb", "clusters", "get", "--id", cluster_id][..],
+                &ids[..],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!("21.10.9", cluster["cluster"]["serverVersionTag"]);
+
+    let output = esc
+        .run(
+            &[
+                &["mesdb", "clusters", "upgrade", "--id", "missing"][..],
+                &ids[..],
+                &["--to", "latest-patch"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert_eq!(Some(13), output.status.code(), "{:?}", output);
+
+    let output = esc
+        .run(
+            &[
+                &upgrade[..],
+                &["--to", "recommended", "--allow-major", "--yes"],
+            ]
+            .concat(),
+        )
+        .await;
+    assert!(output.status.success(), "{:?}", output);
+}
+
+/// Saves a token with the refresh token where `esc` looks for it when
+/// talking to the mock, and returns the file's path.
+fn save_token(esc: &Esc, refresh_token: &str) -> std::path::PathBuf {
+    let token_file = esc.home.join(".esc").join("tokens").join("127.0.0.1");
+    std::fs::create_dir_all(token_file.parent().unwrap()).unwrap();
+    let token = serde_json::json!({
+        "access_token": "expired",
+        "refresh_token": refresh_token,
+        "scope": "cloud:access offline_access",
+        "expires_in": 0,
+        "token_type": "Bearer",
+    });
+    std::fs::write(&token_file, token.to_string()).unwrap();
+    token_file
+}
+
+/// The ids of the user's refresh tokens, as seen with the harness's token.
+async fn token_ids(esc: &Esc) -> Vec<String> {
+    let tokens = esc.json(&["access", "tokens", "list"]).await;
+    tokens["tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|token| token["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+// The mock's refresh tokens are their id followed by a secret.
+fn token_id(refresh_token: &str) -> String {
+    refresh_token.split('.').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_logout_revokes_the_saved_refresh_token_and_removes_it() {
+    let esc = Esc::new("logout");
+    let saved = esc._server.refresh_token("test@example.com");
+    let token_file = save_token(&esc, &saved);
+
+    let output = esc.run_with(None, &["logout"]).await;
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!token_file.exists());
+    assert_eq!(vec![token_id(&esc.refresh_token)], token_ids(&esc).await);
+    let output = esc
+        .run_with(Some(&saved), &["access", "tokens", "list"])
+        .await;
+    assert!(!output.status.success(), "{:?}", output);
+    let output = esc.run_with(None, &["logout"]).await;
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("nothing to revoke"),
+        "{:?}",
+        output
+    );
+}
+
+#[tokio::test]
+async fn test_logout_with_a_refresh_token_keeps_another_saved_one() {
+    let esc = Esc::new("logout-given");
+    let given = esc._server.refresh_token("test@example.com");
+    let saved = esc._server.refresh_token("test@example.com");
+    let token_file = save_token(&esc, &saved);
+
+    let output = esc.run_with(Some(&given), &["logout"]).await;
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(token_file.exists());
+    let mut remaining = token_ids(&esc).await;
+    remaining.sort();
+    let mut expected = vec![token_id(&esc.refresh_token), token_id(&saved)];
+    expected.sort();
+    assert_eq!(expected, remaining);
+}
+
+#[tokio::test]
+async fn test_access_settings_are_validated_and_dumped() {
+    let esc = Esc::new("settings");
+    let org_id = esc
+        .json(&["resources", "organizations", "create", "--name", "org"])
+        .await["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let set = |name: &'static str, value: &'static str| {
+        let org_id = org_id.clone();
+        let esc = &esc;
+        async move {
+            esc.run(&[
+                "access", "settings", "set", "--org-id", &org_id, "--name", name, "--value", value,
+            ])
+            .await
+        }
+    };
+
+    assert!(set("require-mfa", "true").await.status.success());
+    assert!(set("restrict-invite-domain", "Example.com")
+        .await
+        .status
+        .success());
+    let output = set("require-mfa", "yes").await;
+    assert!(!output.status.success(), "{:?}", output);
+    let output = set("restrict-invite-domain", "not a domain").await;
+    assert!(!output.status.success(), "{:?}", output);
+    let output = set("mfa", "true").await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("require-mfa, restrict-invite-domain"),
+        "{}",
+        stderr
+    );
+
+    let settings = esc
+        .json(&["access", "settings", "get", "--org-id", &org_id])
+        .await;
+    assert_eq!(
+        serde_json::json!({"require-mfa": true, "restrict-invite-domain": "example.com"}),
+        settings
+    );
+    let output = esc
+        .run(&[
+            "access",
+            "settings",
+            "get",
+            "--org-id",
+            &org_id,
+            "--name",
+            "restrict-invite-domain",
+        ])
+        .await;
+    assert_eq!("example.com\n", String::from_utf8_lossy(&output.stdout));
+    let output = esc
+        .run(&["access", "settings", "get", "--org-id", &org_id])
+        .await;
+    assert_eq!(
+        "require-mfa: true\nrestrict-invite-domain: example.com\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let require_mfa = esc
+        .json(&[
+            "access",
+            "settings",
+            "get",
+            "--org-id",
+            &org_id,
+            "--name",
+            "require-mfa",
+        ])
+        .await;
+    assert_eq!(serde_json::json!(true), require_mfa);
+}
+
+#[tokio::test]
+async fn test_policy_checks_explain_which_policies_decided() {
+    let esc = Esc::new("policies");
+    let org_id = esc
+        .json(&["resources", "organizations", "create", "--name", "org"])
+        .await["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let members = esc
+        .json(&["access", "members", "list", "--org-id", &org_id])
+        .await;
+    let member_id = members["members"][0]["id"].as_str().unwrap();
+    let group_id = esc
+        .json(&[
+            "access",
+            "groups",
+            "create",
+            "--org-id",
+            &org_id,
+            "--name",
+            "devs",
+            "--members",
+            member_id,
+        ])
+        .await["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for (name, subject, resource, effect) in &[
+        ("devs", group_id.as_str(), "orgs/*", "allow"),
+        ("no clusters", "*", "orgs/clusters", "deny"),
+    ] {
+        esc.json(&[
+            "access",
+            "policies",
+            "create",
+            "--org-id",
+            &org_id,
+            "--name",
+            name,
+            "--subjects",
+            subject,
+            "--resources",
+            resource,
+            "--actions",
+            "create",
+            "--effect",
+            effect,
+        ])
+        .await;
+    }
+    let check = |resource: &'static str, subject: Option<&'static str>| {
+        let org_id = org_id.clone();
+        let esc = &esc;
+        async move {
+            let mut args = vec![
+                "access",
+                "policies",
+                "check",
+                "--org-id",
+                &org_id,
+                "--action",
+                "create",
+                "--resource",
+                resource,
+            ];
+            args.extend(subject.iter().flat_map(|s| ["--subject", s]));
+            esc.json(&args).await
+        }
+    };
+
+    let clusters = check("orgs/clusters", Some("test@example.com")).await;
+    assert_eq!(false, clusters["allowed"], "{}", clusters);
+    assert_eq!(true, clusters["denyWon"], "{}", clusters);
+    assert_eq!(2, clusters["matches"].as_array().unwrap().len());
+    assert!(clusters["matches"][0]["via"][0]
+        .as_str()
+        .unwrap()
+        .contains("devs"));
+
+    let networks = check("orgs/networks", None).await;
+    assert_eq!(true, networks["allowed"], "{}", networks);
+
+    let stranger = check("orgs/networks", Some("other@example.com")).await;
+    assert_eq!(false, stranger["allowed"], "{}", stranger);
+    assert!(stranger["matches"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_integrations_are_checked_against_the_options() {
+    let esc = Esc::new("integrations");
+    let (org_id, project_id, _) = esc.network().await;
+    let options = esc
+        .json(&["integrations", "options", "--organization-id", &org_id])
+        .await;
+    let sources: Vec<&str> = options["sources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(vec!["issues", "notifications", "metrics", "logs"], sources);
+
+    let create = |source: &'static str| {
+        let (org_id, project_id) = (org_id.clone(), project_id.clone());
+        let esc = &esc;
+        async move {
+            esc.run(&[
+                "integrations",
+                "create",
+                "--organization-id",
+                &org_id,
+                "--project-id",
+                &project_id,
+                "--description",
+                "alerts",
+                "slack",
+                "--channel-id",
+                "C123",
+                "--token",
+                "xoxb-secret",
+                "--source",
+                source,
+            ])
+            .await
+        }
+    };
+    let output = create("metrics").await;
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("valid --source values are: issues, notifications"),
+        "{}",
+        stderr
+    );
+
+    let output = create("notifications").await;
+    assert!(output.status.success(), "{:?}", output);
+}
@@ -0,0 +1,311 @@
+use esc_client_generated::access::*;
+use esc_client_generated::resources::OrganizationId;
+use hyper::Method;
+use serde_json::Value;
+
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{Request, State};
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", org, "groups"]) => {
+            let linked_resource = req.query.get("linked_resource");
+            json(&ListGroupsResponse {
+                groups: state
+                    .groups
+                    .values()
+                    .filter(|g| g.organization_id.0 == *org)
+                    .filter(|g| linked_resource.map_or(true, |r| g.linked_resource == *r))
+                    .cloned()
+                    .collect(),
+            })
+        }
+        (&Method::POST, ["organizations", org, "groups"]) => {
+            let body: CreateGroupRequest = req.json()?;
+            let id = state.new_id("group");
+            state.groups.insert(
+                id.clone(),
+                Group {
+                    created: chrono::Utc::now(),
+                    id: GroupId(id.clone()),
+                    linked_resource: String::new(),
+                    members: body.members.unwrap_or_default(),
+                    name: body.name,
+                    organization_id: OrganizationId(org.to_string()),
+                },
+            );
+            json(&CreateGroupResponse { id: GroupId(id) })
+        }
+        (&Method::GET, ["organizations", org, "groups", id]) => json(&GetGroupResponse {
+            group: group_mut(state, org, id)?.clone(),
+        }),
+        (&Method::PUT, ["organizations", org, "groups", id]) => {
+            let body: UpdateGroupRequest = req.json()?;
+            let group = group_mut(state, org, id)?;
+            if let Some(name) = body.name {
+                group.name = name;
+            }
+            if let Some(members) = body.members {
+                group.members = members.into_iter().map(MemberId).collect();
+            }
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org, "groups", id]) => {
+            group_mut(state, org, id)?;
+            state.groups.remove(*id);
+            empty()
+        }
+
+        (&Method::GET, ["organizations", org, "invites"]) => json(&ListInvitesResponse {
+            invites: state
+                .invites
+                .values()
+                .filter(|i| i.organization_id.0 == *org)
+                .cloned()
+                .collect(),
+        }),
+        (&Method::POST, ["organizations", org, "invites"]) => {
+            let body: CreateInviteRequest = req.json()?;
+            if !body.user_email.contains('@') {
+                return Err(Problem::invalid_field("userEmail", "not an email address"));
+            }
+            let id = state.new_id("invite");
+            state.invites.insert(
+                id.clone(),
+                Invite {
+                    accepted: false,
+                    created: chrono::Utc::now(),
+                    email: body.user_email,
+                    expired: Some(false),
+                    groups: body.groups,
+                    id: InviteId(id.clone()),
+                    organization_id: OrganizationId(org.to_string()),
+                },
+            );
+            json(&CreateInviteResponse { id: InviteId(id) })
+        }
+        (&Method::PUT, ["organizations", org, "invites"]) => {
+            let body: ResendInviteRequest = req.json()?;
+            let invite = state
+                .invites
+                .get_mut(body.id.0.as_str())
+                .filter(|i| i.organization_id.0 == *org)
+                .ok_or_else(|| Problem::not_found("invite", &body.id.0))?;
+            invite.created = chrono::Utc::now();
+            invite.expired = Some(false);
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org, "invites", id]) => {
+            match state.invites.get(*id) {
+                Some(invite) if invite.organization_id.0 == *org => {}
+                _ => return Err(Problem::not_found("invite", id)),
+            }
+            state.invites.remove(*id);
+            empty()
+        }
+
+        (&Method::GET, ["organizations", org, "members"]) => json(&ListMembersResponse {
+            members: state
+                .members
+                .values()
+                .filter(|m| m.organization_id.0 == *org)
+                .cloned()
+                .collect(),
+        }),
+        (&Method::GET, ["organizations", org, "members", id]) => json(&GetMemberResponse {
+            member: member_mut(state, org, id)?.clone(),
+        }),
+        (&Method::PUT, ["organizations", org, "members", id]) => {
+            let body: UpdateMemberRequest = req.json()?;
+            member_mut(state, org, id)?.active = body.active;
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org, "members", id]) => {
+            member_mut(state, org, id)?;
+            state.members.remove(*id);
+            empty()
+        }
+
+        (&Method::GET, ["organizations", org, "policies"]) => json(&ListPoliciesResponse {
+            policies: state
+                .policies
+                .values()
+                .filter(|p| p.organization_id.0 == *org)
+                .cloned()
+                .collect(),
+        }),
+        (&Method::POST, ["organizations", org, "policies"]) => {
+            let body: CreatePolicyRequest = req.json()?;
+            let id = state.new_id("policy");
+            state.policies.insert(
+                id.clone(),
+                Policy {
+                    actions: Some(body.policy.actions),
+                    created: chrono::Utc::now(),
+                    effect: Some(body.policy.effect),
+                    linked_resource: String::new(),
+                    id: PolicyId(id.clone()),
+                    name: body.policy.name,
+                    organization_id: OrganizationId(org.to_string()),
+                    resources: Some(body.policy.resources),
+                    subjects: Some(body.policy.subjects),
+                },
+            );
+            json(&CreatePolicyResponse { id: PolicyId(id) })
+        }
+        (&Method::GET, ["organizations", org, "policies", id]) => json(&GetPolicyResponse {
+            policy: policy_mut(state, org, id)?.clone(),
+        }),
+        (&Method::PUT, ["organizations", org, "policies", id]) => {
+            let body: UpdatePolicyRequest = req.json()?;
+            let policy = policy_mut(state, org, id)?;
+            policy.actions = Some(body.policy.actions);
+            policy.effect = Some(body.policy.effect);
+            policy.name = body.policy.name;
+            policy.resources = Some(body.policy.resources);
+            policy.subjects = Some(body.policy.subjects);
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org, "policies", id]) => {
+            policy_mut(state, org, id)?;
+            state.policies.remove(*id);
+            empty()
+        }
+
+        (&Method::GET, ["organizations", org, "settings"]) => json(&GetSettingsResponse {
+            settings: settings_mut(state, org).clone(),
+        }),
+        (&Method::PUT, ["organizations", org, "settings"]) => {
+            let body: UpdateSettingsRequest = req.json()?;
+            let settings = settings_mut(state, org);
+            if let Some(require_mfa) = body.require_mfa {
+                settings.require_mfa = require_mfa;
+            }
+            if let Some(restrict_invite_domain) = body.restrict_invite_domain {
+                settings.restrict_invite_domain = restrict_invite_domain;
+            }
+            empty()
+        }
+
+        (&Method::GET, ["tokens", "user"]) => json(&ListUserRefreshTokensResponse {
+            tokens: state
+                .refresh_tokens
+                .values()
+                .filter(|t| t.subject == req.subject)
+                .map(|t| UserRefreshToken {
+                    id: t.id.clone(),
+                    client_id: t.client_id.clone(),
+                    last_used: t.last_used.to_rfc3339(),
+                })
+                .collect(),
+        }),
+        (&Method::DELETE, ["tokens", "user", id]) => {
+            let before = state.refresh_tokens.len();
+            state
+                .refresh_tokens
+                .retain(|_, t| !(t.id == *id && t.subject == req.subject));
+            if state.refresh_tokens.len() == before {
+                return Err(Problem::not_found("token", id));
+            }
+            empty()
+        }
+
+        (&Method::POST, ["policy", "allowed"]) => {
+            let body: Value = req.json()?;
+            let (action, resource) = allowed_request(&body)?;
+            json(&PolicyAllowedResponse {
+                allowed: is_allowed(state, &req.subject, &action, &resource),
+            })
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+// The generated request has the types of its two fields mixed up, so which
+// of them carries the action depends on who's asking. Whichever field holds
+// an action name is taken as the action.
+fn allowed_request(body: &Value) -> Result<(Action, String), Problem> {
+    let field = |name: &str| body.get(name).and_then(Value::as_str).unwrap_or_default();
+    let parse = |value: &str| serde_json::from_value::<Action>(Value::String(value.to_string()));
+    match (parse(field("action")), parse(field("resource"))) {
+        (Ok(action), _) => Ok((action, field("resource").to_string())),
+        (_, Ok(action)) => Ok((action, field("action").to_string())),
+        _ => Err(Problem::invalid_field("action", "not a known action")),
+    }
+}
+
+/// Policies which deny an action win over those which allow it. If no
+/// policy mentions the action at all it's denied.
+fn is_allowed(state: &State, subject: &str, action: &Action, resource: &str) -> bool {
+    let groups: Vec<&str> = state
+        .groups
+        .values()
+        .filter(|g| {
+            g.members.iter().any(|m| {
+                state
+                    .members
+                    .get(m.0.as_str())
+                    .map_or(false, |m| m.email == subject)
+            })
+        })
+        .map(|g| g.id.0.as_str())
+        .collect();
+    let mut allowed = false;
+    for policy in state.policies.values() {
+        let subjects = policy.subjects.as_deref().unwrap_or_default();
+        let resources = policy.resources.as_deref().unwrap_or_default();
+        let actions = policy.actions.as_deref().unwrap_or_default();
+        let applies = subjects
+            .iter()
+            .any(|s| s == "*" || s == subject || groups.contains(&s.as_str()))
+            && resources.iter().any(|r| matches_resource(r, resource))
+            && actions.contains(action);
+        match (applies, &policy.effect) {
+            (true, Some(Effect::Deny)) => return false,
+            (true, Some(Effect::Allow)) => allowed = true,
+            _ => {}
+        }
+    }
+    allowed
+}
+
+fn matches_resource(pattern: &str, resource: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => resource.starts_with(prefix),
+        None => pattern == resource,
+    }
+}
+
+fn group_mut<'a>(state: &'a mut State, org: &str, id: &str) -> Result<&'a mut Group, Problem> {
+    state
+        .groups
+        .get_mut(id)
+        .filter(|g| g.organization_id.0 == org)
+        .ok_or_else(|| Problem::not_found("group", id))
+}
+
+fn member_mut<'a>(state: &'a mut State, org: &str, id: &str) -> Result<&'a mut Member, Problem> {
+    state
+        .members
+        .get_mut(id)
+        .filter(|m| m.organization_id.0 == org)
+        .ok_or_else(|| Problem::not_found("member", id))
+}
+
+fn policy_mut<'a>(state: &'a mut State, org: &str, id: &str) -> Result<&'a mut Policy, Problem> {
+    state
+        .policies
+        .get_mut(id)
+        .filter(|p| p.organization_id.0 == org)
+        .ok_or_else(|| Problem::not_found("policy", id))
+}
+
+fn settings_mut<'a>(state: &'a mut State, org: &str) -> &'a mut Settings {
+    state
+        .settings
+        .entry(org.to_string())
+        .or_insert_with(|| Settings {
+            require_mfa: false,
+            restrict_invite_domain: String::new(),
+        })
+}
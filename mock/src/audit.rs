@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use esc_client_generated::audit::*;
+use esc_client_generated::resources::OrganizationId;
+use hyper::Method;
+
+use crate::reply::{json, Problem, Reply};
+use crate::state::{Request, State};
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", org, "log"]) => json(&GetAuditResponse {
+            logs: query(state, req, |log| log.organization_id.0 == *org)?,
+        }),
+        (&Method::GET, ["user", "log"]) => {
+            let org = req.query.get("org").filter(|org| !org.is_empty());
+            json(&GetAuditResponse {
+                logs: query(state, req, |log| {
+                    log.user == req.subject && org.map_or(true, |org| log.organization_id.0 == *org)
+                })?,
+            })
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+/// Writes down a change someone made, the way every service reports what
+/// it did to the audit log.
+pub(crate) fn record(state: &mut State, req: &Request, segments: &[&str]) {
+    let organization_id = match segments {
+        [_, _, "organizations", org, ..] => org.to_string(),
+        _ => String::new(),
+    };
+    let service = segments.first().copied().unwrap_or_default().to_string();
+    state.audit_log.push(Log {
+        organization_id: OrganizationId(organization_id),
+        message: format!("{} /{}", req.method, segments.join("/")),
+        user: req.subject.clone(),
+        urn: format!(
+            "urn:esc:{}:{}",
+            service,
+            segments.get(2..).unwrap_or_default().join(":")
+        ),
+        service,
+        time: Utc::now(),
+    });
+}
+
+fn query(state: &State, req: &Request, filter: impl Fn(&Log) -> bool) -> Result<Vec<Log>, Problem> {
+    let before = timestamp(req, "before")?;
+    let after = timestamp(req, "after")?;
+    let limit = match req.query.get("limit").filter(|limit| !limit.is_empty()) {
+        Some(limit) => limit
+            .parse::<usize>()
+            .map_err(|_| Problem::invalid_field("limit", "not a number"))?,
+        None => usize::MAX,
+    };
+    // Newest first, like the real thing.
+    Ok(state
+        .audit_log
+        .iter()
+        .rev()
+        .filter(|log| filter(log))
+        .filter(|log| before.map_or(true, |before| log.time < before))
+        .filter(|log| after.map_or(true, |after| log.time > after))
+        .take(limit)
+        .cloned()
+        .collect())
+}
+
+fn timestamp(req: &Request, name: &str) -> Result<Option<DateTime<Utc>>, Problem> {
+    match req.query.get(name).filter(|value| !value.is_empty()) {
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(|time| Some(time.with_timezone(&Utc)))
+            .map_err(|_| Problem::invalid_field(name, "not an RFC 3339 timestamp")),
+        None => Ok(None),
+    }
+}
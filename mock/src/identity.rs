@@ -0,0 +1,220 @@
+use hyper::{Body, Response, StatusCode};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::server::MockConfig;
+use crate::state::{RefreshToken, State};
+
+/// Private key the mock signs access tokens with. It's for tests only and
+/// must never be trusted anywhere else.
+pub static TEST_PRIVATE_KEY: &str = include_str!("keys/private.pem");
+
+/// Public half of [`TEST_PRIVATE_KEY`], which clients need in their token
+/// config to accept the mock's access tokens.
+pub static TEST_PUBLIC_KEY: &str = include_str!("keys/public.pem");
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    aud: String,
+    exp: i64,
+    iat: i64,
+    iss: String,
+    jti: String,
+    sub: String,
+}
+
+/// Issues and verifies access tokens.
+pub(crate) struct Issuer {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    audience: String,
+    url: String,
+    lifetime: Duration,
+}
+
+impl Issuer {
+    pub fn new(
+        url: &str,
+        audience: &str,
+        lifetime: Duration,
+    ) -> jsonwebtoken::errors::Result<Self> {
+        Ok(Self {
+            encoding_key: EncodingKey::from_rsa_pem(TEST_PRIVATE_KEY.as_bytes())?,
+            decoding_key: DecodingKey::from_rsa_pem(TEST_PUBLIC_KEY.as_bytes())?,
+            audience: audience.to_string(),
+            url: url.to_string(),
+            lifetime,
+        })
+    }
+
+    pub fn access_token(&self, state: &mut State, subject: &str) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            aud: self.audience.clone(),
+            exp: now + self.lifetime.as_secs() as i64,
+            iat: now,
+            iss: format!("{}/", self.url),
+            jti: state.new_id("token"),
+            sub: subject.to_string(),
+        };
+        jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.encoding_key)
+            .expect("test key can sign tokens")
+    }
+
+    /// Checks the access token and returns who it was issued to.
+    pub fn verify(&self, access_token: &str) -> Result<String, String> {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&self.audience]);
+        validation.leeway = 0;
+        jsonwebtoken::decode::<Claims>(access_token, &self.decoding_key, &validation)
+            .map(|data| data.claims.sub)
+            .map_err(|err| format!("invalid access token: {}", err))
+    }
+
+    pub fn refresh_token(&self, state: &mut State, client_id: &str, subject: &str) -> String {
+        let id = state.new_id("refresh");
+        // Refresh tokens are secrets, so unlike IDs they shouldn't be
+        // guessable. Every RandomState is seeded with fresh random keys.
+        let secret = RandomState::new().build_hasher().finish();
+        let token = format!("{}.{:016x}", id, secret);
+        state.refresh_tokens.insert(
+            token.clone(),
+            RefreshToken {
+                id,
+                client_id: client_id.to_string(),
+                subject: subject.to_string(),
+                last_used: chrono::Utc::now(),
+            },
+        );
+        token
+    }
+}
+
+#[derive(Serialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    scope: String,
+    expires_in: u64,
+    token_type: String,
+}
+
+/// Handles `POST /oauth/token` for the password and refresh token grants.
+pub(crate) fn token(
+    issuer: &Issuer,
+    config: &MockConfig,
+    state: &mut State,
+    body: &[u8],
+) -> Response<Body> {
+    let form: HashMap<String, String> = url::form_urlencoded::parse(body).into_owned().collect();
+    let field = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+    let client_id = field("client_id");
+
+    let (subject, refresh_token) = match field("grant_type") {
+        "password" => {
+            let user_name = field("username");
+            let accepted = match &config.users {
+                Some(users) => users.get(user_name).map(String::as_str) == Some(field("password")),
+                None => !user_name.is_empty(),
+            };
+            if !accepted {
+                return oauth_error(
+                    StatusCode::FORBIDDEN,
+                    "invalid_grant",
+                    "Wrong email or password.",
+                );
+            }
+            let refresh_token = if field("scope").split(' ').any(|s| s == "offline_access") {
+                Some(issuer.refresh_token(state, client_id, user_name))
+            } else {
+                None
+            };
+            (user_name.to_string(), refresh_token)
+        }
+        "refresh_token" => match state.refresh_tokens.get_mut(field("refresh_token")) {
+            Some(token) => {
+                token.last_used = chrono::Utc::now();
+                (token.subject.clone(), None)
+            }
+            None => {
+                return oauth_error(
+                    StatusCode::FORBIDDEN,
+                    "invalid_grant",
+                    "Unknown or invalid refresh token.",
+                )
+            }
+        },
+        other => {
+            return oauth_error(
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                &format!("Grant type {:?} not allowed for the client.", other),
+            )
+        }
+    };
+
+    let response = TokenResponse {
+        access_token: issuer.access_token(state, &subject),
+        refresh_token,
+        scope: "cloud:access offline_access".to_string(),
+        expires_in: config.access_token_lifetime.as_secs(),
+        token_type: "Bearer".to_string(),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::to_string(&response).expect("token response serializes"),
+        ))
+        .expect("response is valid")
+}
+
+#[derive(Deserialize)]
+struct RevokeRequest {
+    client_id: String,
+    token: String,
+}
+
+/// Handles `POST /oauth/revoke`. As OAuth has it, revoking a token which
+/// doesn't exist, or wasn't issued to the client, succeeds without doing
+/// anything.
+pub(crate) fn revoke(state: &mut State, body: &[u8]) -> Response<Body> {
+    let request: RevokeRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => {
+            return oauth_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                &format!("Invalid revocation request: {}", err),
+            )
+        }
+    };
+    let issued_to_client = state
+        .refresh_tokens
+        .get(&request.token)
+        .map_or(false, |token| token.client_id == request.client_id);
+    if issued_to_client {
+        state.refresh_tokens.remove(&request.token);
+    }
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::empty())
+        .expect("response is valid")
+}
+
+// Identity errors follow OAuth rather than problem details.
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> Response<Body> {
+    let body = serde_json::json!({
+        "error": error,
+        "error_description": description,
+    });
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("response is valid")
+}
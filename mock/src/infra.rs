@@ -0,0 +1,278 @@
+use esc_client_generated::infra::*;
+use esc_client_generated::mesdb::ClusterStatus;
+use esc_client_generated::resources::ProjectId;
+use hyper::Method;
+
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{observe, observe_all, Request, State, Step, Tracked};
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", _, "projects", project, "networks"]) => {
+            json(&ListNetworksResponse {
+                networks: observe_all(&mut state.networks, state.step, |n| {
+                    n.project_id.0 == *project
+                }),
+            })
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "networks"]) => {
+            create_network(state, req, project)
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "networks", id]) => {
+            json(&GetNetworkResponse {
+                network: get_network(state, project, id)?,
+            })
+        }
+        (&Method::PUT, ["organizations", _, "projects", project, "networks", id]) => {
+            let body: UpdateNetworkRequest = req.json()?;
+            get_network(state, project, id)?;
+            if let Some(network) = state.networks.get_mut(*id) {
+                network.resource.description = body.description;
+            }
+            empty()
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "networks", id]) => {
+            delete_network(state, project, id)
+        }
+
+        (&Method::GET, ["organizations", _, "projects", project, "peerings"]) => {
+            json(&ListPeeringsResponse {
+                peerings: observe_all(&mut state.peerings, state.step, |p| {
+                    p.project_id.0 == *project
+                }),
+            })
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "peerings"]) => {
+            create_peering(state, req, project)
+        }
+        (&Method::POST, ["organizations", _, "projects", _, "peerings", "commands"]) => {
+            let body: CreatePeeringCommandsRequest = req.json()?;
+            json(&CreatePeeringCommandsResponse {
+                commands: vec![Command {
+                    file_name: None,
+                    language: "shell".to_string(),
+                    title: "Accept the peering request".to_string(),
+                    value: format!(
+                        "aws ec2 accept-vpc-peering-connection --vpc-peering-connection-id {}",
+                        body.peer_network_id
+                    ),
+                }],
+            })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "peerings", id]) => {
+            json(&GetPeeringResponse {
+                peering: get_peering(state, project, id)?,
+            })
+        }
+        (&Method::PUT, ["organizations", _, "projects", project, "peerings", id]) => {
+            let body: UpdatePeeringRequest = req.json()?;
+            get_peering(state, project, id)?;
+            if let Some(peering) = state.peerings.get_mut(*id) {
+                peering.resource.description = body.description;
+            }
+            empty()
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "peerings", id]) => {
+            get_peering(state, project, id)?;
+            if let Some(peering) = state.peerings.get_mut(*id) {
+                peering.start(
+                    PeeringStatus::Deleting,
+                    vec![Step::Status(PeeringStatus::Deleted), Step::Remove],
+                );
+            }
+            empty()
+        }
+
+        (&Method::GET, ["organizations", _, "projects", project, "acls"]) => {
+            json(&ListAclsResponse {
+                acls: state
+                    .acls
+                    .values()
+                    .filter(|a| a.project_id.0 == *project)
+                    .cloned()
+                    .collect(),
+            })
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "acls"]) => {
+            let body: CreateAclRequest = req.json()?;
+            validate_cidr_blocks(&body.cidr_blocks)?;
+            let id = state.new_id("acl");
+            state.acls.insert(
+                id.clone(),
+                Acl {
+                    cidr_blocks: body.cidr_blocks,
+                    created: chrono::Utc::now().to_rfc3339(),
+                    description: body.description,
+                    id: AclId(id.clone()),
+                    project_id: ProjectId(project.to_string()),
+                    status: AclStatus::Active,
+                    updated: None,
+                },
+            );
+            json(&CreateAclResponse { id: AclId(id) })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "acls", id]) => {
+            json(&GetAclResponse {
+                acl: acl_mut(state, project, id)?.clone(),
+            })
+        }
+        (&Method::PUT, ["organizations", _, "projects", project, "acls", id]) => {
+            let body: UpdateAclRequest = req.json()?;
+            if let Some(cidr_blocks) = &body.cidr_blocks {
+                validate_cidr_blocks(cidr_blocks)?;
+            }
+            let acl = acl_mut(state, project, id)?;
+            if let Some(cidr_blocks) = body.cidr_blocks {
+                acl.cidr_blocks = cidr_blocks;
+            }
+            if let Some(description) = body.description {
+                acl.description = description;
+            }
+            acl.updated = Some(chrono::Utc::now().to_rfc3339());
+            empty()
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "acls", id]) => {
+            acl_mut(state, project, id)?;
+            if state.clusters.values().any(|c| {
+                c.resource.acl_id.as_deref() == Some(*id)
+                    && c.resource.status != ClusterStatus::Deleted
+            }) {
+                return Err(Problem::conflict("The ACL is still used by a cluster"));
+            }
+            state.acls.remove(*id);
+            empty()
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+fn create_network(state: &mut State, req: &Request, project: &str) -> Reply {
+    let body: CreateNetworkRequest = req.json()?;
+    if !["aws", "gcp", "azure"].contains(&body.provider.as_str()) {
+        return Err(Problem::invalid_field(
+            "provider",
+            "must be one of aws, gcp or azure",
+        ));
+    }
+    if body.region.is_empty() {
+        return Err(Problem::invalid_field("region", "is required"));
+    }
+    let id = state.new_id("network");
+    let network = Network {
+        cidr_block: body.cidr_block,
+        created: chrono::Utc::now().to_rfc3339(),
+        description: body.description,
+        id: NetworkId(id.clone()),
+        project_id: ProjectId(project.to_string()),
+        provider: body.provider,
+        public_access: body.public_access,
+        region: body.region,
+        status: NetworkStatus::Provisioning,
+    };
+    state.networks.insert(
+        id.clone(),
+        Tracked::new(network, vec![Step::Status(NetworkStatus::Available)]),
+    );
+    json(&CreateNetworkResponse { id: NetworkId(id) })
+}
+
+fn delete_network(state: &mut State, project: &str, id: &str) -> Reply {
+    get_network(state, project, id)?;
+    if state
+        .clusters
+        .values()
+        .any(|c| c.resource.network_id.0 == id && c.resource.status != ClusterStatus::Deleted)
+    {
+        return Err(Problem::conflict("The network still has clusters"));
+    }
+    if state
+        .peerings
+        .values()
+        .any(|p| p.resource.network_id.0 == id && p.resource.status != PeeringStatus::Deleted)
+    {
+        return Err(Problem::conflict("The network still has peerings"));
+    }
+    if let Some(network) = state.networks.get_mut(id) {
+        network.start(
+            NetworkStatus::Deleting,
+            vec![Step::Status(NetworkStatus::Deleted), Step::Remove],
+        );
+    }
+    empty()
+}
+
+fn create_peering(state: &mut State, req: &Request, project: &str) -> Reply {
+    let body: CreatePeeringRequest = req.json()?;
+    let network = match state.networks.get(body.network_id.0.as_str()) {
+        Some(network) if network.resource.project_id.0 == project => network.resource.clone(),
+        _ => return Err(Problem::invalid_field("networkId", "network not found")),
+    };
+    if body.routes.is_empty() {
+        return Err(Problem::invalid_field("routes", "at least one is required"));
+    }
+    let id = state.new_id("peering");
+    let peering = Peering {
+        created: chrono::Utc::now().to_rfc3339(),
+        description: body.description,
+        id: PeeringId(id.clone()),
+        network_id: body.network_id,
+        network_cidr_block: network.cidr_block.unwrap_or_default(),
+        project_id: ProjectId(project.to_string()),
+        provider: network.provider,
+        peer_account_id: body.peer_account_id,
+        peer_network_id: body.peer_network_id,
+        peer_network_region: body.peer_network_region,
+        provider_peering_metadata: None,
+        routes: body.routes,
+        status: PeeringStatus::Provisioning,
+    };
+    // The peer accepting the request is what takes a peering from
+    // initiated to active; the mock accepts on the peer's behalf.
+    state.peerings.insert(
+        id.clone(),
+        Tracked::new(
+            peering,
+            vec![
+                Step::Status(PeeringStatus::Initiated),
+                Step::Status(PeeringStatus::Active),
+            ],
+        ),
+    );
+    json(&CreatePeeringResponse { id: PeeringId(id) })
+}
+
+fn get_network(state: &mut State, project: &str, id: &str) -> Result<Network, Problem> {
+    observe(&mut state.networks, id, state.step)
+        .filter(|n| n.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("network", id))
+}
+
+fn get_peering(state: &mut State, project: &str, id: &str) -> Result<Peering, Problem> {
+    observe(&mut state.peerings, id, state.step)
+        .filter(|p| p.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("peering", id))
+}
+
+fn acl_mut<'a>(state: &'a mut State, project: &str, id: &str) -> Result<&'a mut Acl, Problem> {
+    state
+        .acls
+        .get_mut(id)
+        .filter(|a| a.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("acl", id))
+}
+
+fn validate_cidr_blocks(cidr_blocks: &[AclCidrBlock]) -> Result<(), Problem> {
+    if cidr_blocks.is_empty() {
+        return Err(Problem::invalid_field(
+            "cidrBlocks",
+            "at least one is required",
+        ));
+    }
+    match cidr_blocks.iter().find(|b| !b.address.contains('/')) {
+        Some(block) => Err(Problem::invalid_field(
+            "cidrBlocks",
+            &format!("{} is not a CIDR block", block.address),
+        )),
+        None => Ok(()),
+    }
+}
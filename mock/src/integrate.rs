@@ -0,0 +1,206 @@
+use esc_client_generated::integrate::*;
+use esc_client_generated::resources::{OrganizationId, ProjectId};
+use hyper::Method;
+use serde_json::Value;
+
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{Request, State};
+
+// (source, sinks it can send to). Every sink supports the test endpoint
+// except the metric and log shippers, which only move data around.
+const SOURCES: &[(&str, &str, &[&str])] = &[
+    ("issues", "Issues", &["opsGenie", "pagerDuty", "slack"]),
+    ("notifications", "Notifications", &["slack"]),
+    ("metrics", "Metrics", &["awsCloudWatchMetrics"]),
+    ("logs", "Logs", &["awsCloudWatchLogs", "gcpLogging"]),
+];
+
+// Fields holding secrets, which are only ever shown masked in a matching
+// `*Display` field. A GCP private key isn't shown at all.
+const SECRETS: &[&str] = &[
+    "accessKeyId",
+    "apiKey",
+    "authToken",
+    "gcpServiceAccountPrivateKey",
+    "secretAccessKey",
+    "token",
+];
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", _, "integrations", "options"]) => {
+            json(&IntegrationsOptionsResponse { sources: options() })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "integrations"]) => {
+            json(&ListIntegrationsResponse {
+                integrations: state
+                    .integrations
+                    .values()
+                    .filter(|i| i.project_id.0 == *project)
+                    .cloned()
+                    .collect(),
+            })
+        }
+        (&Method::POST, ["organizations", org, "projects", project, "integrations"]) => {
+            let body: CreateIntegrationRequest = req.json()?;
+            let data = stored_data(serde_json::to_value(&body.data).expect("data serializes"))?;
+            let id = state.new_id("integration");
+            let now = chrono::Utc::now();
+            state.integrations.insert(
+                id.clone(),
+                Integration {
+                    created: now,
+                    data,
+                    description: body.description,
+                    id: IntegrationId(id.clone()),
+                    organization_id: OrganizationId(org.to_string()),
+                    project_id: ProjectId(project.to_string()),
+                    status: IntegrationStatus::Active,
+                    updated: now,
+                },
+            );
+            json(&CreateIntegrationResponse {
+                id: IntegrationId(id),
+            })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "integrations", id]) => {
+            json(&GetIntegrationResponse {
+                integration: integration_mut(state, project, id)?.clone(),
+            })
+        }
+        (&Method::PUT, ["organizations", _, "projects", project, "integrations", id]) => {
+            // The generated update data is untagged and every field is
+            // optional, so it can't tell the sinks apart; go by field names.
+            let body: Value = req.json()?;
+            let integration = integration_mut(state, project, id)?;
+            if let Some(description) = body.get("description").and_then(Value::as_str) {
+                integration.description = description.to_string();
+            }
+            if let Some(Value::Object(changes)) = body.get("data") {
+                let mut data = serde_json::to_value(&integration.data).expect("data serializes");
+                for (field, value) in changes {
+                    data[field.as_str()] = value.clone();
+                }
+                integration.data = stored_data(data)?;
+            }
+            integration.updated = chrono::Utc::now();
+            empty()
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "integrations", id]) => {
+            integration_mut(state, project, id)?;
+            state.integrations.remove(*id);
+            empty()
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "integrations", id, "test"]) => {
+            let sink = sink_of(&integration_mut(state, project, id)?.data);
+            if options()
+                .iter()
+                .flat_map(|source| source.sinks.iter())
+                .any(|s| s.id == sink && s.supports_test_api)
+            {
+                empty()
+            } else {
+                Err(Problem::bad_request(&format!(
+                    "{} integrations can't be tested",
+                    sink
+                )))
+            }
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+pub(crate) fn options() -> Vec<SourceSummary> {
+    SOURCES
+        .iter()
+        .map(|(id, name, sinks)| SourceSummary {
+            enabled: true,
+            id: id.to_string(),
+            name: name.to_string(),
+            sinks: sinks
+                .iter()
+                .map(|sink| SinkSummary {
+                    id: sink.to_string(),
+                    name: sink.to_string(),
+                    enabled: true,
+                    supports_test_api: !sink.starts_with("aws") && !sink.starts_with("gcp"),
+                    label: None,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// Turns integration data as sent by a client into what's kept (and shown),
+// checking the source can feed the sink along the way.
+fn stored_data(data: Value) -> Result<IntegrationData, Problem> {
+    let mut data = match data {
+        Value::Object(data) => data,
+        _ => return Err(Problem::invalid_field("data", "must be an object")),
+    };
+    let sink = data
+        .get("sink")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let sources: Vec<&str> = SOURCES
+        .iter()
+        .filter(|(_, _, sinks)| sinks.contains(&sink.as_str()))
+        .map(|(id, _, _)| *id)
+        .collect();
+    let source = match data.get("source").and_then(Value::as_str) {
+        None => sources.first().copied().unwrap_or_default().to_string(),
+        Some(source) if sources.contains(&source) => source.to_string(),
+        Some(source) => {
+            return Err(Problem::invalid_field(
+                "source",
+                &format!(
+                    "{} can't be sent to {}, valid sources are: {}",
+                    source,
+                    sink,
+                    sources.join(", ")
+                ),
+            ))
+        }
+    };
+    data.insert("source".to_string(), Value::String(source));
+    for secret in SECRETS {
+        if let Some(value) = data.remove(*secret) {
+            if *secret != "gcpServiceAccountPrivateKey" {
+                let value = value.as_str().unwrap_or_default();
+                data.insert(format!("{}Display", secret), Value::String(mask(value)));
+            }
+        }
+    }
+    serde_json::from_value(Value::Object(data))
+        .map_err(|err| Problem::invalid_field("data", &err.to_string()))
+}
+
+fn mask(secret: &str) -> String {
+    let shown: String = secret
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("****{}", shown)
+}
+
+fn sink_of(data: &IntegrationData) -> String {
+    let data = serde_json::to_value(data).expect("data serializes");
+    data["sink"].as_str().unwrap_or_default().to_string()
+}
+
+fn integration_mut<'a>(
+    state: &'a mut State,
+    project: &str,
+    id: &str,
+) -> Result<&'a mut Integration, Problem> {
+    state
+        .integrations
+        .get_mut(id)
+        .filter(|i| i.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("integration", id))
+}
@@ -0,0 +1,26 @@
+//! An in-memory stand-in for the Event Store Cloud API and its identity
+//! provider. It serves every endpoint the generated client calls, keeps the
+//! resources created through it in memory and moves them through the same
+//! states the real API does (a new cluster is provisioning before it becomes
+//! available, for instance), so code built on esc-api, or the esc binary
+//! itself, can be tested end to end without the cloud.
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+mod access;
+mod audit;
+mod identity;
+mod infra;
+mod integrate;
+mod mesdb;
+mod orchestrate;
+mod reply;
+mod resources;
+mod server;
+mod state;
+
+pub use identity::{TEST_PRIVATE_KEY, TEST_PUBLIC_KEY};
+pub use server::{MockConfig, MockServer};
@@ -0,0 +1,63 @@
+use esc_mock::{MockConfig, MockServer};
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "esc-mock",
+    about = "Serves an in-memory Event Store Cloud API for testing"
+)]
+struct Opt {
+    #[structopt(long, default_value = "8080", help = "Port to listen on")]
+    port: u16,
+
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Milliseconds a resource stays in each intermediate state, such as provisioning"
+    )]
+    step_ms: u64,
+
+    #[structopt(
+        long,
+        default_value = "test@example.com",
+        help = "User the printed refresh token is issued to"
+    )]
+    user: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let opt = Opt::from_args();
+    let config = MockConfig {
+        step: Duration::from_millis(opt.step_ms),
+        ..MockConfig::default()
+    };
+    let server = MockServer::bind(([127, 0, 0, 1], opt.port).into(), config)?;
+    let token_config = server.token_config();
+
+    println!("Listening on {}", server.url());
+    println!();
+    println!("Add a profile for it to ~/.esc/settings.toml:");
+    println!();
+    println!("[[profile]]");
+    println!("name = \"mock\"");
+    println!("api-base-url = \"{}\"", server.url());
+    println!();
+    println!("[profile.token-config]");
+    println!("identity-url = \"{}\"", token_config.identity_url);
+    println!("audience = \"{}\"", token_config.audience);
+    println!("public-key = \"\"\"");
+    print!("{}", token_config.public_key);
+    println!("\"\"\"");
+    println!();
+    println!(
+        "Refresh token for {}: {}",
+        opt.user,
+        server.refresh_token(&opt.user)
+    );
+
+    std::future::pending::<()>().await;
+    Ok(())
+}
@@ -0,0 +1,533 @@
+use esc_client_generated::infra::NetworkStatus;
+use esc_client_generated::mesdb::*;
+use esc_client_generated::resources::{OrganizationId, ProjectId};
+use hyper::Method;
+
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{observe, observe_all, Request, State, Step, Tracked};
+
+/// Server versions the mock offers, as (version, tag, long term support).
+const VERSIONS: &[(&str, &str, bool)] = &[
+    ("20.10", "20.10.5", true),
+    ("21.10", "21.10.8", true),
+    ("21.10", "21.10.9", true),
+    ("22.10", "22.10.2", true),
+    ("22.10", "22.10.3", true),
+    ("23.6", "23.6.0", false),
+];
+
+const RECOMMENDED_VERSION: &str = "22.10";
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", _, "clusters", "versions"]) => {
+            json(&ListClusterCreateVersionsResponse {
+                versions: create_versions(),
+            })
+        }
+
+        (&Method::GET, ["organizations", _, "projects", project, "clusters"]) => {
+            json(&ListClustersResponse {
+                clusters: observe_all(&mut state.clusters, state.step, |c| {
+                    c.project_id.0 == *project
+                }),
+            })
+        }
+        (&Method::POST, ["organizations", org, "projects", project, "clusters"]) => {
+            create_cluster(state, req, org, project)
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "clusters", id]) => {
+            json(&GetClusterResponse {
+                cluster: get_cluster(state, project, id)?,
+            })
+        }
+        (&Method::PUT, ["organizations", _, "projects", project, "clusters", id]) => {
+            update_cluster(state, req, project, id)
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "clusters", id]) => {
+            let cluster = cluster_mut(state, project, id)?;
+            if cluster.resource.protected {
+                return Err(Problem::conflict(
+                    "The cluster is protected from deletion, update it first",
+                ));
+            }
+            if matches!(
+                cluster.resource.status,
+                ClusterStatus::DeletingInstances
+                    | ClusterStatus::DeletingDisks
+                    | ClusterStatus::Deleted
+            ) {
+                return Err(Problem::conflict("The cluster is already being deleted"));
+            }
+            cluster.start(
+                ClusterStatus::DeletingInstances,
+                vec![
+                    Step::Status(ClusterStatus::DeletingDisks),
+                    Step::Status(ClusterStatus::Deleted),
+                    Step::Remove,
+                ],
+            );
+            empty()
+        }
+        (
+            &Method::PUT,
+            ["organizations", _, "projects", project, "clusters", id, "disk", "expand"],
+        ) => {
+            let body: ExpandClusterDiskRequest = req.json()?;
+            let cluster = available_cluster(state, project, id, ClusterStatus::Available)?;
+            if body.disk_size_gb <= cluster.resource.disk_size_gb {
+                return Err(Problem::invalid_field(
+                    "diskSizeGb",
+                    "must be larger than the current disk size",
+                ));
+            }
+            let mut expanded = cluster.resource.clone();
+            expanded.disk_size_gb = body.disk_size_gb;
+            if let Some(disk_type) = body.disk_type {
+                expanded.disk_type = disk_type;
+            }
+            if body.disk_iops.is_some() {
+                expanded.disk_iops = body.disk_iops;
+            }
+            if body.disk_throughput.is_some() {
+                expanded.disk_throughput = body.disk_throughput;
+            }
+            cluster.queue(
+                expanded,
+                vec![
+                    Step::Status(ClusterStatus::ExpandingDisks),
+                    Step::Status(ClusterStatus::Available),
+                ],
+            );
+            empty()
+        }
+        (
+            &Method::GET,
+            ["organizations", _, "projects", project, "clusters", id, "upgrade", "versions"],
+        ) => {
+            let cluster = cluster_mut(state, project, id)?;
+            json(&ListClusterUpgradeVersionsResponse {
+                versions: upgrade_versions(&cluster.resource.server_version_tag),
+            })
+        }
+        (
+            &Method::PUT,
+            ["organizations", _, "projects", project, "clusters", id, "commands", command],
+        ) => cluster_command(state, req, project, id, command),
+
+        (&Method::GET, ["organizations", _, "projects", project, "backups"]) => {
+            json(&ListBackupsResponse {
+                backups: observe_all(&mut state.backups, state.step, |b| {
+                    b.project_id.0 == *project
+                }),
+            })
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "backups"]) => {
+            create_backup(state, req, project)
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "backups", id]) => {
+            json(&GetBackupResponse {
+                backup: get_backup(state, project, id)?,
+            })
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "backups", id]) => {
+            delete_backup(state, project, id)?;
+            empty()
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+fn create_cluster(state: &mut State, req: &Request, org: &str, project: &str) -> Reply {
+    let body: CreateClusterRequest = req.json()?;
+    if body.instance_type.is_empty() {
+        return Err(Problem::invalid_field("instanceType", "is required"));
+    }
+    if body.disk_size_gb < 8 {
+        return Err(Problem::invalid_field("diskSizeGb", "must be at least 8"));
+    }
+    let network = match state.networks.get(body.network_id.0.as_str()) {
+        Some(network) if network.resource.project_id.0 == project => network.resource.clone(),
+        _ => return Err(Problem::invalid_field("networkId", "network not found")),
+    };
+    if network.status != NetworkStatus::Available {
+        return Err(Problem::conflict(&format!(
+            "The network is {:?}, not available",
+            network.status
+        )));
+    }
+    if let Some(acl_id) = &body.acl_id {
+        check_acl(state, project, acl_id)?;
+    }
+    if let Some(backup_id) = &body.source_backup_id {
+        let backup_project = body.source_backup_project_id.as_deref().unwrap_or(project);
+        match state.backups.get(backup_id.as_str()) {
+            Some(backup) if backup.resource.project_id.0 == backup_project => {
+                if backup.resource.status != BackupStatus::Available {
+                    return Err(Problem::conflict(&format!(
+                        "The backup is {}, not available",
+                        backup.resource.status
+                    )));
+                }
+            }
+            _ => return Err(Problem::invalid_field("sourceBackupId", "backup not found")),
+        }
+    }
+    let (version, tag) = resolve_version(&body.server_version)
+        .ok_or_else(|| Problem::invalid_field("serverVersion", "unknown server version"))?;
+
+    let id = state.new_id("cluster");
+    let cluster = Cluster {
+        acl_id: body.acl_id,
+        can_expand_disk: true,
+        cloud_integrated_authentication: false,
+        created: chrono::Utc::now(),
+        description: body.description,
+        disk_size_gb: body.disk_size_gb,
+        disk_type: body.disk_type,
+        disk_iops: body.disk_iops,
+        disk_throughput: body.disk_throughput,
+        id: ClusterId(id.clone()),
+        instance_type: body.instance_type,
+        health: Health::Down,
+        network_id: body.network_id,
+        organization_id: OrganizationId(org.to_string()),
+        patch_available: patch_available(tag),
+        project_id: ProjectId(project.to_string()),
+        projection_level: body.projection_level,
+        protected: body.protected.unwrap_or(false),
+        provider: network.provider,
+        public_access: body.public_access.or(Some(network.public_access)),
+        region: network.region,
+        server_version: version.to_string(),
+        server_version_tag: tag.to_string(),
+        status: ClusterStatus::Provisioning,
+        topology: body.topology,
+    };
+    state.clusters.insert(
+        id.clone(),
+        Tracked::new(cluster, vec![Step::Status(ClusterStatus::Available)]),
+    );
+    json(&CreateClusterResponse { id: ClusterId(id) })
+}
+
+// ACLs belong to a project, and only its clusters can use them.
+fn check_acl(state: &State, project: &str, acl_id: &str) -> Result<(), Problem> {
+    match state.acls.get(acl_id) {
+        Some(acl) if acl.project_id.0 == project => Ok(()),
+        _ => Err(Problem::invalid_field("aclId", "acl not found")),
+    }
+}
+
+fn update_cluster(state: &mut State, req: &Request, project: &str, id: &str) -> Reply {
+    let body: UpdateClusterRequest = req.json()?;
+    if let Some(acl_id) = &body.acl_id {
+        check_acl(state, project, acl_id)?;
+    }
+    let acl_changed =
+        body.acl_id.is_some() && body.acl_id != cluster_mut(state, project, id)?.resource.acl_id;
+    // Changing the ACL reconfigures the nodes, so the cluster must be idle.
+    let cluster = if acl_changed {
+        available_cluster(state, project, id, ClusterStatus::Available)?
+    } else {
+        cluster_mut(state, project, id)?
+    };
+    if let Some(description) = body.description {
+        cluster.resource.description = description;
+    }
+    if let Some(protected) = body.protected {
+        cluster.resource.protected = protected;
+    }
+    if acl_changed {
+        cluster.resource.acl_id = body.acl_id;
+        cluster.start(
+            ClusterStatus::UpdatingConfiguration,
+            vec![Step::Status(ClusterStatus::Available)],
+        );
+    }
+    empty()
+}
+
+fn cluster_command(
+    state: &mut State,
+    req: &Request,
+    project: &str,
+    id: &str,
+    command: &str,
+) -> Reply {
+    match command {
+        "resize" => {
+            let body: ResizeClusterRequest = req.json()?;
+            if body.target_size.is_empty() {
+                return Err(Problem::invalid_field("targetSize", "is required"));
+            }
+            let cluster = available_cluster(state, project, id, ClusterStatus::Available)?;
+            let mut resized = cluster.resource.clone();
+            resized.instance_type = body.target_size;
+            cluster.queue(
+                resized,
+                vec![
+                    Step::Status(ClusterStatus::Resizing),
+                    Step::Status(ClusterStatus::Available),
+                ],
+            );
+            json(&ResizeClusterResponse { id: id.to_string() })
+        }
+        "restart" => {
+            let cluster = available_cluster(state, project, id, ClusterStatus::Available)?;
+            cluster.start(
+                ClusterStatus::Restarting,
+                vec![Step::Status(ClusterStatus::Available)],
+            );
+            json(&RestartClusterResponse { id: id.to_string() })
+        }
+        // A stopped cluster has its instances removed but keeps its disks.
+        "stop" => {
+            let cluster = available_cluster(state, project, id, ClusterStatus::Available)?;
+            cluster.start(
+                ClusterStatus::Stopping,
+                vec![Step::Status(ClusterStatus::DisksAvailable)],
+            );
+            json(&StopClusterResponse { id: id.to_string() })
+        }
+        "start" => {
+            let cluster = available_cluster(state, project, id, ClusterStatus::DisksAvailable)?;
+            cluster.start(
+                ClusterStatus::ComputeAvailable,
+                vec![
+                    Step::Status(ClusterStatus::Installing),
+                    Step::Status(ClusterStatus::Available),
+                ],
+            );
+            json(&StartClusterResponse { id: id.to_string() })
+        }
+        "upgrade" => {
+            let body: UpgradeClusterRequest = req.json()?;
+            let (version, tag) = VERSIONS
+                .iter()
+                .find(|(_, tag, _)| *tag == body.target_tag)
+                .map(|(version, tag, _)| (*version, *tag))
+                .ok_or_else(|| Problem::invalid_field("targetTag", "unknown server version"))?;
+            let cluster = available_cluster(state, project, id, ClusterStatus::Available)?;
+            if cluster.resource.server_version_tag == tag {
+                return Err(Problem::invalid_field(
+                    "targetTag",
+                    "the cluster already runs this version",
+                ));
+            }
+            let mut upgraded = cluster.resource.clone();
+            upgraded.server_version = version.to_string();
+            upgraded.server_version_tag = tag.to_string();
+            upgraded.patch_available = patch_available(tag);
+            cluster.queue(
+                upgraded,
+                vec![
+                    Step::Status(ClusterStatus::Upgrading),
+                    Step::Status(ClusterStatus::Available),
+                ],
+            );
+            json(&UpgradeClusterResponse { id: id.to_string() })
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+fn create_backup(state: &mut State, req: &Request, project: &str) -> Reply {
+    let body: CreateBackupRequest = req.json()?;
+    let id = backup_cluster(
+        state,
+        project,
+        &body.source_cluster_id.0,
+        body.description,
+        None,
+    )?;
+    json(&CreateBackupResponse { id })
+}
+
+/// Starts backing up a cluster. Also used by scheduled backup jobs, which
+/// link the backups they make to themselves.
+pub(crate) fn backup_cluster(
+    state: &mut State,
+    project: &str,
+    cluster_id: &str,
+    description: String,
+    linked_resource: Option<String>,
+) -> Result<BackupId, Problem> {
+    let cluster = match state.clusters.get(cluster_id) {
+        Some(cluster) if cluster.resource.project_id.0 == project => cluster.resource.clone(),
+        _ => {
+            return Err(Problem::invalid_field(
+                "sourceClusterId",
+                "cluster not found",
+            ))
+        }
+    };
+    if cluster.status != ClusterStatus::Available {
+        return Err(Problem::conflict(&format!(
+            "The cluster is {}, not available",
+            cluster.status
+        )));
+    }
+    let id = state.new_id("backup");
+    let backup = Backup {
+        available_node_count: match cluster.topology {
+            Topology::SingleNode => 1,
+            Topology::ThreeNodeMultiZone => 3,
+        },
+        created: chrono::Utc::now(),
+        description,
+        linked_resource,
+        id: BackupId(id.clone()),
+        project_id: ProjectId(project.to_string()),
+        provider: cluster.provider,
+        region: cluster.region,
+        server_version: cluster.server_version,
+        server_version_tag: cluster.server_version_tag,
+        size_gb: cluster.disk_size_gb,
+        source_cluster_id: cluster.id,
+        source_cluster_description: cluster.description,
+        status: BackupStatus::Creating,
+    };
+    state.backups.insert(
+        id.clone(),
+        Tracked::new(backup, vec![Step::Status(BackupStatus::Available)]),
+    );
+    Ok(BackupId(id))
+}
+
+/// Deletes a backup, unless something is happening to it already.
+pub(crate) fn delete_backup(state: &mut State, project: &str, id: &str) -> Result<(), Problem> {
+    let backup = state
+        .backups
+        .get_mut(id)
+        .filter(|b| b.resource.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("backup", id))?;
+    if !matches!(
+        backup.resource.status,
+        BackupStatus::Available | BackupStatus::Defunct
+    ) {
+        return Err(Problem::conflict(&format!(
+            "The backup is {}",
+            backup.resource.status
+        )));
+    }
+    backup.start(
+        BackupStatus::Deleting,
+        vec![Step::Status(BackupStatus::Deleted), Step::Remove],
+    );
+    Ok(())
+}
+
+fn get_cluster(state: &mut State, project: &str, id: &str) -> Result<Cluster, Problem> {
+    observe(&mut state.clusters, id, state.step)
+        .filter(|c| c.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("cluster", id))
+}
+
+fn get_backup(state: &mut State, project: &str, id: &str) -> Result<Backup, Problem> {
+    observe(&mut state.backups, id, state.step)
+        .filter(|b| b.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("backup", id))
+}
+
+fn cluster_mut<'a>(
+    state: &'a mut State,
+    project: &str,
+    id: &str,
+) -> Result<&'a mut Tracked<Cluster>, Problem> {
+    state
+        .clusters
+        .get_mut(id)
+        .filter(|c| c.resource.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("cluster", id))
+}
+
+/// Gets a cluster which must be in the given status, with no operation in
+/// progress, for a command to be accepted.
+fn available_cluster<'a>(
+    state: &'a mut State,
+    project: &str,
+    id: &str,
+    status: ClusterStatus,
+) -> Result<&'a mut Tracked<Cluster>, Problem> {
+    let cluster = cluster_mut(state, project, id)?;
+    if cluster.resource.status != status || !cluster.is_settled() {
+        return Err(Problem::conflict(&format!(
+            "The cluster is {}, it must be {} for this operation",
+            cluster.resource.status, status
+        )));
+    }
+    Ok(cluster)
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version.split('.').filter_map(|p| p.parse().ok()).collect()
+}
+
+/// Accepts either a version, which resolves to its latest tag, or a tag.
+fn resolve_version(requested: &str) -> Option<(&'static str, &'static str)> {
+    if let Some((version, tag, _)) = VERSIONS.iter().find(|(_, tag, _)| *tag == requested) {
+        return Some((version, tag));
+    }
+    VERSIONS
+        .iter()
+        .filter(|(version, _, _)| *version == requested)
+        .max_by_key(|(_, tag, _)| parse_version(tag))
+        .map(|(version, tag, _)| (*version, *tag))
+}
+
+fn patch_available(tag: &str) -> bool {
+    let current = parse_version(tag);
+    VERSIONS.iter().any(|(_, other, _)| {
+        let other = parse_version(other);
+        other[..2] == current[..2] && other > current
+    })
+}
+
+fn create_versions() -> Vec<ClusterCreateVersion> {
+    let mut versions: Vec<ClusterCreateVersion> = Vec::new();
+    for (version, tag, lts) in VERSIONS {
+        let (_, latest) = resolve_version(version).expect("version is listed");
+        if *tag == latest {
+            versions.push(ClusterCreateVersion {
+                lts: *lts,
+                recommended: *version == RECOMMENDED_VERSION,
+                tag: tag.to_string(),
+                version: version.to_string(),
+            });
+        }
+    }
+    versions
+}
+
+fn upgrade_versions(current_tag: &str) -> Vec<ClusterUpgradeVersion> {
+    let current = parse_version(current_tag);
+    VERSIONS
+        .iter()
+        .filter(|(_, tag, _)| *tag != current_tag)
+        .map(|(version, tag, lts)| {
+            let target = parse_version(tag);
+            let (change, warnings) = if target < current {
+                (
+                    UpgradeChangeType::Downgrade,
+                    vec!["Downgrading may leave the database unable to read its data".to_string()],
+                )
+            } else if target[..2] != current[..2] {
+                (
+                    UpgradeChangeType::Major,
+                    vec!["Major upgrades can't be rolled back".to_string()],
+                )
+            } else {
+                (UpgradeChangeType::Patch, vec![])
+            };
+            ClusterUpgradeVersion {
+                change,
+                lts: *lts,
+                recommended: *version == RECOMMENDED_VERSION,
+                tag: tag.to_string(),
+                version: version.to_string(),
+                warnings,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+use esc_client_generated::orchestrate::*;
+use esc_client_generated::resources::{OrganizationId, ProjectId};
+use hyper::Method;
+
+use crate::mesdb;
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{Request, State};
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations", _, "projects", project, "jobs"]) => {
+            json(&ListJobsResponse {
+                jobs: state
+                    .jobs
+                    .values()
+                    .filter(|j| j.project_id.0 == *project)
+                    .cloned()
+                    .collect(),
+            })
+        }
+        (&Method::POST, ["organizations", org, "projects", project, "jobs"]) => {
+            let body: CreateJobRequest = req.json()?;
+            validate_job_data(state, project, &body.data)?;
+            if body.schedule.split_whitespace().count() != 5 {
+                return Err(Problem::invalid_field(
+                    "schedule",
+                    "must be a cron expression with five fields",
+                ));
+            }
+            let id = state.new_id("job");
+            state.jobs.insert(
+                id.clone(),
+                Job {
+                    data: body.data,
+                    description: body.description,
+                    id: JobId(id.clone()),
+                    organization_id: OrganizationId(org.to_string()),
+                    project_id: ProjectId(project.to_string()),
+                    schedule: body.schedule,
+                    status: "Active".to_string(),
+                },
+            );
+            json(&CreateJobResponse { id: JobId(id) })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "jobs", id]) => {
+            json(&GetJobResponse {
+                job: job(state, project, id)?.clone(),
+            })
+        }
+        (&Method::DELETE, ["organizations", _, "projects", project, "jobs", id]) => {
+            job(state, project, id)?;
+            state.jobs.remove(*id);
+            empty()
+        }
+        (&Method::POST, ["organizations", _, "projects", project, "jobs", id, "run"]) => {
+            let job = job(state, project, id)?.clone();
+            let history_id = run_job(state, &job);
+            json(&RunJobResponse {
+                history_id: Some(history_id),
+            })
+        }
+        (&Method::GET, ["organizations", _, "projects", project, "history"]) => {
+            let job_id = req.query.get("jobId");
+            json(&GetHistoryResponse {
+                items: state
+                    .history
+                    .iter()
+                    .filter(|h| h.project_id.0 == *project)
+                    .filter(|h| job_id.map_or(true, |job_id| h.job_id.0 == *job_id))
+                    .cloned()
+                    .collect(),
+            })
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+fn validate_job_data(state: &State, project: &str, data: &JobData) -> Result<(), Problem> {
+    match data {
+        JobData::ScheduledBackup(data) => {
+            match state.clusters.get(data.cluster_id.0.as_str()) {
+                Some(cluster) if cluster.resource.project_id.0 == project => {}
+                _ => return Err(Problem::invalid_field("clusterId", "cluster not found")),
+            }
+            if data.max_backup_count < 1 {
+                return Err(Problem::invalid_field(
+                    "maxBackupCount",
+                    "must be at least 1",
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Runs a job right away, recording the outcome in the history.
+fn run_job(state: &mut State, job: &Job) -> String {
+    let start_time = chrono::Utc::now();
+    let project = job.project_id.0.as_str();
+    let (status, details, linked_resource) = match &job.data {
+        JobData::ScheduledBackup(data) => {
+            let result = mesdb::backup_cluster(
+                state,
+                project,
+                &data.cluster_id.0,
+                data.description.clone(),
+                Some(job.id.0.clone()),
+            );
+            match result {
+                Ok(backup_id) => {
+                    prune_backups(state, project, job, data.max_backup_count);
+                    (
+                        "Completed",
+                        format!("Created backup {}", backup_id),
+                        backup_id.0,
+                    )
+                }
+                Err(_) => (
+                    "Failed",
+                    format!("Could not back up cluster {}", data.cluster_id),
+                    data.cluster_id.0.clone(),
+                ),
+            }
+        }
+    };
+    let id = state.new_id("history");
+    state.history.push(HistoryItem {
+        details,
+        end_time: Some(chrono::Utc::now()),
+        job_id: job.id.clone(),
+        id: id.clone(),
+        linked_resource,
+        organization_id: job.organization_id.clone(),
+        project_id: job.project_id.clone(),
+        start_time,
+        status: status.to_string(),
+    });
+    id
+}
+
+// Scheduled backups only keep the given number of backups around, the
+// oldest ones beyond that are deleted.
+fn prune_backups(state: &mut State, project: &str, job: &Job, max_backup_count: i32) {
+    let mut made_by_job: Vec<String> = state
+        .backups
+        .values()
+        .filter(|b| b.resource.linked_resource.as_deref() == Some(job.id.0.as_str()))
+        .filter(|b| b.resource.status != esc_client_generated::mesdb::BackupStatus::Deleting)
+        .map(|b| b.resource.id.0.clone())
+        .collect();
+    made_by_job.sort();
+    let excess = made_by_job.len().saturating_sub(max_backup_count as usize);
+    for id in made_by_job.into_iter().take(excess) {
+        if let Err(err) = mesdb::delete_backup(state, project, &id) {
+            debug!("Could not prune backup {}: {}", id, err.detail);
+        }
+    }
+}
+
+fn job<'a>(state: &'a State, project: &str, id: &str) -> Result<&'a Job, Problem> {
+    state
+        .jobs
+        .get(id)
+        .filter(|j| j.project_id.0 == project)
+        .ok_or_else(|| Problem::not_found("job", id))
+}
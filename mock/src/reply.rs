@@ -0,0 +1,91 @@
+use esc_client_base::errors::ProblemDetails;
+use hyper::{Body, Response, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub(crate) type Reply = Result<Response<Body>, Problem>;
+
+/// An error response, sent back as problem details like the real API does.
+pub(crate) struct Problem {
+    status: StatusCode,
+    pub(crate) detail: String,
+    fields: Option<HashMap<String, String>>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, detail: &str) -> Self {
+        Self {
+            status,
+            detail: detail.to_string(),
+            fields: None,
+        }
+    }
+
+    pub fn not_found(what: &str, id: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, &format!("{} {} not found", what, id))
+    }
+
+    pub fn conflict(detail: &str) -> Self {
+        Self::new(StatusCode::CONFLICT, detail)
+    }
+
+    pub fn unauthorized(detail: &str) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, detail)
+    }
+
+    pub fn bad_request(detail: &str) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, detail)
+    }
+
+    /// A validation failure pinned on one field of the request.
+    pub fn invalid_field(field: &str, message: &str) -> Self {
+        let mut fields = HashMap::new();
+        fields.insert(field.to_string(), message.to_string());
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            detail: "The request failed validation".to_string(),
+            fields: Some(fields),
+        }
+    }
+
+    pub fn no_route() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "no such endpoint")
+    }
+
+    pub fn into_response(self, instance: &str) -> Response<Body> {
+        let problem_details = ProblemDetails {
+            detail: Some(self.detail),
+            fields: self.fields,
+            instance: instance.to_string(),
+            status: self.status.as_u16() as i32,
+            title: self
+                .status
+                .canonical_reason()
+                .unwrap_or("Unknown status")
+                .to_string(),
+            _type: "about:blank".to_string(),
+        };
+        let body = serde_json::to_string(&problem_details).expect("problem details serialize");
+        Response::builder()
+            .status(self.status)
+            .header("Content-Type", "application/problem+json")
+            .body(Body::from(body))
+            .expect("response is valid")
+    }
+}
+
+pub(crate) fn json<T: Serialize>(body: &T) -> Reply {
+    let body = serde_json::to_string(body).expect("response body serializes");
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .expect("response is valid"))
+}
+
+pub(crate) fn empty() -> Reply {
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::empty())
+        .expect("response is valid"))
+}
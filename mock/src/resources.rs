@@ -0,0 +1,140 @@
+use esc_client_generated::access::{Member, MemberId};
+use esc_client_generated::resources::*;
+use hyper::Method;
+
+use crate::reply::{empty, json, Problem, Reply};
+use crate::state::{Request, State};
+
+pub(crate) fn route(state: &mut State, req: &Request, path: &[&str]) -> Reply {
+    match (&req.method, path) {
+        (&Method::GET, ["organizations"]) => json(&ListOrganizationsResponse {
+            organizations: state.organizations.values().cloned().collect(),
+        }),
+        (&Method::POST, ["organizations"]) => create_organization(state, req),
+        (&Method::GET, ["organizations", org]) => json(&GetOrganizationResponse {
+            organization: organization(state, org)?.clone(),
+        }),
+        (&Method::PUT, ["organizations", org]) => {
+            let body: UpdateOrganizationRequest = req.json()?;
+            organization_mut(state, org)?.name = body.name;
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org]) => {
+            organization(state, org)?;
+            if state.projects.values().any(|p| p.organization_id.0 == *org) {
+                return Err(Problem::conflict(
+                    "The organization still has projects, delete them first",
+                ));
+            }
+            state.organizations.remove(*org);
+            empty()
+        }
+        (&Method::GET, ["organizations", org, "mfa"]) => {
+            organization(state, org)?;
+            json(&MfaStatus {
+                mfa_enabled: state.mfa.get(*org).copied().unwrap_or(false),
+            })
+        }
+        (&Method::POST, ["organizations", org, "mfa"]) => {
+            organization(state, org)?;
+            let body: MfaStatus = req.json()?;
+            state.mfa.insert(org.to_string(), body.mfa_enabled);
+            json(&UpdateMfaResponse {
+                mfa_enabled: body.mfa_enabled,
+            })
+        }
+        (&Method::GET, ["organizations", org, "projects"]) => json(&ListProjectsResponse {
+            projects: state
+                .projects
+                .values()
+                .filter(|p| p.organization_id.0 == *org)
+                .cloned()
+                .collect(),
+        }),
+        (&Method::POST, ["organizations", org, "projects"]) => {
+            organization(state, org)?;
+            let body: CreateProjectRequest = req.json()?;
+            let id = state.new_id("project");
+            state.projects.insert(
+                id.clone(),
+                Project {
+                    created: chrono::Utc::now(),
+                    id: ProjectId(id.clone()),
+                    name: body.name,
+                    organization_id: OrganizationId(org.to_string()),
+                },
+            );
+            json(&CreateProjectResponse { id: ProjectId(id) })
+        }
+        (&Method::GET, ["organizations", org, "projects", project]) => json(&GetProjectResponse {
+            project: project_mut(state, org, project)?.clone(),
+        }),
+        (&Method::PUT, ["organizations", org, "projects", project]) => {
+            let body: UpdateProjectRequest = req.json()?;
+            project_mut(state, org, project)?.name = body.name;
+            empty()
+        }
+        (&Method::DELETE, ["organizations", org, "projects", project]) => {
+            project_mut(state, org, project)?;
+            state.projects.remove(*project);
+            empty()
+        }
+        _ => Err(Problem::no_route()),
+    }
+}
+
+fn create_organization(state: &mut State, req: &Request) -> Reply {
+    let body: CreateOrganizationRequest = req.json()?;
+    let id = state.new_id("org");
+    state.organizations.insert(
+        id.clone(),
+        Organization {
+            created: chrono::Utc::now(),
+            id: OrganizationId(id.clone()),
+            name: body.name,
+            provisioning_enabled: true,
+        },
+    );
+    // Whoever creates an organization becomes its first member.
+    let member_id = state.new_id("member");
+    state.members.insert(
+        member_id.clone(),
+        Member {
+            active: true,
+            created: chrono::Utc::now().to_rfc3339(),
+            email: req.subject.clone(),
+            id: MemberId(member_id),
+            name: req.subject.clone(),
+            organization_id: OrganizationId(id.clone()),
+        },
+    );
+    json(&CreateOrganizationResponse {
+        id: OrganizationId(id),
+    })
+}
+
+fn organization<'a>(state: &'a State, org: &str) -> Result<&'a Organization, Problem> {
+    state
+        .organizations
+        .get(org)
+        .ok_or_else(|| Problem::not_found("organization", org))
+}
+
+fn organization_mut<'a>(state: &'a mut State, org: &str) -> Result<&'a mut Organization, Problem> {
+    state
+        .organizations
+        .get_mut(org)
+        .ok_or_else(|| Problem::not_found("organization", org))
+}
+
+fn project_mut<'a>(
+    state: &'a mut State,
+    org: &str,
+    project: &str,
+) -> Result<&'a mut Project, Problem> {
+    state
+        .projects
+        .get_mut(project)
+        .filter(|p| p.organization_id.0 == org)
+        .ok_or_else(|| Problem::not_found("project", project))
+}
@@ -0,0 +1,222 @@
+use esc_client_base::identity::TokenConfig;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Response};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::identity::{Issuer, TEST_PUBLIC_KEY};
+use crate::reply::{Problem, Reply};
+use crate::state::{Request, State};
+use crate::{access, audit, identity, infra, integrate, mesdb, orchestrate, resources};
+
+#[derive(Clone, Debug)]
+pub struct MockConfig {
+    /// How long a resource stays in each intermediate state, such as a
+    /// cluster provisioning. With the default of zero, a resource moves on
+    /// to its next state the second time it's read.
+    pub step: Duration,
+    /// How long the access tokens the mock issues are valid.
+    pub access_token_lifetime: Duration,
+    /// Email addresses and passwords accepted by the password grant. When
+    /// not set, any password is accepted.
+    pub users: Option<HashMap<String, String>>,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            step: Duration::ZERO,
+            access_token_lifetime: Duration::from_secs(3600),
+            users: None,
+        }
+    }
+}
+
+struct Context {
+    config: MockConfig,
+    issuer: Issuer,
+    state: Mutex<State>,
+}
+
+/// A running mock server. It stops when dropped.
+pub struct MockServer {
+    url: String,
+    context: Arc<Context>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a free port of the loopback interface. Must be
+    /// called from within a Tokio runtime.
+    pub fn start() -> std::io::Result<Self> {
+        Self::start_with(MockConfig::default())
+    }
+
+    pub fn start_with(config: MockConfig) -> std::io::Result<Self> {
+        Self::bind(([127, 0, 0, 1], 0).into(), config)
+    }
+
+    pub fn bind(addr: SocketAddr, config: MockConfig) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let issuer = Issuer::new(&url, &url, config.access_token_lifetime)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        let context = Arc::new(Context {
+            state: Mutex::new(State::new(config.step)),
+            config,
+            issuer,
+        });
+
+        let service_context = context.clone();
+        let make_service = make_service_fn(move |_| {
+            let context = service_context.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(context.clone(), req))) }
+        });
+        let server = hyper::Server::from_tcp(listener)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
+            .serve(make_service);
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = server.with_graceful_shutdown(async {
+            stopped.await.ok();
+        });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                error!("Mock server failed: {}", err);
+            }
+        });
+        info!("Mock server listening on {}", url);
+
+        Ok(Self {
+            url,
+            context,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Base URL of the server, to be used as the API base URL as well as
+    /// the identity URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Token config which makes clients get their tokens from the mock.
+    pub fn token_config(&self) -> TokenConfig {
+        TokenConfig {
+            audience: self.url.clone(),
+            identity_url: self.url.clone(),
+            public_key: TEST_PUBLIC_KEY.to_string(),
+            ..TokenConfig::default()
+        }
+    }
+
+    /// Issues an access token for the given user, as if they had logged in.
+    pub fn access_token(&self, subject: &str) -> String {
+        let mut state = self.context.state.lock().expect("state lock was poisoned");
+        self.context.issuer.access_token(&mut state, subject)
+    }
+
+    /// Issues a refresh token for the given user, as if they had logged in.
+    pub fn refresh_token(&self, subject: &str) -> String {
+        let client_id = TokenConfig::default().client_id;
+        let mut state = self.context.state.lock().expect("state lock was poisoned");
+        self.context
+            .issuer
+            .refresh_token(&mut state, &client_id, subject)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(
+    context: Arc<Context>,
+    req: hyper::Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = req.into_parts();
+    let path = parts.uri.path().to_string();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body.to_vec(),
+        Err(err) => {
+            return Ok(
+                Problem::bad_request(&format!("Could not read body: {}", err)).into_response(&path),
+            )
+        }
+    };
+    debug!("{} {}", parts.method, parts.uri);
+
+    let mut state = context.state.lock().expect("state lock was poisoned");
+    if parts.method == Method::POST && path == "/oauth/token" {
+        return Ok(identity::token(
+            &context.issuer,
+            &context.config,
+            &mut state,
+            &body,
+        ));
+    }
+    if parts.method == Method::POST && path == "/oauth/revoke" {
+        return Ok(identity::revoke(&mut state, &body));
+    }
+
+    let reply = authenticate(&context, &parts.headers).and_then(|subject| {
+        let request = Request {
+            method: parts.method.clone(),
+            query: parts
+                .uri
+                .query()
+                .map(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .into_owned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+            body,
+            subject,
+        };
+        route(&mut state, &request, &path)
+    });
+    Ok(reply.unwrap_or_else(|problem| problem.into_response(&path)))
+}
+
+fn authenticate(context: &Context, headers: &hyper::HeaderMap) -> Result<String, Problem> {
+    let header = headers
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Problem::unauthorized("missing Authorization header"))?;
+    let access_token = header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| Problem::unauthorized("expected a bearer token"))?;
+    context
+        .issuer
+        .verify(access_token)
+        .map_err(|err| Problem::unauthorized(&err))
+}
+
+fn route(state: &mut State, request: &Request, path: &str) -> Reply {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let reply = match segments.as_slice() {
+        ["access", "v1", rest @ ..] => access::route(state, request, rest),
+        ["audit", "v1", rest @ ..] => audit::route(state, request, rest),
+        ["infra", "v1", rest @ ..] => infra::route(state, request, rest),
+        ["integrate", "v1", rest @ ..] => integrate::route(state, request, rest),
+        ["mesdb", "v1", rest @ ..] => mesdb::route(state, request, rest),
+        ["orchestrate", "v1", rest @ ..] => orchestrate::route(state, request, rest),
+        ["resources", "v1", rest @ ..] => resources::route(state, request, rest),
+        _ => Err(Problem::no_route()),
+    };
+    if reply.is_ok() && request.method != Method::GET {
+        audit::record(state, request, &segments);
+    }
+    reply
+}
@@ -0,0 +1,240 @@
+use esc_client_generated::{access, audit, infra, integrate, mesdb, orchestrate, resources};
+use hyper::Method;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::reply::Problem;
+
+/// A resource whose status changes over time, such as a cluster which is
+/// provisioning. It takes one step towards its final state each time it is
+/// read, provided it has been seen in its current state before and it has
+/// been in that state for at least the configured step duration. That way
+/// every intermediate state can be observed, and with no step duration the
+/// transitions are entirely deterministic.
+pub(crate) struct Tracked<T: HasStatus> {
+    pub resource: T,
+    /// The resource as it is once a queued operation is picked up.
+    queued: Option<T>,
+    steps: VecDeque<Step<T::Status>>,
+    observed: bool,
+    changed_at: Instant,
+}
+
+pub(crate) enum Step<S> {
+    Status(S),
+    /// The resource is gone, reading it from now on gives a 404.
+    Remove,
+}
+
+pub(crate) trait HasStatus {
+    type Status;
+    fn set_status(&mut self, status: Self::Status);
+}
+
+impl<T: HasStatus + Clone> Tracked<T> {
+    pub fn new(resource: T, steps: Vec<Step<T::Status>>) -> Self {
+        Self {
+            resource,
+            queued: None,
+            steps: steps.into(),
+            observed: false,
+            changed_at: Instant::now(),
+        }
+    }
+
+    /// Starts a new operation: the status is set right away, and the
+    /// resource then goes through the given steps.
+    pub fn start(&mut self, status: T::Status, steps: Vec<Step<T::Status>>) {
+        self.resource.set_status(status);
+        self.queued = None;
+        self.steps = steps.into();
+        self.observed = false;
+        self.changed_at = Instant::now();
+    }
+
+    /// Starts a new operation which the API takes a while to pick up, like
+    /// a resize: the resource is read as it was until the first step, which
+    /// turns it into `changed`.
+    pub fn queue(&mut self, changed: T, steps: Vec<Step<T::Status>>) {
+        self.queued = Some(changed);
+        self.steps = steps.into();
+        self.observed = false;
+        self.changed_at = Instant::now();
+    }
+
+    /// True when no operation is in progress.
+    pub fn is_settled(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Reads the resource, first moving it along if it's due. Returns None
+    /// once the resource is gone.
+    fn observe(&mut self, step: Duration) -> Option<T> {
+        if self.observed && self.changed_at.elapsed() >= step {
+            match self.steps.pop_front() {
+                Some(Step::Status(status)) => {
+                    if let Some(queued) = self.queued.take() {
+                        self.resource = queued;
+                    }
+                    self.resource.set_status(status);
+                    self.observed = false;
+                    self.changed_at = Instant::now();
+                }
+                Some(Step::Remove) => return None,
+                None => {}
+            }
+        }
+        self.observed = true;
+        Some(self.resource.clone())
+    }
+}
+
+/// Reads a single tracked resource, dropping it if it's gone.
+pub(crate) fn observe<T: HasStatus + Clone>(
+    map: &mut BTreeMap<String, Tracked<T>>,
+    id: &str,
+    step: Duration,
+) -> Option<T> {
+    let resource = map.get_mut(id)?.observe(step);
+    if resource.is_none() {
+        map.remove(id);
+    }
+    resource
+}
+
+/// Reads every tracked resource matching the filter.
+pub(crate) fn observe_all<T: HasStatus + Clone>(
+    map: &mut BTreeMap<String, Tracked<T>>,
+    step: Duration,
+    filter: impl Fn(&T) -> bool,
+) -> Vec<T> {
+    let mut result = Vec::new();
+    let mut gone = Vec::new();
+    for (id, tracked) in map.iter_mut() {
+        if !filter(&tracked.resource) {
+            continue;
+        }
+        match tracked.observe(step) {
+            Some(resource) => result.push(resource),
+            None => gone.push(id.clone()),
+        }
+    }
+    for id in gone {
+        map.remove(&id);
+    }
+    result
+}
+
+impl HasStatus for mesdb::Cluster {
+    type Status = mesdb::ClusterStatus;
+    fn set_status(&mut self, status: Self::Status) {
+        use mesdb::ClusterStatus::*;
+        // The database only answers health checks while its nodes run.
+        self.health = match status {
+            Available
+            | Restarting
+            | Resizing
+            | Upgrading
+            | ExpandingDisks
+            | UpdatingConfiguration => mesdb::Health::Ok,
+            _ => mesdb::Health::Down,
+        };
+        self.status = status;
+    }
+}
+
+impl HasStatus for mesdb::Backup {
+    type Status = mesdb::BackupStatus;
+    fn set_status(&mut self, status: Self::Status) {
+        self.status = status;
+    }
+}
+
+impl HasStatus for infra::Network {
+    type Status = infra::NetworkStatus;
+    fn set_status(&mut self, status: Self::Status) {
+        self.status = status;
+    }
+}
+
+impl HasStatus for infra::Peering {
+    type Status = infra::PeeringStatus;
+    fn set_status(&mut self, status: Self::Status) {
+        self.status = status;
+    }
+}
+
+pub(crate) struct RefreshToken {
+    pub id: String,
+    pub client_id: String,
+    pub subject: String,
+    pub last_used: chrono::DateTime<chrono::Utc>,
+}
+
+/// Everything the mock knows about. Resources of all organizations and
+/// projects live side by side and are filtered on access.
+#[derive(Default)]
+pub(crate) struct State {
+    pub step: Duration,
+    next_id: u64,
+
+    pub organizations: BTreeMap<String, resources::Organization>,
+    pub projects: BTreeMap<String, resources::Project>,
+    pub mfa: HashMap<String, bool>,
+
+    pub networks: BTreeMap<String, Tracked<infra::Network>>,
+    pub peerings: BTreeMap<String, Tracked<infra::Peering>>,
+    pub acls: BTreeMap<String, infra::Acl>,
+
+    pub clusters: BTreeMap<String, Tracked<mesdb::Cluster>>,
+    pub backups: BTreeMap<String, Tracked<mesdb::Backup>>,
+
+    pub jobs: BTreeMap<String, orchestrate::Job>,
+    pub history: Vec<orchestrate::HistoryItem>,
+
+    pub integrations: BTreeMap<String, integrate::Integration>,
+
+    pub groups: BTreeMap<String, access::Group>,
+    pub invites: BTreeMap<String, access::Invite>,
+    pub members: BTreeMap<String, access::Member>,
+    pub policies: BTreeMap<String, access::Policy>,
+    pub settings: HashMap<String, access::Settings>,
+
+    /// Keyed by the refresh token itself.
+    pub refresh_tokens: BTreeMap<String, RefreshToken>,
+
+    pub audit_log: Vec<audit::Log>,
+}
+
+impl State {
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            ..Self::default()
+        }
+    }
+
+    /// Generates an ID. They are sequential, so resources are listed in the
+    /// order they were created.
+    pub fn new_id(&mut self, kind: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{:05}", kind, self.next_id)
+    }
+}
+
+/// An authenticated API request.
+pub(crate) struct Request {
+    pub method: Method,
+    pub query: HashMap<String, String>,
+    pub body: Vec<u8>,
+    /// Who the access token was issued to.
+    pub subject: String,
+}
+
+impl Request {
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Problem> {
+        serde_json::from_slice(&self.body)
+            .map_err(|err| Problem::bad_request(&format!("Invalid request body: {}", err)))
+    }
+}
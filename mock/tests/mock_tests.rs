@@ -0,0 +1,280 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use esc_api::mesdb::{ClusterStatus, CreateClusterRequest, ProjectionLevel, Topology};
+use esc_api::resources::{CreateOrganizationRequest, CreateProjectRequest};
+use esc_api::{infra, mesdb, resources};
+use esc_api::{
+    Client, OrgId, ProjectId, RefreshTokenAuthorizer, RequestSender, Transport, WaitError,
+    WaitOptions,
+};
+use esc_client_base::identity::operations;
+use esc_mock::MockServer;
+
+async fn client(server: &MockServer) -> Client {
+    let transport: Arc<dyn Transport + Send + Sync> = Arc::new(reqwest::Client::new());
+    let config = server.token_config();
+    let token = operations::create(
+        transport.as_ref(),
+        &config,
+        "test@example.com",
+        "password",
+        None,
+    )
+    .await
+    .unwrap();
+    let refresh_token = token.refresh_token().cloned().unwrap();
+    Client {
+        authorization: Arc::new(RefreshTokenAuthorizer::new(
+            transport.clone(),
+            config,
+            refresh_token,
+            token,
+        )),
+        base_url: server.url().to_string(),
+        sender: RequestSender::new(transport, None),
+    }
+}
+
+async fn project(client: &Client) -> (OrgId, ProjectId) {
+    let org_id = resources::create_organization(
+        client,
+        CreateOrganizationRequest {
+            name: "org".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let project_id = resources::create_project(
+        client,
+        org_id.clone(),
+        CreateProjectRequest {
+            name: "project".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    (org_id, project_id)
+}
+
+#[tokio::test]
+async fn test_cluster_goes_through_its_lifecycle() {
+    let server = MockServer::start().unwrap();
+    let client = client(&server).await;
+    let (org_id, project_id) = project(&client).await;
+
+    let network_id = infra::create_network(
+        &client,
+        org_id.clone(),
+        project_id.clone(),
+        infra::CreateNetworkRequest {
+            cidr_block: Some("172.21.0.0/16".to_string()),
+            description: "network".to_string(),
+            provider: "aws".to_string(),
+            public_access: false,
+            region: "us-west-2".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let network = |client: Client| {
+        let (org_id, project_id, network_id) =
+            (org_id.clone(), project_id.clone(), network_id.clone());
+        async move {
+            infra::get_network(&client, org_id, project_id, network_id)
+                .await
+                .unwrap()
+                .network
+                .status
+        }
+    };
+    assert_eq!(
+        infra::NetworkStatus::Provisioning,
+        network(client.clone()).await
+    );
+    assert_eq!(
+        infra::NetworkStatus::Available,
+        network(client.clone()).await
+    );
+
+    let cluster_id = mesdb::create_cluster(
+        &client,
+        org_id.clone(),
+        project_id.clone(),
+        CreateClusterRequest {
+            acl_id: None,
+            description: "cluster".to_string(),
+            disk_size_gb: 16,
+            disk_type: "gp3".to_string(),
+            disk_iops: None,
+            disk_throughput: None,
+            instance_type: "F1".to_string(),
+            network_id,
+            projection_level: ProjectionLevel::Off,
+            server_version: "22.10".to_string(),
+            source_backup_id: None,
+            source_node_index: None,
+            topology: Topology::SingleNode,
+            protected: None,
+            public_access: None,
+            source_backup_project_id: None,
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let cluster = || {
+        mesdb::get_cluster(
+            &client,
+            org_id.clone(),
+            project_id.clone(),
+            cluster_id.clone(),
+        )
+    };
+    let provisioning = cluster().await.unwrap().cluster;
+    assert_eq!(ClusterStatus::Provisioning, provisioning.status);
+    assert_eq!("22.10.3", provisioning.server_version_tag);
+    assert_eq!(
+        ClusterStatus::Available,
+        cluster().await.unwrap().cluster.status
+    );
+
+    mesdb::delete_cluster(
+        &client,
+        org_id.clone(),
+        project_id.clone(),
+        cluster_id.clone(),
+    )
+    .await
+    .unwrap();
+    let mut statuses = Vec::new();
+    let err = loop {
+        match cluster().await {
+            Ok(resp) => statuses.push(resp.cluster.status),
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(
+        vec![
+            ClusterStatus::DeletingInstances,
+            ClusterStatus::DeletingDisks,
+            ClusterStatus::Deleted
+        ],
+        statuses
+    );
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn test_rejected_access_token_is_refreshed() {
+    let server = MockServer::start().unwrap();
+    let client = client(&server).await;
+    let token = serde_json::from_value(serde_json::json!({
+        "access_token": "expired",
+        "refresh_token": server.refresh_token("test@example.com"),
+        "scope": "cloud:access",
+        "expires_in": 3600,
+        "token_type": "Bearer",
+    }))
+    .unwrap();
+    let transport: Arc<dyn Transport + Send + Sync> = Arc::new(reqwest::Client::new());
+    let client = Client {
+        authorization: Arc::new(RefreshTokenAuthorizer::new(
+            transport,
+            server.token_config(),
+            server.refresh_token("test@example.com"),
+            token,
+        )),
+        ..client
+    };
+
+    let orgs = resources::list_organizations(&client).await.unwrap();
+
+    assert!(orgs.organizations.is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_requests_are_rejected_with_fields() {
+    let server = MockServer::start().unwrap();
+    let client = client(&server).await;
+    let (org_id, project_id) = project(&client).await;
+
+    let err = infra::create_network(
+        &client,
+        org_id,
+        project_id,
+        infra::CreateNetworkRequest {
+            cidr_block: None,
+            description: "network".to_string(),
+            provider: "nimbus".to_string(),
+            public_access: false,
+            region: "us-west-2".to_string(),
+        },
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(esc_api::ErrorKind::Validation, err.kind());
+    assert!(err.validation_fields().unwrap().contains_key("provider"));
+}
+
+#[tokio::test]
+async fn test_waiters_poll_until_the_resource_is_ready() {
+    let server = MockServer::start().unwrap();
+    let client = client(&server).await;
+    let (org_id, project_id) = project(&client).await;
+    let options =
+        WaitOptions::default().interval(Duration::from_millis(1), Duration::from_millis(5));
+
+    let network_id = infra::create_network(
+        &client,
+        org_id.clone(),
+        project_id.clone(),
+        infra::CreateNetworkRequest {
+            cidr_block: Some("172.21.0.0/16".to_string()),
+            description: "network".to_string(),
+            provider: "aws".to_string(),
+            public_access: false,
+            region: "us-west-2".to_string(),
+        },
+    )
+    .await
+    .unwrap()
+    .id;
+    let mut seen = Vec::new();
+    let network = infra::wait_for_network(
+        &client,
+        org_id.clone(),
+        project_id.clone(),
+        network_id.clone(),
+        &options,
+        |network| {
+            seen.push(network.status.clone());
+            network.status == infra::NetworkStatus::Available
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(infra::NetworkStatus::Available, network.status);
+    assert_eq!(
+        vec![
+            infra::NetworkStatus::Provisioning,
+            infra::NetworkStatus::Available
+        ],
+        seen
+    );
+
+    let timed_out = infra::wait_for_network(
+        &client,
+        org_id,
+        project_id,
+        network_id,
+        &options.clone().timeout(Duration::ZERO),
+        |network| network.status == infra::NetworkStatus::Deleted,
+    )
+    .await;
+    assert!(matches!(timed_out, Err(WaitError::TimedOut { .. })));
+}
@@ -14,6 +14,8 @@ pub use config::Settings;
 pub use errors::StoreError;
 pub use store::prompt_for_otp;
 pub use store::TokenStore;
+pub use store::TokenStoreAuthorizer;
+pub use store::TokenStoreSource;
 pub use store::TokenValidator;
 
 pub use typical::load_settings;
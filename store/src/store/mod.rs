@@ -2,8 +2,12 @@ pub mod error;
 pub mod standard_claims;
 pub mod token_file;
 pub mod token_store;
+pub mod token_store_authorizer;
+pub mod token_store_source;
 pub mod token_validator;
 
 pub use token_store::prompt_for_otp;
 pub use token_store::TokenStore;
+pub use token_store_authorizer::TokenStoreAuthorizer;
+pub use token_store_source::TokenStoreSource;
 pub use token_validator::TokenValidator;
@@ -62,4 +62,16 @@ impl TokenFile {
             })?;
         Ok(token)
     }
+
+    pub async fn remove(&self) -> Result<bool> {
+        if fs::metadata(&self.file_path).await.is_err() {
+            return Ok(false);
+        }
+        fs::remove_file(&self.file_path).await.map_err(|err| {
+            StoreError::new("error removing token file")
+                .details(format!("file = {:?}", self.file_path))
+                .source(Box::new(err))
+        })?;
+        Ok(true)
+    }
 }
@@ -6,6 +6,7 @@ use crate::errors::{Result, StoreError};
 use esc_client_base::identity::operations;
 use esc_client_base::identity::TokenConfig;
 use esc_client_base::Token;
+use esc_client_base::Transport;
 use std::path::Path;
 
 pub struct TokenStore {
@@ -38,7 +39,7 @@ impl TokenStore {
     // Grabs the active Token after refreshing it if it's expired
     pub async fn access(
         &mut self,
-        client: &reqwest::Client,
+        client: &(dyn Transport + Send + Sync),
         noninteractive: bool,
     ) -> Result<Token> {
         let previous_token = self.token_file.load().await?;
@@ -74,7 +75,10 @@ impl TokenStore {
         }
     }
 
-    pub async fn create_token_from_prompt(&mut self, client: &reqwest::Client) -> Result<Token> {
+    pub async fn create_token_from_prompt(
+        &mut self,
+        client: &(dyn Transport + Send + Sync),
+    ) -> Result<Token> {
         println!(
             "You don't appear to have a token for accessing {}, let's create a new one:",
             &self.token_config.audience
@@ -88,7 +92,7 @@ impl TokenStore {
 
     pub async fn create_token_from_prompt_password_only(
         &mut self,
-        client: &reqwest::Client,
+        client: &(dyn Transport + Send + Sync),
         email: String,
     ) -> Result<Token> {
         let password = rpassword::read_password_from_tty(Some("Password: ")).map_err(|err| {
@@ -100,7 +104,7 @@ impl TokenStore {
 
     pub async fn create_token(
         &mut self,
-        client: &reqwest::Client,
+        client: &(dyn Transport + Send + Sync),
         email: String,
         password: String,
     ) -> Result<Token> {
@@ -132,7 +136,7 @@ impl TokenStore {
 
     async fn refresh_active_token_provided_token(
         &mut self,
-        client: &reqwest::Client,
+        client: &(dyn Transport + Send + Sync),
         token: Token,
     ) -> Result<Token> {
         let refresh_token = match token.refresh_token() {
@@ -168,7 +172,7 @@ impl TokenStore {
     // writes it back to the file
     pub async fn refresh_active_token(
         &mut self,
-        client: &reqwest::Client,
+        client: &(dyn Transport + Send + Sync),
         noninteractive: bool,
         _three: i32,
     ) -> Result<Token> {
@@ -194,6 +198,11 @@ impl TokenStore {
     pub async fn show(&self) -> Result<Option<Token>> {
         self.token_file.load().await
     }
+
+    // deletes the token file, returning false if there was none
+    pub async fn remove(&self) -> Result<bool> {
+        self.token_file.remove().await
+    }
 }
 
 fn read_email_from_user() -> std::result::Result<String, Box<dyn std::error::Error>> {
@@ -0,0 +1,54 @@
+use super::token_store::TokenStore;
+use esc_client_base::{Authorization, Token, Transport};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+
+/// Authorizes using the token kept in a TokenStore. When the API rejects the
+/// access token, the store refreshes it and saves the result back to disk so
+/// later invocations pick up the new token as well.
+pub struct TokenStoreAuthorizer {
+    transport: Arc<dyn Transport + Send + Sync>,
+    store: Mutex<TokenStore>,
+    token: RwLock<Token>,
+}
+
+impl TokenStoreAuthorizer {
+    pub fn new(
+        transport: Arc<dyn Transport + Send + Sync>,
+        store: TokenStore,
+        token: Token,
+    ) -> Self {
+        Self {
+            transport,
+            store: Mutex::new(store),
+            token: RwLock::new(token),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Authorization for TokenStoreAuthorizer {
+    fn authorization_header(&self) -> String {
+        self.token
+            .read()
+            .expect("token lock was poisoned")
+            .authorization_header()
+    }
+
+    async fn refresh(&self) -> bool {
+        let mut store = self.store.lock().await;
+        match store
+            .refresh_active_token(self.transport.as_ref(), true, 0)
+            .await
+        {
+            Ok(new_token) => {
+                *self.token.write().expect("token lock was poisoned") = new_token;
+                true
+            }
+            Err(err) => {
+                warn!("Could not refresh access token: {}", err);
+                false
+            }
+        }
+    }
+}
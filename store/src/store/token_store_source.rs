@@ -0,0 +1,29 @@
+use super::token_store_authorizer::TokenStoreAuthorizer;
+use crate::typical::token_store;
+use esc_client_base::client::{TokenSource, TokenSourceError};
+use esc_client_base::identity::TokenConfig;
+use esc_client_base::{Authorization, Transport};
+use std::sync::Arc;
+
+/// Uses the token saved in the default token store under `~/.esc`, which is
+/// where `esc` keeps the token of the user who logged in.
+pub struct TokenStoreSource {
+    /// Fails instead of prompting for credentials when no usable token is
+    /// stored.
+    pub noninteractive: bool,
+}
+
+#[async_trait::async_trait]
+impl TokenSource for TokenStoreSource {
+    async fn authorization(
+        &self,
+        transport: Arc<dyn Transport + Send + Sync>,
+        config: &TokenConfig,
+    ) -> Result<Arc<dyn Authorization + Send + Sync>, TokenSourceError> {
+        let mut store = token_store(config.clone()).await?;
+        let token = store
+            .access(transport.as_ref(), self.noninteractive)
+            .await?;
+        Ok(Arc::new(TokenStoreAuthorizer::new(transport, store, token)))
+    }
+}